@@ -0,0 +1,63 @@
+#!/usr/bin/env python3
+"""Adds the CLDR annotation keywords to the emoji in emoji.tsv.
+
+Usage: annotate-emoji.py CLDR_DIR
+
+CLDR_DIR is a checkout or release of unicode-org/cldr; the keywords come
+from common/annotations/en.xml and, for sequences such as skin tones,
+flags and keycaps, common/annotationsDerived/en.xml. They are put ahead of
+the keywords already listed; symbols without an annotation keep theirs.
+"""
+
+import sys
+import xml.etree.ElementTree as ET
+from pathlib import Path
+
+DATA = Path(__file__).with_name("emoji.tsv")
+SOURCES = ["common/annotations/en.xml", "common/annotationsDerived/en.xml"]
+NOTE = "# Keywords also come from the CLDR English annotations."
+
+
+def annotations(cldr):
+    """Maps each annotated character, without variation selectors, to its
+    keywords."""
+    keywords = {}
+    for source in SOURCES:
+        root = ET.parse(cldr / source).getroot()
+        for annotation in root.iter("annotation"):
+            if annotation.get("type") == "tts" or not annotation.text:
+                continue
+            words = [w.strip() for w in annotation.text.split("|")]
+            keywords[annotation.get("cp").replace("\ufe0f", "")] = words
+    return keywords
+
+
+def main():
+    if len(sys.argv) != 2:
+        sys.exit(__doc__.splitlines()[2])
+    cldr = Path(sys.argv[1])
+    keywords = annotations(cldr)
+
+    header, rows = [], []
+    for line in DATA.read_text(encoding="utf-8").splitlines():
+        # Not the keycap "#️⃣".
+        comment = line == "#" or line.startswith("# ")
+        (header if comment else rows).append(line)
+
+    annotated = []
+    for row in rows:
+        character, name, listed, *rest = row.split("\t")
+        words = keywords.get(character.replace("\ufe0f", ""), [])
+        merged = []
+        for word in words + listed.split("|"):
+            if word and word != name and word not in merged:
+                merged.append(word)
+        annotated.append("\t".join([character, name, "|".join(merged), *rest]))
+
+    if NOTE not in header:
+        header.append(NOTE)
+    DATA.write_text("\n".join(header + annotated) + "\n", encoding="utf-8")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,31 @@
+/// Startup notification data obtained by the UI for the next launch.
+///
+/// Opaque to callers: the UI builds it from whatever its windowing system
+/// hands out, and launchers only forward it to the child process.
+#[derive(Clone, Debug, Default)]
+pub struct ActivationContext {
+    token: Option<String>,
+}
+
+impl ActivationContext {
+    /// Wraps an `xdg_activation_v1` token.
+    pub fn from_token(token: String) -> Self {
+        Self { token: Some(token) }
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Environment variables telling the launched application how to claim
+    /// focus. Toolkits read either name depending on their age.
+    pub fn env(&self) -> Vec<(&'static str, &str)> {
+        match &self.token {
+            Some(token) => vec![
+                ("XDG_ACTIVATION_TOKEN", token.as_str()),
+                ("DESKTOP_STARTUP_ID", token.as_str()),
+            ],
+            None => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use zbus::{blocking::Connection, zvariant::Value};
+
+use crate::ActivationContext;
+
+const APPLICATION_INTERFACE: &str = "org.freedesktop.Application";
+
+/// Starts a `DBusActivatable` application through `org.freedesktop.Application`
+/// on the session bus: `Activate` without `uris`, `Open` with them.
+pub(super) fn activate(
+    desktop_id: &str,
+    uris: &[String],
+    activation: &ActivationContext,
+) -> Result<()> {
+    let connection = Connection::session().context("Failed to connect to the session bus")?;
+
+    let mut platform_data: HashMap<&str, Value> = HashMap::new();
+    if let Some(token) = activation.token() {
+        platform_data.insert("activation-token", token.into());
+        platform_data.insert("desktop-startup-id", token.into());
+    }
+
+    let path = object_path(desktop_id);
+    let reply = if uris.is_empty() {
+        connection.call_method(
+            Some(desktop_id),
+            path.as_str(),
+            Some(APPLICATION_INTERFACE),
+            "Activate",
+            &(platform_data,),
+        )
+    } else {
+        connection.call_method(
+            Some(desktop_id),
+            path.as_str(),
+            Some(APPLICATION_INTERFACE),
+            "Open",
+            &(uris, platform_data),
+        )
+    };
+    reply.with_context(|| format!("Failed to activate {desktop_id} over D-Bus"))?;
+
+    Ok(())
+}
+
+/// The object path the desktop entry spec derives from a desktop id, e.g.
+/// `org.gnome.Nautilus` becomes `/org/gnome/Nautilus`.
+fn object_path(desktop_id: &str) -> String {
+    let mut path = String::from("/");
+    path.extend(desktop_id.chars().map(|c| match c {
+        '.' => '/',
+        '-' => '_',
+        c => c,
+    }));
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use super::object_path;
+
+    #[test]
+    fn derives_object_path_from_desktop_id() {
+        assert_eq!(object_path("org.gnome.Nautilus"), "/org/gnome/Nautilus");
+        assert_eq!(object_path("org.example.my-app"), "/org/example/my_app");
+    }
+}
@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use crate::common::percent_decode;
+
+/// Values substituted for the field codes that don't come from the files
+/// being opened.
+pub(super) struct Fields<'a> {
+    pub name: &'a str,
+    pub icon: Option<&'a str>,
+    pub desktop_file: &'a Path,
+}
+
+/// Splits an `Exec` value into arguments, undoing the quoting rules of the
+/// desktop entry spec. Field codes are kept for [`expand`].
+pub(super) fn parse(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if quoted => match chars.next() {
+                Some(escaped @ ('"' | '`' | '$' | '\\')) => current.push(escaped),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ' ' | '\t' if !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
+
+/// Substitutes the field codes in `args`. `%f` and `%u` take the first of
+/// `targets`, `%F` and `%U` all of them; deprecated codes are dropped.
+pub(super) fn expand(args: &[String], targets: &[String], fields: &Fields) -> Vec<String> {
+    let mut expanded = Vec::with_capacity(args.len() + targets.len());
+
+    for arg in args {
+        match arg.as_str() {
+            "%f" => expanded.extend(targets.first().map(|t| file_arg(t))),
+            "%F" => expanded.extend(targets.iter().map(|t| file_arg(t))),
+            "%u" => expanded.extend(targets.first().cloned()),
+            "%U" => expanded.extend(targets.iter().cloned()),
+            "%i" => {
+                if let Some(icon) = fields.icon {
+                    expanded.extend(["--icon".to_string(), icon.to_string()]);
+                }
+            }
+            _ => {
+                let mut value = String::with_capacity(arg.len());
+                let mut chars = arg.chars();
+                while let Some(c) = chars.next() {
+                    if c != '%' {
+                        value.push(c);
+                        continue;
+                    }
+                    match chars.next() {
+                        Some('%') => value.push('%'),
+                        Some('c') => value.push_str(fields.name),
+                        Some('k') => value.push_str(&fields.desktop_file.to_string_lossy()),
+                        Some('f') => value.extend(targets.first().map(|t| file_arg(t))),
+                        Some('u') => value.extend(targets.first().map(String::as_str)),
+                        _ => {}
+                    }
+                }
+                if !value.is_empty() {
+                    expanded.push(value);
+                }
+            }
+        }
+    }
+    expanded
+}
+
+/// `%f` wants a local path, so `file://` URLs are turned back into one.
+fn file_arg(target: &str) -> String {
+    match target.strip_prefix("file://") {
+        Some(path) => percent_decode(path),
+        None => target.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use super::{Fields, expand, parse};
+
+    #[test]
+    fn parses_quoted_arguments() {
+        assert_eq!(
+            parse(r#"flatpak run "--command=my app" org.App %U"#),
+            ["flatpak", "run", "--command=my app", "org.App", "%U"]
+        );
+        assert_eq!(
+            parse(r#"sh -c "echo \"\$HOME\" \\ done""#),
+            ["sh", "-c", r#"echo "$HOME" \ done"#]
+        );
+        assert_eq!(parse(r#"app "" last"#), ["app", "", "last"]);
+    }
+
+    #[test]
+    fn expands_field_codes() {
+        let fields = Fields {
+            name: "Viewer",
+            icon: Some("viewer"),
+            desktop_file: Path::new("/apps/viewer.desktop"),
+        };
+        let args = parse("viewer %i --class=%c %f --from %k 100%% %d");
+
+        let targets = [
+            "file:///tmp/a%20b.png".to_string(),
+            "/tmp/c.png".to_string(),
+        ];
+        assert_eq!(
+            expand(&args, &targets, &fields),
+            [
+                "viewer",
+                "--icon",
+                "viewer",
+                "--class=Viewer",
+                "/tmp/a b.png",
+                "--from",
+                "/apps/viewer.desktop",
+                "100%",
+            ]
+        );
+
+        let args = parse("viewer %U");
+        assert_eq!(expand(&args, &[], &fields), ["viewer"]);
+        assert_eq!(
+            expand(&args, &targets, &fields),
+            ["viewer", "file:///tmp/a%20b.png", "/tmp/c.png"]
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::common::{IconSize, xdg};
+
+const FALLBACK_THEME: &str = "hicolor";
+const THEME_EXTENSIONS: [&str; 3] = ["svg", "png", "xpm"];
+const FALLBACK_EXTENSIONS: [&str; 5] = ["", ".svg", ".png", ".xpm", ".ico"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+#[derive(Debug, Clone)]
+struct ThemeDirectory {
+    name: String,
+    size: u32,
+    scale: u32,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+    kind: DirectoryType,
+}
+
+impl ThemeDirectory {
+    fn matches_size(&self, icon_size: IconSize) -> bool {
+        if self.scale != icon_size.scale {
+            return false;
+        }
+
+        match self.kind {
+            DirectoryType::Fixed => self.size == icon_size.size,
+            DirectoryType::Scalable => (self.min_size..=self.max_size).contains(&icon_size.size),
+            DirectoryType::Threshold => {
+                let min = self.size.saturating_sub(self.threshold);
+                (min..=self.size + self.threshold).contains(&icon_size.size)
+            }
+        }
+    }
+
+    fn size_distance(&self, icon_size: IconSize) -> u32 {
+        let wanted = icon_size.size * icon_size.scale;
+
+        let (min, max) = match self.kind {
+            DirectoryType::Fixed => (self.size, self.size),
+            DirectoryType::Scalable => (self.min_size, self.max_size),
+            DirectoryType::Threshold => (
+                self.size.saturating_sub(self.threshold),
+                self.size + self.threshold,
+            ),
+        };
+
+        let (min, max) = (min * self.scale, max * self.scale);
+        if wanted < min {
+            min - wanted
+        } else {
+            wanted.saturating_sub(max)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Theme {
+    /// Every `<base dir>/<theme name>` that exists on disk.
+    roots: Vec<PathBuf>,
+    parents: Vec<String>,
+    directories: Vec<ThemeDirectory>,
+}
+
+impl Theme {
+    fn load(name: &str, base_dirs: &[PathBuf]) -> Option<Self> {
+        let roots: Vec<PathBuf> = base_dirs
+            .iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+
+        let index = roots
+            .iter()
+            .find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+        let index = parse_ini(&index);
+        let theme_section = index.get("Icon Theme")?;
+
+        let parents = theme_section
+            .get("Inherits")
+            .map(|v| split_list(v))
+            .unwrap_or_default();
+
+        let mut directory_names = theme_section
+            .get("Directories")
+            .map(|v| split_list(v))
+            .unwrap_or_default();
+        if let Some(scaled) = theme_section.get("ScaledDirectories") {
+            directory_names.extend(split_list(scaled));
+        }
+
+        let directories = directory_names
+            .into_iter()
+            .filter_map(|dir_name| {
+                let section = index.get(&dir_name)?;
+                let get = |key: &str| section.get(key).and_then(|v| v.parse::<u32>().ok());
+
+                let size = get("Size")?;
+                let kind = match section.get("Type").map(String::as_str) {
+                    Some("Fixed") => DirectoryType::Fixed,
+                    Some("Scalable") => DirectoryType::Scalable,
+                    _ => DirectoryType::Threshold,
+                };
+
+                Some(ThemeDirectory {
+                    name: dir_name,
+                    size,
+                    scale: get("Scale").unwrap_or(1),
+                    min_size: get("MinSize").unwrap_or(size),
+                    max_size: get("MaxSize").unwrap_or(size),
+                    threshold: get("Threshold").unwrap_or(2),
+                    kind,
+                })
+            })
+            // Drop directories that are not installed in any root so lookups
+            // don't stat thousands of missing paths.
+            .filter(|dir| roots.iter().any(|root| root.join(&dir.name).is_dir()))
+            .collect();
+
+        Some(Theme {
+            roots,
+            parents,
+            directories,
+        })
+    }
+
+    fn lookup(&self, icon_name: &str, icon_size: IconSize) -> Option<PathBuf> {
+        let mut exact: Option<PathBuf> = None;
+
+        for dir in self
+            .directories
+            .iter()
+            .filter(|d| d.matches_size(icon_size))
+        {
+            for root in &self.roots {
+                for ext in THEME_EXTENSIONS {
+                    let candidate = root.join(&dir.name).join(format!("{icon_name}.{ext}"));
+                    if !candidate.is_file() {
+                        continue;
+                    }
+                    if ext == "svg" {
+                        return Some(candidate);
+                    }
+                    exact.get_or_insert(candidate);
+                }
+            }
+        }
+
+        if exact.is_some() {
+            return exact;
+        }
+
+        let mut closest: Option<(u32, bool, PathBuf)> = None;
+
+        for dir in &self.directories {
+            let distance = dir.size_distance(icon_size);
+            for root in &self.roots {
+                for ext in THEME_EXTENSIONS {
+                    let candidate = root.join(&dir.name).join(format!("{icon_name}.{ext}"));
+                    if !candidate.is_file() {
+                        continue;
+                    }
+
+                    // Prefer the closest size, and SVG over raster on ties.
+                    let is_svg = ext == "svg";
+                    let better = match &closest {
+                        None => true,
+                        Some((best, best_svg, _)) => {
+                            distance < *best || (distance == *best && is_svg && !best_svg)
+                        }
+                    };
+                    if better {
+                        closest = Some((distance, is_svg, candidate));
+                    }
+                }
+            }
+        }
+
+        closest.map(|(_, _, path)| path)
+    }
+}
+
+/// Resolves freedesktop icon names against an icon theme and its parents,
+/// following the XDG icon theme specification.
+#[derive(Debug, Clone)]
+pub struct IconLookup {
+    base_dirs: Vec<PathBuf>,
+    /// The selected theme followed by its inherited themes, depth first,
+    /// with `hicolor` always last.
+    themes: Vec<Theme>,
+}
+
+impl IconLookup {
+    pub fn new(theme_name: &str, base_dirs: Vec<PathBuf>) -> Self {
+        let mut themes = Vec::new();
+        let mut seen = Vec::new();
+        Self::load_theme_chain(theme_name, &base_dirs, &mut themes, &mut seen);
+        Self::load_theme_chain(FALLBACK_THEME, &base_dirs, &mut themes, &mut seen);
+
+        IconLookup { base_dirs, themes }
+    }
+
+    /// Builds a lookup for the icon theme configured in the user's desktop
+    /// environment, searching the standard XDG icon directories.
+    pub fn from_env() -> Self {
+        let theme = configured_theme().unwrap_or_else(|| FALLBACK_THEME.to_string());
+        Self::new(&theme, default_base_dirs())
+    }
+
+    fn load_theme_chain(
+        name: &str,
+        base_dirs: &[PathBuf],
+        themes: &mut Vec<Theme>,
+        seen: &mut Vec<String>,
+    ) {
+        if seen.iter().any(|s| s == name) {
+            return;
+        }
+        seen.push(name.to_string());
+
+        let Some(theme) = Theme::load(name, base_dirs) else {
+            return;
+        };
+        let parents = theme.parents.clone();
+        themes.push(theme);
+
+        for parent in parents {
+            Self::load_theme_chain(&parent, base_dirs, themes, seen);
+        }
+    }
+
+    pub fn find(&self, icon_name: &str, icon_size: IconSize) -> Option<PathBuf> {
+        let path = Path::new(icon_name);
+
+        if path.is_absolute() {
+            if path.is_file() {
+                return Some(path.to_path_buf());
+            }
+            let stem = path.file_stem()?.to_str()?;
+            return self.find(stem, icon_size);
+        }
+
+        // Some entries spell out the extension even though the spec says not to.
+        let icon_name = match path.extension().and_then(|e| e.to_str()) {
+            Some("png" | "svg" | "xpm" | "ico") => path.file_stem()?.to_str()?,
+            _ => icon_name,
+        };
+
+        self.themes
+            .iter()
+            .find_map(|theme| theme.lookup(icon_name, icon_size))
+            .or_else(|| self.lookup_fallback(icon_name))
+    }
+
+    fn lookup_fallback(&self, icon_name: &str) -> Option<PathBuf> {
+        self.base_dirs.iter().find_map(|dir| {
+            FALLBACK_EXTENSIONS
+                .iter()
+                .map(|ext| dir.join(format!("{icon_name}{ext}")))
+                .find(|candidate| candidate.is_file())
+        })
+    }
+}
+
+fn default_base_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if let Some(home) = env::var_os("HOME").map(PathBuf::from) {
+        dirs.push(home.join(".icons"));
+    }
+
+    dirs.extend(xdg::data_dirs().into_iter().map(|dir| dir.join("icons")));
+
+    dirs.push(PathBuf::from("/usr/share/pixmaps"));
+    dirs
+}
+
+/// Reads the icon theme selected in the user's GTK or KDE settings.
+fn configured_theme() -> Option<String> {
+    let desktop = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+    let config_home = xdg::config_home()?;
+
+    let kde = || read_ini_value(&config_home.join("kdeglobals"), "Icons", "Theme");
+    let gtk = || {
+        ["gtk-4.0", "gtk-3.0"].iter().find_map(|dir| {
+            read_ini_value(
+                &config_home.join(dir).join("settings.ini"),
+                "Settings",
+                "gtk-icon-theme-name",
+            )
+        })
+    };
+    let gsettings = || {
+        let output = Command::new("gsettings")
+            .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+            .output()
+            .ok()?;
+        let value = String::from_utf8(output.stdout).ok()?;
+        let value = value.trim().trim_matches('\'');
+        (!value.is_empty()).then(|| value.to_string())
+    };
+
+    if desktop.split(':').any(|d| d == "KDE") {
+        kde().or_else(gtk)
+    } else if desktop.split(':').any(|d| d == "GNOME") {
+        gsettings().or_else(gtk)
+    } else {
+        gtk().or_else(kde)
+    }
+}
+
+fn read_ini_value(path: &Path, section: &str, key: &str) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    parse_ini(&content)
+        .get(section)?
+        .get(key)
+        .filter(|v| !v.is_empty())
+        .cloned()
+}
+
+fn parse_ini(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = String::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            current = name.to_string();
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            sections
+                .entry(current.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    sections
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::IconLookup;
+    use crate::common::IconSize;
+
+    fn fixture_lookup() -> IconLookup {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/icons");
+        IconLookup::new("Fixture", vec![root.join("share"), root.join("pixmaps")])
+    }
+
+    fn file_name(path: Option<PathBuf>) -> String {
+        let path = path.expect("icon should resolve");
+        let parts: Vec<_> = path.iter().rev().take(4).collect();
+        parts
+            .into_iter()
+            .rev()
+            .map(|p| p.to_string_lossy().into_owned())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[test]
+    fn picks_exact_size() {
+        let lookup = fixture_lookup();
+        assert_eq!(
+            file_name(lookup.find("sized", IconSize::new(16, 1))),
+            "Fixture/16x16/apps/sized.png"
+        );
+        assert_eq!(
+            file_name(lookup.find("sized", IconSize::new(48, 1))),
+            "Fixture/48x48/apps/sized.png"
+        );
+    }
+
+    #[test]
+    fn picks_closest_size_when_no_exact_match() {
+        let lookup = fixture_lookup();
+        assert_eq!(
+            file_name(lookup.find("sized", IconSize::new(40, 1))),
+            "Fixture/48x48/apps/sized.png"
+        );
+    }
+
+    #[test]
+    fn honours_scale_factor() {
+        let lookup = fixture_lookup();
+        assert_eq!(
+            file_name(lookup.find("sized", IconSize::new(32, 2))),
+            "Fixture/32x32@2/apps/sized.png"
+        );
+    }
+
+    #[test]
+    fn prefers_svg() {
+        let lookup = fixture_lookup();
+        assert_eq!(
+            file_name(lookup.find("vector", IconSize::new(48, 1))),
+            "Fixture/scalable/apps/vector.svg"
+        );
+    }
+
+    #[test]
+    fn follows_inheritance_then_hicolor_then_pixmaps() {
+        let lookup = fixture_lookup();
+        assert_eq!(
+            file_name(lookup.find("inherited", IconSize::new(32, 1))),
+            "FixtureParent/32x32/apps/inherited.png"
+        );
+        assert_eq!(
+            file_name(lookup.find("hicolor-only", IconSize::new(32, 1))),
+            "hicolor/48x48/apps/hicolor-only.png"
+        );
+        assert!(
+            lookup
+                .find("legacy", IconSize::new(32, 1))
+                .is_some_and(|p| p.ends_with("pixmaps/legacy.xpm"))
+        );
+        assert!(lookup.find("missing", IconSize::new(32, 1)).is_none());
+    }
+
+    #[test]
+    fn strips_extension_from_icon_name() {
+        let lookup = fixture_lookup();
+        assert_eq!(
+            file_name(lookup.find("sized.png", IconSize::new(16, 1))),
+            "Fixture/16x16/apps/sized.png"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    process::{Command, Stdio},
+    time::SystemTime,
+};
+
+use which::which;
+
+use crate::LaunchStrategy;
+
+/// Builds the command running `argv`, wrapped according to `strategy`.
+/// Falls back to spawning directly when `systemd-run` isn't installed or
+/// there is no user service manager to create the scope in.
+pub(super) fn launch_command(
+    argv: &[String],
+    desktop_id: &str,
+    strategy: LaunchStrategy,
+) -> Command {
+    let argv = match strategy {
+        LaunchStrategy::Systemd if which("systemd-run").is_err() => {
+            tracing::debug!("systemd-run not found, spawning directly");
+            argv.to_vec()
+        }
+        LaunchStrategy::Systemd if !user_manager_running() => {
+            tracing::debug!("No systemd user manager, spawning directly");
+            argv.to_vec()
+        }
+        LaunchStrategy::Systemd => {
+            let random = format!("{:x}", RandomState::new().hash_one(SystemTime::now()));
+            systemd_scope_argv(desktop_id, &random, argv)
+        }
+        LaunchStrategy::Spawn => argv.to_vec(),
+    };
+
+    let mut cmd = Command::new(&argv[0]);
+    cmd.args(&argv[1..]);
+    cmd
+}
+
+/// Whether the user's service manager is up to create scopes in. Without
+/// it, in containers or sessions not started by systemd, `systemd-run
+/// --user` fails and the application wouldn't start at all.
+fn user_manager_running() -> bool {
+    Command::new("systemctl")
+        .args(["--user", "is-system-running"])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .is_ok_and(|output| accepts_scopes(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Whether a manager in `state`, as printed by `systemctl
+/// is-system-running`, accepts new units. A degraded manager has failed
+/// units but still works.
+fn accepts_scopes(state: &str) -> bool {
+    matches!(
+        state.trim(),
+        "initializing" | "starting" | "running" | "degraded"
+    )
+}
+
+/// Runs `argv` in a transient user scope named
+/// `app-<desktop id>-<random>.scope`, as the systemd XDG application
+/// naming convention asks for.
+fn systemd_scope_argv(desktop_id: &str, random: &str, argv: &[String]) -> Vec<String> {
+    let unit = format!("app-{}-{random}.scope", escape_unit_component(desktop_id));
+
+    ["systemd-run", "--user", "--scope", "--quiet"]
+        .into_iter()
+        .map(str::to_string)
+        .chain([format!("--unit={unit}"), "--".to_string()])
+        .chain(argv.iter().cloned())
+        .collect()
+}
+
+/// Escapes a string for use inside a unit name like `systemd-escape` does,
+/// so dashes in the desktop id don't read as separators.
+fn escape_unit_component(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for (i, byte) in value.bytes().enumerate() {
+        match byte {
+            b'.' if i == 0 => escaped.push_str("\\x2e"),
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b':' | b'_' | b'.' => {
+                escaped.push(byte as char)
+            }
+            _ => escaped.push_str(&format!("\\x{byte:02x}")),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{accepts_scopes, systemd_scope_argv};
+
+    fn argv(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn wraps_command_in_scope() {
+        assert_eq!(
+            systemd_scope_argv(
+                "org.gnome.Nautilus",
+                "1a2b",
+                &argv(&["nautilus", "--new-window"])
+            ),
+            argv(&[
+                "systemd-run",
+                "--user",
+                "--scope",
+                "--quiet",
+                "--unit=app-org.gnome.Nautilus-1a2b.scope",
+                "--",
+                "nautilus",
+                "--new-window",
+            ])
+        );
+    }
+
+    #[test]
+    fn escapes_desktop_id_in_unit_name() {
+        let wrapped = systemd_scope_argv("my-app é", "ff", &argv(&["my-app"]));
+        assert_eq!(wrapped[4], "--unit=app-my\\x2dapp\\x20\\xc3\\xa9-ff.scope");
+        assert_eq!(wrapped[6..], argv(&["my-app"]));
+    }
+
+    #[test]
+    fn only_uses_working_user_managers() {
+        assert!(accepts_scopes("running\n"));
+        assert!(accepts_scopes("degraded\n"));
+        assert!(!accepts_scopes("offline\n"));
+        assert!(!accepts_scopes("stopping\n"));
+        assert!(!accepts_scopes(""));
+    }
+}
@@ -0,0 +1,480 @@
+mod dbus;
+mod exec;
+mod icons;
+mod launch;
+mod terminal;
+
+use std::{
+    os::unix::process::CommandExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::{Arc, OnceLock},
+};
+
+use anyhow::{Context, Result};
+use freedesktop_desktop_entry::{DesktopEntry, desktop_entries, get_languages_from_env};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+use crate::application::{Application, Image};
+use crate::common::{IconSize, decode::load_icon, file_uri};
+use crate::{ActivationContext, Detail, LaunchConfig};
+
+use self::icons::IconLookup;
+use self::launch::launch_command;
+use self::terminal::{terminal, terminal_argv};
+
+#[derive(Debug, Clone)]
+pub struct LinuxApplication {
+    name: String,
+    comment: Option<String>,
+    exec_raw: String,
+    /// The `Exec` arguments with their field codes still in place.
+    exec: Vec<String>,
+    desktop_id: String,
+    desktop_file: PathBuf,
+    working_dir: Option<PathBuf>,
+    categories: Vec<String>,
+    mime_types: Vec<String>,
+    icon_name: Option<String>,
+    icons: Arc<IconLookup>,
+    is_terminal: bool,
+    dbus_activatable: bool,
+}
+
+/// Runs `argv` in a new window of the user's terminal. `hold` keeps the
+/// window open after the command exits.
+pub(crate) fn run_in_terminal(
+    argv: &[String],
+    title: &str,
+    hold: bool,
+    launch: &LaunchConfig,
+) -> Result<()> {
+    let profile = terminal(&launch.terminal).context("No terminal emulator found")?;
+    let argv = terminal_argv(profile, Some(title), None, hold, argv);
+
+    let mut cmd = launch_command(&argv, &profile.exe, launch.strategy);
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+
+    tracing::info!(command = ?cmd, "Launching in terminal");
+    cmd.spawn()
+        .with_context(|| format!("Failed to launch {}", profile.exe))?;
+    Ok(())
+}
+
+/// Resolves the first of `names` found in the icon theme, for entities that
+/// name their icon instead of carrying an image.
+pub(crate) fn themed_icon(names: &[String], icon_size: IconSize) -> Option<Image> {
+    static ICONS: OnceLock<IconLookup> = OnceLock::new();
+    let icons = ICONS.get_or_init(IconLookup::from_env);
+
+    let path = names.iter().find_map(|name| icons.find(name, icon_size))?;
+    load_icon(&path, icon_size)
+        .inspect_err(|e| tracing::warn!("{e:#}"))
+        .ok()
+}
+
+/// Starts `argv` detached from the launcher, the way applications are.
+pub fn spawn(argv: &[String], launch: &LaunchConfig, activation: &ActivationContext) -> Result<()> {
+    let program = argv.first().context("Empty command")?;
+    let name = Path::new(program).file_name().map_or_else(
+        || program.clone(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+
+    let mut cmd = launch_command(argv, &name, launch.strategy);
+    cmd.envs(activation.env())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+
+    tracing::info!(command = ?cmd, "Spawning");
+    cmd.spawn()
+        .with_context(|| format!("Failed to launch {program}"))?;
+    Ok(())
+}
+
+impl LinuxApplication {
+    fn from_desktop_entry(
+        entry: &DesktopEntry,
+        locales: &[String],
+        icons: &Arc<IconLookup>,
+    ) -> Option<Self> {
+        // D-Bus activatable entries may leave out Exec entirely.
+        let dbus_activatable = entry.dbus_activatable();
+        let exec_raw = match entry.exec() {
+            Some(exec) => exec,
+            None if dbus_activatable => "",
+            None => return None,
+        };
+
+        let exec = exec::parse(exec_raw);
+        if exec.is_empty() && !dbus_activatable {
+            return None;
+        }
+
+        let name = entry
+            .name(locales)
+            .map(|cow| cow.into_owned())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        let to_owned = |values: Option<Vec<&str>>| {
+            values
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+
+        Some(LinuxApplication {
+            name,
+            comment: entry.comment(locales).map(|cow| cow.into_owned()),
+            exec_raw: exec_raw.to_string(),
+            exec,
+            desktop_id: entry.id().to_string(),
+            desktop_file: entry.path.clone(),
+            working_dir: entry
+                .desktop_entry("Path")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+            categories: to_owned(entry.categories()),
+            mime_types: to_owned(entry.mime_type()),
+            icon_name: entry.icon().map(str::to_string),
+            icons: icons.clone(),
+            is_terminal: entry.terminal(),
+            dbus_activatable,
+        })
+    }
+}
+
+impl LinuxApplication {
+    /// Every installed application, including the ones hidden from menus
+    /// with `NoDisplay`, which may still open files.
+    pub(crate) fn installed() -> Vec<Self> {
+        Self::scan(true)
+    }
+
+    /// Parses the desktop entry `text` as if it were installed at `path`.
+    #[cfg(test)]
+    pub(crate) fn from_desktop_file(path: &str, text: &str) -> Option<Self> {
+        let entry = DesktopEntry::from_str(path, text, None::<&[&str]>).ok()?;
+        let icons = Arc::new(IconLookup::new("Fixture", Vec::new()));
+        Self::from_desktop_entry(&entry, &[], &icons)
+    }
+
+    fn scan(include_no_display: bool) -> Vec<Self> {
+        let locales = get_languages_from_env();
+        let icons = Arc::new(IconLookup::from_env());
+
+        desktop_entries(&locales)
+            .into_par_iter()
+            .filter_map(|entry| {
+                if entry.hidden() || (entry.no_display() && !include_no_display) {
+                    return None;
+                }
+                LinuxApplication::from_desktop_entry(&entry, &locales, &icons)
+            })
+            .collect()
+    }
+
+    /// The MIME types listed in the desktop entry.
+    pub(crate) fn mime_types(&self) -> &[String] {
+        &self.mime_types
+    }
+
+    /// Starts the application with `targets`, paths or URLs, as arguments.
+    /// Without targets the file field codes are left out. D-Bus activation
+    /// can't pass the launch overrides, so applications with overrides run
+    /// their `Exec` line instead.
+    pub(crate) fn open(
+        &self,
+        targets: &[String],
+        launch: &LaunchConfig,
+        activation: &ActivationContext,
+    ) -> Result<()> {
+        let overridden = launch.overrides.contains_key(&self.desktop_id);
+        if self.dbus_activatable && overridden && self.exec.is_empty() {
+            tracing::warn!(
+                app = %self.name,
+                "The launch overrides don't apply to D-Bus activation and there's no Exec line"
+            );
+        }
+        if self.dbus_activatable && (!overridden || self.exec.is_empty()) {
+            tracing::info!(app = %self.name, "Activating over D-Bus");
+            let uris: Vec<String> = targets
+                .iter()
+                .map(|target| {
+                    if target.starts_with('/') {
+                        file_uri(Path::new(target))
+                    } else {
+                        target.clone()
+                    }
+                })
+                .collect();
+            match dbus::activate(&self.desktop_id, &uris, activation) {
+                Ok(()) => return Ok(()),
+                Err(e) if !self.exec.is_empty() => {
+                    tracing::warn!(app = %self.name, "{e:#}, falling back to Exec");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let Some(mut cmd) = self.exec_command(targets, launch, activation) else {
+            return Ok(());
+        };
+        tracing::info!(app = %self.name, command = ?cmd, "Launching");
+        let _ = cmd
+            .spawn()
+            .with_context(|| format!("Failed to launch {}", self.name))?;
+
+        Ok(())
+    }
+
+    /// The command running the `Exec` line with `targets`, with the launch
+    /// overrides, working directory and activation token applied. `None`
+    /// when the entry has no `Exec`.
+    fn exec_command(
+        &self,
+        targets: &[String],
+        launch: &LaunchConfig,
+        activation: &ActivationContext,
+    ) -> Option<Command> {
+        if self.exec.is_empty() {
+            return None;
+        }
+
+        let fields = exec::Fields {
+            name: &self.name,
+            icon: self.icon_name.as_deref(),
+            desktop_file: &self.desktop_file,
+        };
+        let mut argv = exec::expand(&self.exec, targets, &fields);
+        let overrides = launch.overrides.get(&self.desktop_id);
+        if let Some(overrides) = overrides {
+            argv.extend(overrides.args.iter().cloned());
+        }
+        if self.is_terminal
+            && let Some(profile) = terminal(&launch.terminal)
+        {
+            argv = terminal_argv(
+                profile,
+                Some(&self.name),
+                self.working_dir.as_deref(),
+                false,
+                &argv,
+            );
+        }
+
+        let mut cmd = launch_command(&argv, &self.desktop_id, launch.strategy);
+        if let Some(overrides) = overrides {
+            cmd.envs(&overrides.env);
+        }
+        cmd.envs(activation.env());
+        if let Some(dir) = self.working_dir.as_deref().filter(|dir| dir.is_dir()) {
+            cmd.current_dir(dir);
+        }
+
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .process_group(0);
+        Some(cmd)
+    }
+}
+
+impl Application for LinuxApplication {
+    fn id(&self) -> &str {
+        &self.desktop_id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn alias(&self) -> Option<&str> {
+        None
+    }
+
+    fn description(&self) -> Option<&str> {
+        None
+    }
+
+    fn execute(
+        &self,
+        _arg: Option<String>,
+        launch: &LaunchConfig,
+        activation: &ActivationContext,
+    ) -> Result<()> {
+        self.open(&[], launch, activation)
+    }
+
+    fn icon(&self, icon_size: IconSize) -> Option<Image> {
+        let icon_name = self.icon_name.as_deref()?;
+
+        let Some(path) = self.icons.find(icon_name, icon_size) else {
+            tracing::debug!(app = %self.name, icon = icon_name, "Icon not found");
+            return None;
+        };
+
+        load_icon(&path, icon_size)
+            .inspect_err(|e| tracing::warn!(app = %self.name, "{e:#}"))
+            .ok()
+    }
+
+    fn detail(&self) -> Detail {
+        let mut detail = Detail::default()
+            .with_row("ID", &self.desktop_id)
+            .with_row("Command", &self.exec_raw)
+            .with_row("Desktop file", self.desktop_file.to_string_lossy());
+
+        if let Some(comment) = &self.comment {
+            detail = detail.with_markdown(comment);
+        }
+        if !self.categories.is_empty() {
+            detail = detail.with_row("Categories", self.categories.join(", "));
+        }
+        if !self.mime_types.is_empty() {
+            detail = detail.with_row("MIME types", self.mime_types.join("\n"));
+        }
+        if let Some(dir) = &self.working_dir {
+            detail = detail.with_row("Working directory", dir.to_string_lossy());
+        }
+        if self.is_terminal {
+            detail = detail.with_row("Terminal", "Yes");
+        }
+        if self.dbus_activatable {
+            detail = detail.with_row("D-Bus activatable", "Yes");
+        }
+
+        detail
+    }
+
+    fn lookup_applications() -> Vec<Self>
+    where
+        Self: Sized,
+    {
+        Self::scan(false)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{ffi::OsStr, path::Path};
+
+    use super::LinuxApplication;
+    use crate::application::Application;
+    use crate::{ActivationContext, LaunchConfig, LaunchOverride};
+
+    fn app(text: &str) -> Option<LinuxApplication> {
+        LinuxApplication::from_desktop_file(
+            "/usr/share/applications/org.example.Editor.desktop",
+            text,
+        )
+    }
+
+    #[test]
+    fn details_come_from_the_desktop_entry() {
+        let app = app("[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Editor\n\
+             Comment=Edit *text* files\n\
+             Exec=editor --new-window %F\n\
+             Path=/srv/work\n\
+             Categories=Utility;TextEditor;\n\
+             MimeType=text/plain;text/markdown;\n")
+        .unwrap();
+
+        let detail = app.detail();
+        assert_eq!(detail.markdown.as_deref(), Some("Edit *text* files"));
+        let row = |label: &str| {
+            detail
+                .metadata
+                .iter()
+                .find(|(l, _)| l == label)
+                .map(|(_, value)| value.as_str())
+        };
+        assert_eq!(row("ID"), Some("org.example.Editor"));
+        assert_eq!(row("Command"), Some("editor --new-window %F"));
+        assert_eq!(
+            row("Desktop file"),
+            Some("/usr/share/applications/org.example.Editor.desktop")
+        );
+        assert_eq!(row("Categories"), Some("Utility, TextEditor"));
+        assert_eq!(row("MIME types"), Some("text/plain\ntext/markdown"));
+        assert_eq!(row("Working directory"), Some("/srv/work"));
+        assert_eq!(row("Terminal"), None);
+    }
+
+    #[test]
+    fn builds_command_with_overrides_and_working_directory() {
+        let dir = std::env::temp_dir();
+        let app = app(&format!(
+            "[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Editor\n\
+             Exec=editor --new-window %F\n\
+             Path={}\n",
+            dir.display()
+        ))
+        .unwrap();
+
+        let mut launch = LaunchConfig::default();
+        launch.overrides.insert(
+            "org.example.Editor".to_string(),
+            LaunchOverride {
+                env: [("GDK_BACKEND".to_string(), "wayland".to_string())].into(),
+                args: vec!["--verbose".to_string()],
+            },
+        );
+        let activation = ActivationContext::from_token("token".to_string());
+        let targets = ["/tmp/a.txt".to_string(), "/tmp/b.txt".to_string()];
+
+        let cmd = app.exec_command(&targets, &launch, &activation).unwrap();
+        assert_eq!(cmd.get_program(), "editor");
+        assert_eq!(
+            cmd.get_args().collect::<Vec<_>>(),
+            ["--new-window", "/tmp/a.txt", "/tmp/b.txt", "--verbose"]
+        );
+        assert_eq!(cmd.get_current_dir(), Some(dir.as_path()));
+        let env = |name: &str| {
+            cmd.get_envs()
+                .find(|(key, _)| *key == name)
+                .and_then(|(_, value)| value)
+        };
+        assert_eq!(env("GDK_BACKEND"), Some(OsStr::new("wayland")));
+        assert_eq!(env("XDG_ACTIVATION_TOKEN"), Some(OsStr::new("token")));
+
+        // Other applications don't get the overrides.
+        launch.overrides.clear();
+        let cmd = app
+            .exec_command(&[], &launch, &ActivationContext::default())
+            .unwrap();
+        assert_eq!(cmd.get_args().collect::<Vec<_>>(), ["--new-window"]);
+        assert_eq!(cmd.get_envs().count(), 0);
+    }
+
+    #[test]
+    fn skips_missing_working_directories() {
+        let app = app("[Desktop Entry]\n\
+             Type=Application\n\
+             Name=Editor\n\
+             Exec=editor\n\
+             Path=/nonexistent/directory\n")
+        .unwrap();
+        let cmd = app
+            .exec_command(&[], &LaunchConfig::default(), &ActivationContext::default())
+            .unwrap();
+        assert_eq!(cmd.get_current_dir(), None::<&Path>);
+    }
+
+    #[test]
+    fn get_applications() {
+        let apps = LinuxApplication::lookup_applications();
+        println!("{:?}", apps.len());
+    }
+}
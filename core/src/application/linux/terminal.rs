@@ -0,0 +1,328 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use freedesktop_desktop_entry::DesktopEntry;
+use which::which;
+
+use crate::common::xdg;
+use crate::{TerminalConfig, TerminalProfile};
+
+/// Built-in profiles as (exe, args, exec flag, title flag, working dir flag,
+/// hold flag). The exe doubles as the profile name.
+type BuiltinProfile = (
+    &'static str,
+    &'static [&'static str],
+    &'static str,
+    Option<&'static str>,
+    Option<&'static str>,
+    Option<&'static str>,
+);
+
+const XDG_TERMINAL_EXEC: &str = "xdg-terminal-exec";
+
+#[rustfmt::skip]
+const BUILTIN_PROFILES: &[BuiltinProfile] = &[
+    (XDG_TERMINAL_EXEC, &[], "-e", Some("--title="), Some("--dir="), Some("--hold")),
+    ("ghostty", &[], "-e", Some("--title="), Some("--working-directory="), Some("--wait-after-command")),
+    ("kitty", &[], "", Some("--title"), Some("--directory"), Some("--hold")),
+    ("alacritty", &[], "-e", Some("--title"), Some("--working-directory"), Some("--hold")),
+    ("wezterm", &["start"], "--", None, Some("--cwd"), None),
+    ("foot", &[], "", Some("--title"), Some("--working-directory"), Some("--hold")),
+    ("konsole", &[], "-e", None, Some("--workdir"), Some("--hold")),
+    ("gnome-terminal", &[], "--", Some("--title"), Some("--working-directory"), None),
+    ("kgx", &[], "--", Some("--title"), Some("--working-directory"), None),
+    ("xfce4-terminal", &[], "-x", Some("--title"), Some("--working-directory"), Some("--hold")),
+    ("terminator", &[], "-x", Some("--title"), Some("--working-directory"), None),
+    ("lxterminal", &[], "-e", Some("--title="), Some("--working-directory="), None),
+    ("termite", &[], "-e", Some("--title"), Some("--directory"), Some("--hold")),
+    ("urxvt", &[], "-e", Some("-title"), Some("-cd"), Some("-hold")),
+    ("xterm", &[], "-e", Some("-T"), None, Some("-hold")),
+    ("weston-terminal", &[], "--", None, None, None),
+];
+
+static RESOLVED: OnceLock<Option<TerminalProfile>> = OnceLock::new();
+
+/// The terminal that `Terminal=true` entries run in. Resolved on first use
+/// and kept for the rest of the session.
+pub(super) fn terminal(config: &TerminalConfig) -> Option<&'static TerminalProfile> {
+    RESOLVED
+        .get_or_init(|| {
+            let terminal = resolve(config, &TerminalEnv::from_env(), &|exe| which(exe).is_ok());
+            tracing::debug!(?terminal, "Resolved terminal");
+            terminal
+        })
+        .as_ref()
+}
+
+/// The argv that runs `command` in a new window of `profile`.
+pub(super) fn terminal_argv(
+    profile: &TerminalProfile,
+    title: Option<&str>,
+    working_dir: Option<&Path>,
+    hold: bool,
+    command: &[String],
+) -> Vec<String> {
+    let mut argv = vec![profile.exe.clone()];
+    argv.extend(profile.args.iter().cloned());
+
+    let mut push_flag = |flag: &Option<String>, value: &str| {
+        let Some(flag) = flag else { return };
+        if flag.ends_with('=') {
+            argv.push(format!("{flag}{value}"));
+        } else {
+            argv.push(flag.clone());
+            argv.push(value.to_string());
+        }
+    };
+    if let Some(title) = title {
+        push_flag(&profile.title_flag, title);
+    }
+    if let Some(dir) = working_dir {
+        push_flag(&profile.working_dir_flag, &dir.to_string_lossy());
+    }
+    if hold && let Some(flag) = &profile.hold_flag {
+        argv.push(flag.clone());
+    }
+
+    if !profile.exec_flag.is_empty() {
+        argv.push(profile.exec_flag.clone());
+    }
+    argv.extend(command.iter().cloned());
+    argv
+}
+
+/// Where terminal preferences and desktop entries are looked up.
+struct TerminalEnv {
+    config_dirs: Vec<PathBuf>,
+    data_dirs: Vec<PathBuf>,
+    desktops: Vec<String>,
+}
+
+impl TerminalEnv {
+    fn from_env() -> Self {
+        Self {
+            config_dirs: xdg::config_dirs(),
+            data_dirs: xdg::data_dirs(),
+            desktops: xdg::current_desktops(),
+        }
+    }
+}
+
+/// Picks the terminal in order of preference: the configured profile,
+/// `xdg-terminal-exec`, the `xdg-terminals.list` files, user profiles and
+/// finally the built-ins.
+fn resolve(
+    config: &TerminalConfig,
+    env: &TerminalEnv,
+    installed: &dyn Fn(&str) -> bool,
+) -> Option<TerminalProfile> {
+    let available = |profile: &TerminalProfile| installed(&profile.exe);
+
+    if let Some(name) = &config.preferred {
+        match config.profiles.get(name).cloned().or_else(|| builtin(name)) {
+            Some(profile) if available(&profile) => return Some(profile),
+            Some(profile) => tracing::warn!("Preferred terminal {} is not installed", profile.exe),
+            None => tracing::warn!("Unknown terminal profile {name}"),
+        }
+    }
+
+    builtin(XDG_TERMINAL_EXEC)
+        .filter(available)
+        .or_else(|| {
+            preferred_desktop_ids(env)
+                .iter()
+                .filter_map(|id| profile_from_desktop_entry(id, &env.data_dirs))
+                .find(available)
+        })
+        .or_else(|| config.profiles.values().find(|p| available(p)).cloned())
+        .or_else(|| {
+            BUILTIN_PROFILES
+                .iter()
+                .filter(|(exe, ..)| *exe != XDG_TERMINAL_EXEC)
+                .map(to_profile)
+                .find(available)
+        })
+}
+
+fn builtin(name: &str) -> Option<TerminalProfile> {
+    BUILTIN_PROFILES
+        .iter()
+        .find(|(exe, ..)| *exe == name)
+        .map(to_profile)
+}
+
+fn to_profile(&(exe, args, exec, title, dir, hold): &BuiltinProfile) -> TerminalProfile {
+    TerminalProfile {
+        exe: exe.to_string(),
+        args: args.iter().map(|a| a.to_string()).collect(),
+        exec_flag: exec.to_string(),
+        title_flag: title.map(str::to_string),
+        working_dir_flag: dir.map(str::to_string),
+        hold_flag: hold.map(str::to_string),
+    }
+}
+
+/// Desktop entry ids listed in the `xdg-terminals.list` files, with the
+/// desktop-specific `<desktop>-xdg-terminals.list` ones first in each
+/// config directory.
+fn preferred_desktop_ids(env: &TerminalEnv) -> Vec<String> {
+    let file_names: Vec<String> = env
+        .desktops
+        .iter()
+        .map(|desktop| format!("{desktop}-xdg-terminals.list"))
+        .chain(["xdg-terminals.list".to_string()])
+        .collect();
+
+    let mut ids = Vec::new();
+    for dir in &env.config_dirs {
+        for name in &file_names {
+            let Ok(content) = fs::read_to_string(dir.join(name)) else {
+                continue;
+            };
+            for line in content.lines().map(str::trim) {
+                // `-id` disables an entry and `/...` lines are directives.
+                if line.is_empty() || line.starts_with(['#', '-', '/']) {
+                    continue;
+                }
+                let id = line.trim_start_matches('+');
+                // Drop the `:action` suffix, if any.
+                let id = id.split_once(':').map_or(id, |(id, _)| id);
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids
+}
+
+/// Builds a profile from a terminal's desktop entry and its
+/// `X-TerminalArg*` keys, as described by the xdg-terminal-exec spec.
+fn profile_from_desktop_entry(id: &str, data_dirs: &[PathBuf]) -> Option<TerminalProfile> {
+    let path = data_dirs
+        .iter()
+        .map(|dir| dir.join("applications").join(id))
+        .find(|path| path.is_file())?;
+    let entry = DesktopEntry::from_path(path, None::<&[&str]>).ok()?;
+
+    let mut exec = entry
+        .exec()?
+        .split_whitespace()
+        .filter(|arg| !arg.starts_with('%'))
+        .map(str::to_string);
+    let arg = |key: &str| entry.desktop_entry(key).map(str::to_string);
+
+    Some(TerminalProfile {
+        exe: exec.next()?,
+        args: exec.collect(),
+        exec_flag: arg("X-TerminalArgExec").unwrap_or_else(|| "-e".to_string()),
+        title_flag: arg("X-TerminalArgTitle"),
+        working_dir_flag: arg("X-TerminalArgDir"),
+        hold_flag: arg("X-TerminalArgHold"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use super::{TerminalEnv, resolve, terminal_argv};
+    use crate::{TerminalConfig, TerminalProfile};
+
+    fn fixture_env() -> TerminalEnv {
+        let root = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/terminals");
+        TerminalEnv {
+            config_dirs: vec![root.join("config")],
+            data_dirs: vec![root.join("share")],
+            desktops: vec!["gnome".to_string()],
+        }
+    }
+
+    fn installed(names: &'static [&'static str]) -> impl Fn(&str) -> bool {
+        move |exe| names.contains(&exe)
+    }
+
+    #[test]
+    fn builds_argv_with_optional_flags() {
+        let profile = TerminalProfile {
+            exe: "ghostty".to_string(),
+            exec_flag: "-e".to_string(),
+            title_flag: Some("--title=".to_string()),
+            working_dir_flag: Some("--working-directory".to_string()),
+            hold_flag: Some("--hold".to_string()),
+            ..Default::default()
+        };
+        let argv = terminal_argv(
+            &profile,
+            Some("Top"),
+            Some(Path::new("/tmp")),
+            true,
+            &["htop".to_string()],
+        );
+        assert_eq!(
+            argv,
+            [
+                "ghostty",
+                "--title=Top",
+                "--working-directory",
+                "/tmp",
+                "--hold",
+                "-e",
+                "htop"
+            ]
+        );
+    }
+
+    #[test]
+    fn prefers_xdg_terminal_exec_then_list_then_builtins() {
+        let config = TerminalConfig::default();
+        let env = fixture_env();
+
+        let exe = |names| resolve(&config, &env, &installed(names)).map(|p| p.exe);
+        assert_eq!(
+            exe(&["xdg-terminal-exec", "fixture-term", "xterm"]).as_deref(),
+            Some("xdg-terminal-exec")
+        );
+        assert_eq!(
+            exe(&["fixture-term", "xterm"]).as_deref(),
+            Some("fixture-term")
+        );
+        assert_eq!(exe(&["xterm"]).as_deref(), Some("xterm"));
+        assert_eq!(exe(&[]), None);
+    }
+
+    #[test]
+    fn reads_terminal_args_from_desktop_entry() {
+        let config = TerminalConfig::default();
+        let profile = resolve(&config, &fixture_env(), &installed(&["fixture-term"])).unwrap();
+
+        assert_eq!(profile.args, ["--single-instance"]);
+        assert_eq!(profile.exec_flag, "--");
+        assert_eq!(profile.title_flag.as_deref(), Some("--title="));
+        assert_eq!(profile.hold_flag, None);
+    }
+
+    #[test]
+    fn honours_preferred_user_profile() {
+        let mut config = TerminalConfig {
+            preferred: Some("mine".to_string()),
+            ..Default::default()
+        };
+        config.profiles.insert(
+            "mine".to_string(),
+            TerminalProfile {
+                exe: "my-term".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let env = fixture_env();
+        let profile = resolve(&config, &env, &installed(&["my-term", "xdg-terminal-exec"]));
+        assert_eq!(profile.unwrap().exe, "my-term");
+
+        // Falls through to the usual order when it isn't installed.
+        let profile = resolve(&config, &env, &installed(&["xterm"]));
+        assert_eq!(profile.unwrap().exe, "xterm");
+    }
+}
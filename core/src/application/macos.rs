@@ -19,6 +19,10 @@ impl Application for MacOSApplication {
             .collect()
     }
 
+    fn id(&self) -> &str {
+        self.inner.path.to_str().unwrap_or(&self.inner.name)
+    }
+
     fn name(&self) -> &str {
         &self.inner.name
     }
@@ -31,7 +35,7 @@ impl Application for MacOSApplication {
         None
     }
 
-    fn icon(&self) -> Option<crate::Image> {
+    fn icon(&self, _icon_size: crate::IconSize) -> Option<crate::Image> {
         self.inner.icon.as_ref().map(|icon_data| {
             // Option A: If your UI handles raw RGBA pixels
             // Image::Data(icon_data.data.clone())
@@ -52,15 +56,28 @@ impl Application for MacOSApplication {
         })
     }
 
-    fn execute(&self, arg: Option<String>) -> anyhow::Result<()> {
+    fn detail(&self) -> crate::Detail {
+        crate::Detail::default().with_row("Path", self.inner.path.to_string_lossy())
+    }
+
+    fn execute(
+        &self,
+        arg: Option<String>,
+        launch: &crate::LaunchConfig,
+        _activation: &crate::ActivationContext,
+    ) -> anyhow::Result<()> {
+        let overrides = launch.overrides.get(self.id()).cloned().unwrap_or_default();
         let mut cmd = Command::new("open");
 
+        for (key, value) in &overrides.env {
+            cmd.arg("--env").arg(format!("{key}={value}"));
+        }
         cmd.arg("-a").arg(&self.inner.path);
 
-        if let Some(a) = arg {
-            if !a.is_empty() {
-                cmd.arg("--args").arg(a);
-            }
+        let mut args = overrides.args;
+        args.extend(arg.filter(|a| !a.is_empty()));
+        if !args.is_empty() {
+            cmd.arg("--args").args(args);
         }
 
         cmd.spawn()?;
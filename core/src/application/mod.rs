@@ -9,6 +9,9 @@ mod windows;
 
 #[cfg(target_os = "linux")]
 use linux::LinuxApplication as ConcreteApp;
+#[cfg(target_os = "linux")]
+pub use linux::spawn;
+pub(crate) use linux::{run_in_terminal, themed_icon};
 
 #[cfg(target_os = "macos")]
 use macos::MacOSApplication as ConcreteApp;
@@ -18,19 +21,66 @@ use windows::WindowsApplication as ConcreteApp;
 
 use anyhow::Result;
 
-use crate::common::Image;
+use crate::common::{IconSize, Image};
+use crate::{ActivationContext, Detail, LaunchConfig};
 
 pub type App = ConcreteApp;
 
+/// Runs `argv` in a new terminal window. `hold` keeps it open after exit.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn run_in_terminal(
+    _argv: &[String],
+    _title: &str,
+    _hold: bool,
+    _launch: &LaunchConfig,
+) -> Result<()> {
+    anyhow::bail!("Running commands in a terminal is only supported on Linux")
+}
+
+/// Icon themes are a freedesktop concept; elsewhere named icons don't
+/// resolve.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn themed_icon(_names: &[String], _icon_size: IconSize) -> Option<Image> {
+    None
+}
+
+/// Starts `argv` detached from the launcher.
+#[cfg(not(target_os = "linux"))]
+pub fn spawn(
+    argv: &[String],
+    _launch: &LaunchConfig,
+    _activation: &ActivationContext,
+) -> Result<()> {
+    use anyhow::Context;
+
+    let program = argv.first().context("Empty command")?;
+    std::process::Command::new(program)
+        .args(&argv[1..])
+        .spawn()
+        .with_context(|| format!("Failed to launch {program}"))?;
+    Ok(())
+}
+
 #[allow(dead_code)]
 pub trait Application {
     fn lookup_applications() -> Vec<Self>
     where
         Self: Sized;
 
+    /// Identifies the application across runs, e.g. for per-app settings.
+    fn id(&self) -> &str;
     fn name(&self) -> &str;
     fn alias(&self) -> Option<&str>;
     fn description(&self) -> Option<&str>;
-    fn icon(&self) -> Option<Image>;
-    fn execute(&self, arg: Option<String>) -> Result<()>;
+    /// Resolves the icon for display at `icon_size`. This may touch the
+    /// disk, so callers should keep it off the UI thread.
+    fn icon(&self, icon_size: IconSize) -> Option<Image>;
+    /// Starts the application, passing `activation` on so it can take focus.
+    fn execute(
+        &self,
+        arg: Option<String>,
+        launch: &LaunchConfig,
+        activation: &ActivationContext,
+    ) -> Result<()>;
+    fn detail(&self) -> Detail;
 }
@@ -31,6 +31,10 @@ impl Application for WindowsApplication {
             .unwrap_or_default()
     }
 
+    fn id(&self) -> &str {
+        self.path.to_str().unwrap_or(&self.name)
+    }
+
     fn name(&self) -> &str {
         &self.name
     }
@@ -43,14 +47,26 @@ impl Application for WindowsApplication {
         self.path.to_str()
     }
 
-    fn icon(&self) -> Option<Image> {
+    fn icon(&self, _icon_size: crate::IconSize) -> Option<Image> {
         self.icon.clone()
     }
 
-    fn execute(&self, _arg: Option<String>) -> anyhow::Result<()> {
+    fn detail(&self) -> crate::Detail {
+        crate::Detail::default().with_row("Path", self.path.to_string_lossy())
+    }
+
+    fn execute(
+        &self,
+        _arg: Option<String>,
+        launch: &crate::LaunchConfig,
+        _activation: &crate::ActivationContext,
+    ) -> anyhow::Result<()> {
+        let overrides = launch.overrides.get(self.id()).cloned().unwrap_or_default();
         std::process::Command::new("cmd")
             .args(["/c", "start", ""])
             .arg(&self.path)
+            .args(&overrides.args)
+            .envs(&overrides.env)
             .spawn()?;
 
         Ok(())
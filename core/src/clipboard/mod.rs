@@ -0,0 +1,198 @@
+//! Clipboard history: a daemon records what gets copied into an encrypted
+//! store, which the clipboard plugin lists.
+
+mod store;
+#[cfg(target_os = "linux")]
+mod wayland;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::ClipboardConfig;
+
+pub(crate) use self::store::{ClipStore, history_path};
+
+/// What was copied.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ClipContent {
+    Text(String),
+    Image {
+        mime: String,
+        width: u32,
+        height: u32,
+        #[serde(with = "store::base64_bytes")]
+        data: Vec<u8>,
+    },
+}
+
+impl ClipContent {
+    /// Whether this is text containing `needle`, which must be lowercase,
+    /// or an image when `needle` is part of "image".
+    pub fn matches(&self, needle: &str) -> bool {
+        match self {
+            ClipContent::Text(text) => text.to_lowercase().contains(needle),
+            ClipContent::Image { .. } => "image".contains(needle),
+        }
+    }
+}
+
+/// An entry of the history.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(crate) struct Clip {
+    pub id: u64,
+    pub content: ClipContent,
+    /// When it was last copied, in seconds since the Unix epoch.
+    pub copied: u64,
+    /// The application it was copied from, when known.
+    pub source: Option<String>,
+    /// Pinned entries are listed first and never dropped.
+    pub pinned: bool,
+}
+
+/// A new clipboard selection.
+#[derive(Debug)]
+pub(crate) struct Selection {
+    pub content: ClipContent,
+    pub source: Option<String>,
+}
+
+/// Where selections come from, so that tests can replace the compositor.
+pub(crate) trait ClipboardSource {
+    /// Blocks until something is copied, returning `None` once there will
+    /// be nothing more, like when the session ends.
+    fn next_selection(&mut self) -> Result<Option<Selection>>;
+}
+
+/// Records clipboard changes into the history until the session ends.
+/// Meant to run in the background, e.g. started with the compositor.
+pub fn run_clipboard_daemon(config: &ClipboardConfig) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        let mut source = wayland::DataControlSource::connect(config.max_image_size)?;
+        let mut store = ClipStore::open_default()?;
+        tracing::info!("Recording the clipboard history");
+        record(&mut source, &mut store, config)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = config;
+        anyhow::bail!("Recording the clipboard history needs a Wayland compositor")
+    }
+}
+
+fn record(
+    source: &mut dyn ClipboardSource,
+    store: &mut ClipStore,
+    config: &ClipboardConfig,
+) -> Result<()> {
+    while let Some(selection) = source.next_selection()? {
+        if let Some(app) = &selection.source
+            && config
+                .ignore_apps
+                .iter()
+                .any(|ignored| ignored.eq_ignore_ascii_case(app))
+        {
+            tracing::debug!(%app, "Not recording a copy from an ignored application");
+            continue;
+        }
+
+        // The daemon runs for the whole session, so a failed write, like on
+        // a full disk, only loses this entry.
+        match store.update(|store| store.add(selection, now(), config.max_entries)) {
+            Ok(id) => tracing::debug!(id, "Recorded a clipboard entry"),
+            Err(e) => tracing::error!("Failed to record a clipboard entry: {e:#}"),
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use anyhow::Result;
+
+    use super::{ClipContent, ClipStore, ClipboardSource, Selection, record};
+    use crate::ClipboardConfig;
+    use crate::common::test_dir::TempDir;
+
+    struct FakeClipboard(VecDeque<Selection>);
+
+    impl ClipboardSource for FakeClipboard {
+        fn next_selection(&mut self) -> Result<Option<Selection>> {
+            Ok(self.0.pop_front())
+        }
+    }
+
+    fn copied(text: &str, app: &str) -> Selection {
+        Selection {
+            content: ClipContent::Text(text.to_string()),
+            source: Some(app.to_string()),
+        }
+    }
+
+    #[test]
+    fn records_copies_except_from_ignored_apps() {
+        let root = TempDir::new("clip-record");
+        let mut store = ClipStore::open(&root.join("history.bin"), &root.join("key")).unwrap();
+
+        let mut clipboard = FakeClipboard(VecDeque::from([
+            copied("git status", "foot"),
+            copied("hunter2", "org.keepassxc.KeePassXC"),
+            Selection {
+                content: ClipContent::Image {
+                    mime: "image/png".to_string(),
+                    width: 1,
+                    height: 1,
+                    data: vec![0x89, b'P', b'N', b'G'],
+                },
+                source: None,
+            },
+        ]));
+        let config = ClipboardConfig {
+            ignore_apps: vec!["org.keepassxc.keepassxc".to_string()],
+            ..ClipboardConfig::default()
+        };
+        record(&mut clipboard, &mut store, &config).unwrap();
+
+        store.reload().unwrap();
+        let recorded: Vec<_> = store
+            .clips()
+            .iter()
+            .map(|clip| (&clip.content, clip.source.as_deref()))
+            .collect();
+        assert!(matches!(
+            recorded[0],
+            (ClipContent::Image { width: 1, .. }, None)
+        ));
+        assert_eq!(
+            recorded[1],
+            (&ClipContent::Text("git status".to_string()), Some("foot"))
+        );
+        assert_eq!(recorded.len(), 2);
+    }
+
+    #[test]
+    fn keeps_recording_after_a_failed_write() {
+        let root = TempDir::new("clip-record-failed");
+        // The history's directory doesn't exist, so every write fails.
+        let missing = root.join("missing");
+        let mut store = ClipStore::open(&missing.join("history.bin"), &root.join("key")).unwrap();
+
+        let mut clipboard = FakeClipboard(VecDeque::from([
+            copied("first", "foot"),
+            copied("second", "foot"),
+        ]));
+        record(&mut clipboard, &mut store, &ClipboardConfig::default()).unwrap();
+        assert!(clipboard.0.is_empty());
+    }
+}
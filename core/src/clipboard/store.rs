@@ -0,0 +1,337 @@
+//! The clipboard history on disk, encrypted with AES-256-GCM under a key
+//! kept in its own file.
+//!
+//! The key sits unencrypted in the same directory, readable only by the
+//! user. This keeps the history unreadable where it travels without the key,
+//! such as a backup or synced folder that leaves out `clipboard.key`. It
+//! doesn't protect against anything running as the user or reading the
+//! whole data directory. Those can read the key just as easily, and while
+//! the daemon runs they can read the clipboard itself.
+
+use std::{
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use aes_gcm::{
+    Aes256Gcm, Key, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng},
+};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use super::{Clip, Selection};
+use crate::common::{data_file, write_atomically};
+
+const MAGIC: &[u8; 8] = b"ICRCLIP1";
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize, Default)]
+struct History {
+    next_id: u64,
+    /// Most recently copied first.
+    clips: Vec<Clip>,
+}
+
+pub(crate) struct ClipStore {
+    path: PathBuf,
+    cipher: Aes256Gcm,
+    history: History,
+}
+
+impl ClipStore {
+    /// The history in the data directory.
+    pub fn open_default() -> Result<Self> {
+        Self::open(&history_path(), &data_file("clipboard.key"))
+    }
+
+    /// Reads the history at `path`, empty if there's none yet, creating
+    /// the key at `key_path` if needed.
+    pub fn open(path: &Path, key_path: &Path) -> Result<Self> {
+        let key = load_key(key_path)?;
+        let mut store = Self {
+            path: path.to_path_buf(),
+            cipher: Aes256Gcm::new(&key),
+            history: History::default(),
+        };
+        store.reload()?;
+        Ok(store)
+    }
+
+    /// Reads the history again, picking up changes of other processes.
+    pub fn reload(&mut self) -> Result<()> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                self.history = History::default();
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()));
+            }
+        };
+        let plain = self
+            .decrypt(&data)
+            .with_context(|| format!("Failed to decrypt {}", self.path.display()))?;
+        self.history = serde_json::from_slice(&plain)
+            .with_context(|| format!("Failed to parse {}", self.path.display()))?;
+        Ok(())
+    }
+
+    /// Applies `change` to the latest history and saves it. The daemon and
+    /// the launcher both write the history, so it's locked meanwhile for
+    /// neither to lose what the other changed.
+    pub fn update<T>(&mut self, change: impl FnOnce(&mut Self) -> T) -> Result<T> {
+        let lock_path = self.path.with_extension("lock");
+        let lock = File::create(&lock_path)
+            .with_context(|| format!("Failed to create {}", lock_path.display()))?;
+        lock.lock()
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+
+        self.reload()?;
+        let changed = change(self);
+        self.save()?;
+        Ok(changed)
+    }
+
+    fn save(&self) -> Result<()> {
+        let plain = serde_json::to_vec(&self.history)?;
+        let data = self.encrypt(&plain)?;
+        write_atomically(&self.path, data)
+    }
+
+    /// The entries, most recently copied first.
+    pub fn clips(&self) -> &[Clip] {
+        &self.history.clips
+    }
+
+    pub fn get(&self, id: u64) -> Option<&Clip> {
+        self.history.clips.iter().find(|clip| clip.id == id)
+    }
+
+    /// Records `selection`, copied at `now`. Copying something again moves
+    /// its entry to the top instead of adding another one. Beyond
+    /// `max_entries`, the oldest unpinned entries are dropped.
+    pub fn add(&mut self, selection: Selection, now: u64, max_entries: usize) -> u64 {
+        let clips = &mut self.history.clips;
+        let existing = clips
+            .iter()
+            .position(|clip| clip.content == selection.content)
+            .map(|i| clips.remove(i));
+
+        let id = match &existing {
+            Some(clip) => clip.id,
+            None => {
+                self.history.next_id += 1;
+                self.history.next_id
+            }
+        };
+        let clips = &mut self.history.clips;
+        clips.insert(
+            0,
+            Clip {
+                id,
+                content: selection.content,
+                copied: now,
+                source: selection
+                    .source
+                    .or_else(|| existing.as_ref().and_then(|clip| clip.source.clone())),
+                pinned: existing.is_some_and(|clip| clip.pinned),
+            },
+        );
+
+        let mut unpinned = 0;
+        clips.retain(|clip| {
+            unpinned += usize::from(!clip.pinned);
+            clip.pinned || unpinned <= max_entries
+        });
+        id
+    }
+
+    /// Moves the entry `id` to the top, as if it was just copied.
+    pub fn touch(&mut self, id: u64, now: u64) -> bool {
+        let clips = &mut self.history.clips;
+        let Some(i) = clips.iter().position(|clip| clip.id == id) else {
+            return false;
+        };
+        let mut clip = clips.remove(i);
+        clip.copied = now;
+        clips.insert(0, clip);
+        true
+    }
+
+    pub fn set_pinned(&mut self, id: u64, pinned: bool) -> bool {
+        match self.history.clips.iter_mut().find(|clip| clip.id == id) {
+            Some(clip) => {
+                clip.pinned = pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, id: u64) -> bool {
+        let before = self.history.clips.len();
+        self.history.clips.retain(|clip| clip.id != id);
+        self.history.clips.len() != before
+    }
+
+    fn encrypt(&self, plain: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, plain)
+            .map_err(|_| anyhow!("Failed to encrypt the clipboard history"))?;
+
+        let mut data = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&nonce);
+        data.extend_from_slice(&sealed);
+        Ok(data)
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let Some(rest) = data.strip_prefix(MAGIC) else {
+            bail!("Not a clipboard history file");
+        };
+        if rest.len() < NONCE_LEN {
+            bail!("The file is truncated");
+        }
+        let (nonce, sealed) = rest.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| anyhow!("Wrong key or corrupted data"))
+    }
+}
+
+/// Where the history is kept, to check whether it changed.
+pub(crate) fn history_path() -> PathBuf {
+    data_file("clipboard.bin")
+}
+
+/// Reads the key at `path`, generating one readable only by the user if
+/// there's none.
+fn load_key(path: &Path) -> Result<Key<Aes256Gcm>> {
+    match fs::read(path) {
+        Ok(bytes) if bytes.len() == 32 => return Ok(*Key::<Aes256Gcm>::from_slice(&bytes)),
+        Ok(_) => bail!("{} isn't a clipboard history key", path.display()),
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    }
+
+    let key = Aes256Gcm::generate_key(&mut OsRng);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    match options.open(path) {
+        Ok(mut file) => {
+            file.write_all(&key)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            Ok(key)
+        }
+        // Another process created it first.
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => load_key(path),
+        Err(e) => Err(e).with_context(|| format!("Failed to create {}", path.display())),
+    }
+}
+
+/// `ClipContent::Image` data is kept as base64 rather than as a JSON array
+/// of numbers.
+pub(super) mod base64_bytes {
+    use base64::{Engine, engine::general_purpose::STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer, de::Error};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        STANDARD.decode(encoded).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::ClipStore;
+    use crate::clipboard::{ClipContent, Selection};
+    use crate::common::test_dir::TempDir;
+
+    fn text(value: &str) -> Selection {
+        Selection {
+            content: ClipContent::Text(value.to_string()),
+            source: Some("editor".to_string()),
+        }
+    }
+
+    #[test]
+    fn keeps_a_bounded_encrypted_history() {
+        let root = TempDir::new("clip-store");
+        let (path, key) = (root.join("history.bin"), root.join("history.key"));
+
+        let mut store = ClipStore::open(&path, &key).unwrap();
+        let first = store
+            .update(|store| {
+                let first = store.add(text("first"), 1, 2);
+                store.add(text("second"), 2, 2);
+                assert!(store.set_pinned(first, true));
+                store.add(text("third"), 3, 2);
+                store.add(text("fourth"), 4, 2);
+                // Copying again moves the entry up, keeping its id and pin.
+                assert_eq!(store.add(text("first"), 5, 2), first);
+                first
+            })
+            .unwrap();
+
+        let saved = fs::read(&path).unwrap();
+        assert!(!String::from_utf8_lossy(&saved).contains("fourth"));
+
+        let mut reopened = ClipStore::open(&path, &key).unwrap();
+        let texts: Vec<(&ClipContent, bool)> = reopened
+            .clips()
+            .iter()
+            .map(|clip| (&clip.content, clip.pinned))
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                (&ClipContent::Text("first".to_string()), true),
+                (&ClipContent::Text("fourth".to_string()), false),
+                (&ClipContent::Text("third".to_string()), false),
+            ]
+        );
+        assert!(reopened.remove(first));
+        assert!(reopened.get(first).is_none());
+
+        // Another key can't read it.
+        fs::remove_file(&key).unwrap();
+        assert!(ClipStore::open(&path, &key).is_err());
+    }
+
+    #[test]
+    fn updates_keep_changes_of_other_processes() {
+        let root = TempDir::new("clip-update");
+        let (path, key) = (root.join("history.bin"), root.join("history.key"));
+
+        let mut daemon = ClipStore::open(&path, &key).unwrap();
+        let id = daemon
+            .update(|store| store.add(text("first"), 1, 10))
+            .unwrap();
+
+        let mut launcher = ClipStore::open(&path, &key).unwrap();
+        assert!(launcher.update(|store| store.set_pinned(id, true)).unwrap());
+
+        // The daemon's copy of the history is stale by now.
+        daemon
+            .update(|store| store.add(text("second"), 2, 10))
+            .unwrap();
+        let reopened = ClipStore::open(&path, &key).unwrap();
+        assert_eq!(reopened.clips().len(), 2);
+        assert!(reopened.get(id).unwrap().pinned);
+    }
+}
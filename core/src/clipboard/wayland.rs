@@ -0,0 +1,373 @@
+//! Watches the clipboard through the wlr data-control protocol, which
+//! wlroots compositors, KWin and others offer to clipboard managers.
+
+use std::{
+    io::{self, Cursor, Read},
+    os::fd::AsFd,
+    process::{Command, Stdio},
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, bail};
+use rustix::event::{PollFd, PollFlags, Timespec, poll};
+use wayland_client::{
+    Connection, Dispatch, EventQueue, Proxy, QueueHandle, event_created_child,
+    globals::{GlobalListContents, registry_queue_init},
+    protocol::{wl_registry::WlRegistry, wl_seat::WlSeat},
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+};
+
+use super::{ClipContent, ClipboardSource, Selection};
+
+/// Text types, most preferred first.
+const TEXT_TYPES: [&str; 4] = [
+    "text/plain;charset=utf-8",
+    "UTF8_STRING",
+    "text/plain",
+    "STRING",
+];
+/// Set by password managers on secrets they copy.
+const SECRET_HINT: &str = "x-kde-passwordManagerHint";
+/// Longer text isn't recorded.
+const MAX_TEXT_SIZE: usize = 1024 * 1024;
+/// How long the source of a selection gets to send it.
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub(super) struct DataControlSource {
+    connection: Connection,
+    queue: EventQueue<State>,
+    state: State,
+    max_image_size: usize,
+    _device: ZwlrDataControlDeviceV1,
+}
+
+#[derive(Default)]
+struct State {
+    /// The new selection with its MIME types, not read yet.
+    selection: Option<(ZwlrDataControlOfferV1, Vec<String>)>,
+    finished: bool,
+}
+
+/// The MIME types an offer was announced with.
+type OfferTypes = Mutex<Vec<String>>;
+
+impl DataControlSource {
+    pub fn connect(max_image_size: usize) -> Result<Self> {
+        let connection = Connection::connect_to_env().context("Not running under Wayland")?;
+        let (globals, queue) = registry_queue_init::<State>(&connection)?;
+        let qh = queue.handle();
+
+        let manager: ZwlrDataControlManagerV1 = globals
+            .bind(&qh, 1..=2, ())
+            .context("Compositor doesn't support wlr data-control")?;
+        let seat: WlSeat = globals.bind(&qh, 1..=1, ()).context("No seat")?;
+        let device = manager.get_data_device(&seat, &qh, ());
+
+        Ok(Self {
+            connection,
+            queue,
+            state: State::default(),
+            max_image_size,
+            _device: device,
+        })
+    }
+
+    /// Reads the content of `offer` worth recording, if any.
+    fn read(
+        &self,
+        offer: &ZwlrDataControlOfferV1,
+        types: &[String],
+    ) -> Result<Option<ClipContent>> {
+        if types.iter().any(|mime| mime == SECRET_HINT) {
+            tracing::debug!("Not recording a secret");
+            return Ok(None);
+        }
+
+        if let Some(mime) = TEXT_TYPES
+            .iter()
+            .find(|text| types.iter().any(|mime| mime == *text))
+        {
+            let Some(data) = self.receive(offer, mime, MAX_TEXT_SIZE)? else {
+                return Ok(None);
+            };
+            let text = String::from_utf8_lossy(&data).into_owned();
+            return Ok((!text.trim().is_empty()).then_some(ClipContent::Text(text)));
+        }
+
+        let image = types
+            .iter()
+            .find(|mime| *mime == "image/png")
+            .or_else(|| types.iter().find(|mime| mime.starts_with("image/")));
+        let Some(mime) = image else {
+            return Ok(None);
+        };
+        let Some(data) = self.receive(offer, mime, self.max_image_size)? else {
+            return Ok(None);
+        };
+        let (width, height) = image::ImageReader::new(Cursor::new(&data))
+            .with_guessed_format()?
+            .into_dimensions()
+            .with_context(|| format!("Unreadable {mime} image"))?;
+        Ok(Some(ClipContent::Image {
+            mime: mime.clone(),
+            width,
+            height,
+            data,
+        }))
+    }
+
+    /// The data of `offer` as `mime`, or `None` when it's over `limit`.
+    fn receive(
+        &self,
+        offer: &ZwlrDataControlOfferV1,
+        mime: &str,
+        limit: usize,
+    ) -> Result<Option<Vec<u8>>> {
+        let (reader, writer) = std::io::pipe()?;
+        offer.receive(mime.to_string(), writer.as_fd());
+        self.connection.flush()?;
+        // The source writes until it closes its end, so ours must be gone.
+        drop(writer);
+
+        let data = read_until(reader, limit, Instant::now() + RECEIVE_TIMEOUT)
+            .with_context(|| format!("Failed to read the {mime} selection"))?;
+        if data.is_none() {
+            tracing::debug!(%mime, limit, "Not recording a large selection");
+        }
+        Ok(data)
+    }
+}
+
+/// Reads `reader` to its end, or `None` once it's over `limit`. A source
+/// that stalls must not hang the daemon, so it fails at `deadline`.
+fn read_until(
+    mut reader: impl Read + AsFd,
+    limit: usize,
+    deadline: Instant,
+) -> Result<Option<Vec<u8>>> {
+    rustix::io::ioctl_fionbio(&reader, true)?;
+
+    let mut data = Vec::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        match reader.read(&mut buffer) {
+            Ok(0) => return Ok(Some(data)),
+            Ok(read) => {
+                data.extend_from_slice(&buffer[..read]);
+                if data.len() > limit {
+                    return Ok(None);
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                if left.is_zero() {
+                    bail!("Timed out after {} bytes", data.len());
+                }
+                let timeout = Timespec::try_from(left)?;
+                let mut fds = [PollFd::new(&reader, PollFlags::IN)];
+                match poll(&mut fds, Some(&timeout)) {
+                    Ok(_) | Err(rustix::io::Errno::INTR) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+impl ClipboardSource for DataControlSource {
+    fn next_selection(&mut self) -> Result<Option<Selection>> {
+        loop {
+            while self.state.selection.is_none() && !self.state.finished {
+                self.queue.blocking_dispatch(&mut self.state)?;
+            }
+            let Some((offer, types)) = self.state.selection.take() else {
+                return Ok(None);
+            };
+
+            let content = self.read(&offer, &types);
+            offer.destroy();
+            match content {
+                Ok(Some(content)) => {
+                    return Ok(Some(Selection {
+                        content,
+                        source: focused_app(),
+                    }));
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to read the clipboard: {e:#}"),
+            }
+        }
+    }
+}
+
+/// The app id or class of the focused window, which is usually where the
+/// copy came from. Only known on compositors with an IPC for it.
+fn focused_app() -> Option<String> {
+    if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        let window = ipc(&["hyprctl", "activewindow", "-j"])?;
+        return non_empty(window.get("class")?.as_str()?);
+    }
+    if std::env::var_os("SWAYSOCK").is_some() {
+        let tree = ipc(&["swaymsg", "-t", "get_tree"])?;
+        let window = focused_node(&tree)?;
+        let app_id = window.get("app_id").and_then(|id| id.as_str());
+        let class = window
+            .pointer("/window_properties/class")
+            .and_then(|class| class.as_str());
+        return app_id.or(class).and_then(non_empty);
+    }
+    None
+}
+
+fn ipc(argv: &[&str]) -> Option<serde_json::Value> {
+    let output = Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    serde_json::from_slice(&output.stdout).ok()
+}
+
+fn focused_node(node: &serde_json::Value) -> Option<&serde_json::Value> {
+    if node.get("focused").and_then(|f| f.as_bool()) == Some(true) {
+        return Some(node);
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node.get(key)?.as_array())
+        .flatten()
+        .find_map(focused_node)
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::Selection { id: Some(offer) } => {
+                let types = offer
+                    .data::<OfferTypes>()
+                    .map(|types| types.lock().unwrap_or_else(PoisonError::into_inner).clone())
+                    .unwrap_or_default();
+                // A selection that was replaced before being read is gone.
+                if let Some((previous, _)) = state.selection.replace((offer, types)) {
+                    previous.destroy();
+                }
+            }
+            zwlr_data_control_device_v1::Event::PrimarySelection { id: Some(offer) } => {
+                offer.destroy();
+            }
+            zwlr_data_control_device_v1::Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, OfferTypes::default()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, OfferTypes> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        types: &OfferTypes,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            types
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .push(mime_type);
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlManagerV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrDataControlManagerV1,
+        _: <ZwlrDataControlManagerV1 as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlSeat,
+        _: <WlSeat as Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: <WlRegistry as Proxy>::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Write,
+        time::{Duration, Instant},
+    };
+
+    use super::read_until;
+
+    #[test]
+    fn reads_within_the_limit_and_deadline() {
+        let deadline = || Instant::now() + Duration::from_secs(5);
+
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"copied").unwrap();
+        drop(writer);
+        assert_eq!(
+            read_until(reader, 6, deadline()).unwrap().as_deref(),
+            Some(&b"copied"[..])
+        );
+
+        let (reader, mut writer) = std::io::pipe().unwrap();
+        writer.write_all(b"copied").unwrap();
+        drop(writer);
+        assert_eq!(read_until(reader, 5, deadline()).unwrap(), None);
+
+        // A source that never closes its end.
+        let (reader, _writer) = std::io::pipe().unwrap();
+        let start = Instant::now();
+        assert!(read_until(reader, 6, start + Duration::from_millis(100)).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,350 @@
+use std::{fs, path::Path};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use image::{DynamicImage, ImageFormat};
+
+use crate::common::{IconSize, Image};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Larger XPM images are rejected rather than allocated; icons are far
+/// smaller.
+const MAX_XPM_SIZE: usize = 4096;
+/// XPM palettes rarely use more than 2 characters per pixel.
+const MAX_XPM_CHARS_PER_PIXEL: usize = 8;
+
+/// Turns an icon file into an [`Image`] the UI can render without touching
+/// the disk again. Raster formats, including the XPM and ICO files iced
+/// cannot draw itself, are decoded to RGBA; SVGs are passed through as a
+/// path.
+pub fn load_icon(path: &Path, icon_size: IconSize) -> Result<Image> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+
+    match extension.as_deref() {
+        Some("xpm") => {
+            let content = fs::read(path)?;
+            decode_xpm(&String::from_utf8_lossy(&content))
+                .with_context(|| format!("Failed to decode {}", path.display()))
+        }
+        Some("ico") => {
+            let content = fs::read(path)?;
+            decode_ico(&content, icon_size.size * icon_size.scale)
+                .with_context(|| format!("Failed to decode {}", path.display()))
+        }
+        Some("svg" | "svgz") => Ok(Image::Path(path.to_string_lossy().into_owned())),
+        _ => {
+            let decoded = image::open(path)
+                .with_context(|| format!("Failed to decode {}", path.display()))?;
+            Ok(fit(decoded, icon_size.size * icon_size.scale))
+        }
+    }
+}
+
+/// `image` as RGBA, scaled down to `target` pixels when larger, so large
+/// icons don't stay in memory at full size.
+fn fit(image: DynamicImage, target: u32) -> Image {
+    let image = if image.width() > target || image.height() > target {
+        image.thumbnail(target, target)
+    } else {
+        image
+    };
+    let rgba = image.into_rgba8();
+    Image::Rgba(rgba.width(), rgba.height(), rgba.into_raw())
+}
+
+struct IcoFrame<'a> {
+    width: u32,
+    bit_count: u16,
+    entry: &'a [u8],
+    data: &'a [u8],
+}
+
+/// Decodes the frame of an ICO file best suited to `target` pixels: the
+/// smallest frame at least that large, or the largest one available.
+pub fn decode_ico(bytes: &[u8], target: u32) -> Result<Image> {
+    ensure!(bytes.len() >= 6, "ICO header is truncated");
+    let count = u16::from_le_bytes([bytes[4], bytes[5]]) as usize;
+
+    let frames: Vec<IcoFrame> = (0..count)
+        .filter_map(|i| {
+            let entry = bytes.get(6 + i * 16..6 + (i + 1) * 16)?;
+            let size = u32::from_le_bytes(entry[8..12].try_into().ok()?) as usize;
+            let offset = u32::from_le_bytes(entry[12..16].try_into().ok()?) as usize;
+
+            Some(IcoFrame {
+                // A stored width of 0 means 256.
+                width: if entry[0] == 0 { 256 } else { entry[0] as u32 },
+                bit_count: u16::from_le_bytes([entry[6], entry[7]]),
+                entry,
+                data: bytes.get(offset..offset.checked_add(size)?)?,
+            })
+        })
+        .collect();
+
+    let frame = frames
+        .iter()
+        .filter(|f| f.width >= target)
+        .min_by_key(|f| (f.width, std::cmp::Reverse(f.bit_count)))
+        .or_else(|| frames.iter().max_by_key(|f| (f.width, f.bit_count)))
+        .ok_or_else(|| anyhow!("ICO file contains no frames"))?;
+
+    let decoded = if frame.data.starts_with(PNG_SIGNATURE) {
+        image::load_from_memory_with_format(frame.data, ImageFormat::Png)?
+    } else {
+        // Re-wrap the BMP frame in a single-entry ICO so the decoder can't
+        // pick a different one.
+        let mut single = Vec::with_capacity(22 + frame.data.len());
+        single.extend_from_slice(&[0, 0, 1, 0, 1, 0]);
+        single.extend_from_slice(&frame.entry[..12]);
+        single.extend_from_slice(&22u32.to_le_bytes());
+        single.extend_from_slice(frame.data);
+        image::load_from_memory_with_format(&single, ImageFormat::Ico)?
+    };
+
+    Ok(fit(decoded, target))
+}
+
+/// Decodes an XPM3 image into RGBA pixels.
+pub fn decode_xpm(source: &str) -> Result<Image> {
+    let mut lines = xpm_strings(source).into_iter();
+
+    let header = lines.next().ok_or_else(|| anyhow!("XPM has no values"))?;
+    let values: Vec<usize> = header
+        .split_whitespace()
+        .take(4)
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .context("Invalid XPM values")?;
+    let [width, height, color_count, chars_per_pixel] = values[..] else {
+        bail!("XPM values are incomplete");
+    };
+    ensure!(
+        (1..=MAX_XPM_CHARS_PER_PIXEL).contains(&chars_per_pixel),
+        "XPM uses {chars_per_pixel} characters per pixel"
+    );
+    ensure!(
+        width <= MAX_XPM_SIZE && height <= MAX_XPM_SIZE,
+        "XPM is too large ({width}×{height})"
+    );
+    let length = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .context("XPM is too large")?;
+
+    // The header can't be trusted to size the palette either.
+    let mut palette = std::collections::HashMap::new();
+    for _ in 0..color_count {
+        let line = lines
+            .next()
+            .ok_or_else(|| anyhow!("XPM palette is truncated"))?;
+        let key = line
+            .get(..chars_per_pixel)
+            .ok_or_else(|| anyhow!("XPM palette entry is too short"))?;
+        palette.insert(key.to_string(), xpm_color(&line[chars_per_pixel..]));
+    }
+
+    let mut pixels = Vec::with_capacity(length);
+    for _ in 0..height {
+        let row = lines
+            .next()
+            .ok_or_else(|| anyhow!("XPM pixels are truncated"))?;
+        for x in 0..width {
+            let key = row
+                .get(x * chars_per_pixel..(x + 1) * chars_per_pixel)
+                .ok_or_else(|| anyhow!("XPM row is too short"))?;
+            pixels.extend_from_slice(palette.get(key).unwrap_or(&[0, 0, 0, 0]));
+        }
+    }
+
+    Ok(Image::Rgba(width as u32, height as u32, pixels))
+}
+
+/// Extracts the C string literals an XPM file is made of.
+fn xpm_strings(source: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = source.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut s = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => s.extend(chars.next()),
+                        c => s.push(c),
+                    }
+                }
+                strings.push(s);
+            }
+            '/' if chars.as_str().starts_with('*') => {
+                let rest = chars.as_str();
+                let end = rest.find("*/").map_or(rest.len(), |i| i + 2);
+                chars = rest[end..].chars();
+            }
+            _ => {}
+        }
+    }
+
+    strings
+}
+
+/// Picks the colour for the richest visual in an XPM palette entry such as
+/// `c #FF0000 m black`.
+fn xpm_color(spec: &str) -> [u8; 4] {
+    let mut contexts: Vec<(&str, String)> = Vec::new();
+
+    for token in spec.split_whitespace() {
+        match token {
+            "c" | "m" | "g" | "g4" | "s" => contexts.push((token, String::new())),
+            _ => {
+                if let Some((_, color)) = contexts.last_mut() {
+                    if !color.is_empty() {
+                        color.push(' ');
+                    }
+                    color.push_str(token);
+                }
+            }
+        }
+    }
+
+    ["c", "g", "g4", "m"]
+        .iter()
+        .find_map(|wanted| contexts.iter().find(|(key, _)| key == wanted))
+        .map_or([0, 0, 0, 255], |(_, color)| parse_color(color))
+}
+
+fn parse_color(color: &str) -> [u8; 4] {
+    let lower = color.to_ascii_lowercase();
+
+    if lower == "none" || lower == "transparent" {
+        return [0, 0, 0, 0];
+    }
+
+    if let Some(hex) = lower.strip_prefix('#') {
+        let digits = hex.len() / 3;
+        if (1..=4).contains(&digits) && hex.len() % 3 == 0 && hex.is_ascii() {
+            let channel = |i: usize| {
+                let value = u32::from_str_radix(&hex[i * digits..(i + 1) * digits], 16).ok()?;
+                let max = (1u32 << (digits * 4)) - 1;
+                Some((value * 255 / max) as u8)
+            };
+            if let (Some(r), Some(g), Some(b)) = (channel(0), channel(1), channel(2)) {
+                return [r, g, b, 255];
+            }
+        }
+        return [0, 0, 0, 255];
+    }
+
+    let gray_level = lower
+        .strip_prefix("gray")
+        .or_else(|| lower.strip_prefix("grey"))
+        .and_then(|level| level.parse::<u32>().ok())
+        .filter(|level| *level <= 100);
+    if let Some(level) = gray_level {
+        let v = (level * 255 / 100) as u8;
+        return [v, v, v, 255];
+    }
+
+    let [r, g, b] = match lower.replace(' ', "").as_str() {
+        "white" => [255, 255, 255],
+        "red" => [255, 0, 0],
+        "green" => [0, 255, 0],
+        "blue" => [0, 0, 255],
+        "yellow" => [255, 255, 0],
+        "cyan" => [0, 255, 255],
+        "magenta" => [255, 0, 255],
+        "gray" | "grey" => [190, 190, 190],
+        "lightgray" | "lightgrey" => [211, 211, 211],
+        "darkgray" | "darkgrey" => [169, 169, 169],
+        "orange" => [255, 165, 0],
+        _ => [0, 0, 0],
+    };
+    [r, g, b, 255]
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_ico, decode_xpm};
+    use crate::common::Image;
+
+    #[test]
+    fn decodes_xpm() {
+        let source = r#"/* XPM */
+static char *test[] = {
+/* columns rows colors chars-per-pixel */
+"3 2 3 1",
+"  c None",
+"r c #FF0000",
+"g c gray50 m white",
+/* pixels */
+"r g",
+"g r"
+};"#;
+
+        let Image::Rgba(width, height, pixels) = decode_xpm(source).unwrap() else {
+            panic!("expected RGBA output");
+        };
+        assert_eq!((width, height), (3, 2));
+        assert_eq!(&pixels[0..4], &[255, 0, 0, 255]);
+        assert_eq!(&pixels[4..8], &[0, 0, 0, 0]);
+        assert_eq!(&pixels[8..12], &[127, 127, 127, 255]);
+    }
+
+    #[test]
+    fn rejects_malformed_xpm_headers() {
+        let huge = format!(r#""{max} {max} 1 1", "a c red""#, max = usize::MAX);
+        assert!(decode_xpm(&huge).is_err());
+        assert!(decode_xpm(r#""5000 1 1 1", "a c red""#).is_err());
+        assert!(decode_xpm(r#""1 1 1 0", "a c red", "a""#).is_err());
+        assert!(decode_xpm(r#""1 1 1 99", "a c red", "a""#).is_err());
+        assert!(decode_xpm(r#""1 2 1 1", "a c red", "a""#).is_err());
+    }
+
+    fn png(size: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::RgbaImage::from_pixel(size, size, image::Rgba([size as u8, 0, 0, 255]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut bytes),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+        bytes
+    }
+
+    fn ico(sizes: &[u32]) -> Vec<u8> {
+        let frames: Vec<Vec<u8>> = sizes.iter().map(|s| png(*s)).collect();
+        let mut bytes = vec![0, 0, 1, 0, sizes.len() as u8, 0];
+        let mut offset = 6 + 16 * sizes.len() as u32;
+
+        for (size, frame) in sizes.iter().zip(&frames) {
+            bytes.extend_from_slice(&[*size as u8, *size as u8, 0, 0, 1, 0, 32, 0]);
+            bytes.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&offset.to_le_bytes());
+            offset += frame.len() as u32;
+        }
+        for frame in frames {
+            bytes.extend_from_slice(&frame);
+        }
+        bytes
+    }
+
+    #[test]
+    fn picks_best_ico_frame() {
+        let bytes = ico(&[16, 48, 32]);
+
+        // Each frame is filled with its size as the red channel.
+        let decoded = |target| match decode_ico(&bytes, target).unwrap() {
+            Image::Rgba(width, _, pixels) => (pixels[0], width),
+            _ => panic!("expected RGBA output"),
+        };
+
+        assert_eq!(decoded(16), (16, 16));
+        // Larger frames are scaled down to the target.
+        assert_eq!(decoded(24), (32, 24));
+        assert_eq!(decoded(32), (32, 32));
+        assert_eq!(decoded(64), (48, 48));
+    }
+}
@@ -2,6 +2,13 @@ use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
+
+pub mod decode;
+#[cfg(test)]
+pub(crate) mod test_dir;
+#[cfg(target_os = "linux")]
+pub mod xdg;
 
 #[derive(Debug, Clone)]
 pub enum Image {
@@ -10,6 +17,28 @@ pub enum Image {
     Rgba(u32, u32, Vec<u8>),
 }
 
+/// The size an icon is rendered at, in logical pixels, together with the
+/// integer scale factor of the output it is displayed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IconSize {
+    pub size: u32,
+    pub scale: u32,
+}
+
+impl IconSize {
+    pub fn new(size: u32, scale: u32) -> Self {
+        Self {
+            size,
+            scale: scale.max(1),
+        }
+    }
+
+    /// Rounds fractional output scales up so icons are never upscaled.
+    pub fn from_scale_factor(size: f32, scale_factor: f32) -> Self {
+        Self::new(size.round() as u32, scale_factor.ceil() as u32)
+    }
+}
+
 #[derive(Serialize, Deserialize, Default, Clone, Debug)]
 pub struct AppState {
     pub usage_stats: HashMap<String, UsageInfo>,
@@ -25,18 +54,114 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::{APPLICATION, ORGANISATION, QUALIFIER};
 
+/// A file in the local data directory, which is created if needed.
+pub(crate) fn data_file(name: &str) -> std::path::PathBuf {
+    let proj_dirs = ProjectDirs::from(QUALIFIER, ORGANISATION, APPLICATION)
+        .expect("Could not find config directory");
+    let data_dir = proj_dirs.data_local_dir();
+
+    // Ensure the directory exists
+    fs::create_dir_all(data_dir).ok();
+    data_dir.join(name)
+}
+
+/// A `file://` URI for `path`, percent-encoding everything but unreserved
+/// characters and separators.
+pub(crate) fn file_uri(path: &Path) -> String {
+    format!(
+        "file://{}",
+        encode(path.as_os_str().as_encoded_bytes(), b"/")
+    )
+}
+
+/// The path `target` refers to, for plain paths and `file://` URLs.
+pub(crate) fn local_path(target: &str) -> Option<String> {
+    if let Some(path) = target.strip_prefix("file://") {
+        // Skip the host, which is empty or `localhost` for local files.
+        let path = &path[path.find('/')?..];
+        return Some(percent_decode(path));
+    }
+    scheme(target).is_none().then(|| target.to_string())
+}
+
+/// The scheme of a URL, e.g. `https` or `mailto`.
+pub(crate) fn scheme(target: &str) -> Option<String> {
+    let (scheme, _) = target.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Writes `data` to a temporary file next to `path`, then renames it over
+/// `path`, so readers never see half of it.
+pub(crate) fn write_atomically(path: &Path, data: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    use anyhow::Context;
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let temporary = path.with_file_name(name);
+    fs::write(&temporary, data)
+        .and_then(|()| fs::rename(&temporary, path))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Percent-encodes everything but unreserved characters, for use as a
+/// URI component such as a query parameter.
+pub(crate) fn percent_encode(value: &str) -> String {
+    encode(value.as_bytes(), b"")
+}
+
+fn encode(bytes: &[u8], keep: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(*byte as char)
+            }
+            _ if keep.contains(byte) => encoded.push(*byte as char),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Undoes the `%XX` escapes of a URI component; invalid escapes are kept.
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
 impl AppState {
-    fn get_path() -> std::path::PathBuf {
-        let proj_dirs = ProjectDirs::from(QUALIFIER, ORGANISATION, APPLICATION)
-            .expect("Could not find config directory");
-        let data_dir = proj_dirs.data_local_dir();
-
-        // Ensure the directory exists
-        fs::create_dir_all(data_dir).ok();
-        data_dir.join("state.json")
+    pub(crate) fn get_path() -> std::path::PathBuf {
+        data_file("state.json")
     }
 
     pub fn record_usage(&mut self, entity: &super::Entity) {
+        if let super::Entity::Command(command) = entity
+            && command.private
+        {
+            return;
+        }
+
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .expect("Time went backwards")
@@ -74,3 +199,46 @@ impl AppState {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::Path};
+
+    use super::{file_uri, local_path, scheme, test_dir::TempDir, write_atomically};
+
+    #[test]
+    fn tells_urls_from_paths() {
+        assert_eq!(scheme("https://example.com").as_deref(), Some("https"));
+        assert_eq!(scheme("mailto:me@example.com").as_deref(), Some("mailto"));
+        assert_eq!(scheme("/tmp/a:b"), None);
+        assert_eq!(scheme("notes.txt"), None);
+
+        assert_eq!(local_path("/tmp/a:b").as_deref(), Some("/tmp/a:b"));
+        assert_eq!(local_path("file:///tmp/a%20b").as_deref(), Some("/tmp/a b"));
+        assert_eq!(
+            local_path("file://localhost/tmp/c").as_deref(),
+            Some("/tmp/c")
+        );
+        assert_eq!(local_path("https://example.com"), None);
+    }
+
+    #[test]
+    fn replaces_files_atomically() {
+        let dir = TempDir::new("atomic");
+        let path = dir.join("state.json");
+        write_atomically(&path, "first").unwrap();
+        write_atomically(&path, "second").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(&*dir).unwrap().count(), 1);
+
+        assert!(write_atomically(&dir.join("missing/state.json"), "").is_err());
+    }
+
+    #[test]
+    fn encodes_file_uris() {
+        assert_eq!(
+            file_uri(Path::new("/home/me/My Notes/é#1.md")),
+            "file:///home/me/My%20Notes/%C3%A9%231.md"
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::{env, path::PathBuf};
+
+fn home_dir(relative: &str) -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(relative))
+}
+
+fn dir_list(var: &str, default: &str) -> Vec<PathBuf> {
+    env::var(var)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| default.to_string())
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+pub fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir(".config"))
+}
+
+pub fn data_home() -> Option<PathBuf> {
+    env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir(".local/share"))
+}
+
+/// `XDG_CONFIG_HOME` followed by `XDG_CONFIG_DIRS`, most important first.
+pub fn config_dirs() -> Vec<PathBuf> {
+    config_home()
+        .into_iter()
+        .chain(dir_list("XDG_CONFIG_DIRS", "/etc/xdg"))
+        .collect()
+}
+
+/// `XDG_DATA_HOME` followed by `XDG_DATA_DIRS`, most important first.
+pub fn data_dirs() -> Vec<PathBuf> {
+    data_home()
+        .into_iter()
+        .chain(dir_list("XDG_DATA_DIRS", "/usr/local/share:/usr/share"))
+        .collect()
+}
+
+/// The lowercased entries of `XDG_CURRENT_DESKTOP`, e.g. `["gnome"]`.
+pub fn current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
@@ -0,0 +1,537 @@
+use std::{collections::BTreeMap, fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+
+use crate::{APPLICATION, ORGANISATION, QUALIFIER};
+
+/// User configuration, read from `config.toml` in the config directory.
+///
+/// ```toml
+/// [keybindings]
+/// preset = "emacs"
+///
+/// [keybindings.bindings]
+/// select_next = ["down", "ctrl+n", "ctrl+j"]
+/// launch_1 = ["alt+1", "ctrl+1"]
+///
+/// [sections]
+/// suggestions = 5
+///
+/// [sections.limits]
+/// commands = 10
+///
+/// [launch]
+/// strategy = "systemd"
+///
+/// [launch.overrides."org.mozilla.firefox"]
+/// env = { MOZ_ENABLE_WAYLAND = "1" }
+/// args = ["--private-window"]
+///
+/// [launch.terminal]
+/// preferred = "myterm"
+///
+/// [launch.terminal.profiles.myterm]
+/// exe = "myterm"
+/// exec_flag = "-e"
+/// title_flag = "--title="
+///
+/// [shell]
+/// keep_terminal_open = true
+///
+/// [files]
+/// roots = ["~/Documents", "~/Projects"]
+/// exclude = ["node_modules/", "*.o"]
+/// show_hidden = false
+///
+/// [recent]
+/// max_results = 5
+///
+/// [browsers]
+/// history = false
+///
+/// [[browsers.profiles]]
+/// browser = "chromium"
+/// path = "~/.config/chromium-work/Default"
+/// command = ["chromium", "--user-data-dir=/home/me/.config/chromium-work"]
+///
+/// [[quicklinks]]
+/// name = "Jira Ticket"
+/// url = "https://jira.example.com/browse/{query}"
+///
+/// [[quicklinks]]
+/// name = "Search docs.rs"
+/// alias = "drs"
+/// url = "https://docs.rs/releases/search?query={argument}"
+/// app = "org.mozilla.firefox"
+///
+/// [fallbacks]
+/// min_results = 1
+///
+/// [[fallbacks.commands]]
+/// type = "web-search"
+/// name = "Search Kagi"
+/// url = "https://kagi.com/search?q={query}"
+///
+/// [[fallbacks.commands]]
+/// type = "create-note"
+/// folder = "~/Notes"
+///
+/// [clipboard]
+/// max_entries = 500
+/// ignore_apps = ["org.keepassxc.KeePassXC"]
+///
+/// [snippets]
+/// type_command = []
+///
+/// [emoji]
+/// skin_tone = "medium-light"
+///
+/// [logging]
+/// filter = "info,core=debug"
+/// json = true
+/// ```
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub keybindings: KeybindingsConfig,
+    pub sections: SectionsConfig,
+    pub launch: LaunchConfig,
+    pub shell: ShellConfig,
+    pub files: FilesConfig,
+    pub recent: RecentConfig,
+    pub browsers: BrowsersConfig,
+    pub quicklinks: Vec<Quicklink>,
+    pub fallbacks: FallbacksConfig,
+    pub clipboard: ClipboardConfig,
+    pub snippets: SnippetsConfig,
+    pub emoji: EmojiConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct KeybindingsConfig {
+    pub preset: KeybindingPreset,
+    /// Action name to the key chords that trigger it, e.g.
+    /// `submit = ["enter", "ctrl+m"]`. Replaces the preset's chords for
+    /// that action.
+    pub bindings: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeybindingPreset {
+    #[default]
+    Default,
+    Emacs,
+    Vim,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SectionsConfig {
+    /// How many of the most used entries are pulled into "Suggestions"
+    /// while the query is empty.
+    pub suggestions: usize,
+    /// Maximum results per section, keyed by [`crate::Section::key`].
+    pub limits: BTreeMap<String, usize>,
+}
+
+impl Default for SectionsConfig {
+    fn default() -> Self {
+        Self {
+            suggestions: 3,
+            limits: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct LaunchConfig {
+    pub strategy: LaunchStrategy,
+    /// Per-application tweaks, keyed by the application id shown in the
+    /// detail pane (the desktop file id on Linux).
+    pub overrides: BTreeMap<String, LaunchOverride>,
+    pub terminal: TerminalConfig,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct LaunchOverride {
+    /// Environment variables set for the launched process.
+    pub env: BTreeMap<String, String>,
+    /// Arguments appended to the application's command line.
+    pub args: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct TerminalConfig {
+    /// Name of the profile to use, from `profiles` or the built-in ones.
+    /// Without it `xdg-terminal-exec` and `xdg-terminals.list` decide.
+    pub preferred: Option<String>,
+    pub profiles: BTreeMap<String, TerminalProfile>,
+}
+
+/// How to run a command in a terminal emulator. Flags ending in `=` take
+/// their value in the same argument, e.g. `--title=Files`.
+#[derive(Serialize, Deserialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct TerminalProfile {
+    pub exe: String,
+    /// Arguments right after `exe`, e.g. `["start"]` for wezterm.
+    pub args: Vec<String>,
+    /// Precedes the command, e.g. `-e` or `--`. Empty when the command
+    /// follows directly.
+    pub exec_flag: String,
+    pub title_flag: Option<String>,
+    pub working_dir_flag: Option<String>,
+    /// Keeps the window open after the command exits.
+    pub hold_flag: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchStrategy {
+    /// Spawn applications as children of the launcher.
+    #[default]
+    Spawn,
+    /// Run each application in its own systemd user scope, falling back to
+    /// `Spawn` without `systemd-run` or a running user manager.
+    Systemd,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct ShellConfig {
+    /// Keep the terminal open after a `> command` run in it exits.
+    pub keep_terminal_open: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FilesConfig {
+    pub enabled: bool,
+    /// Directories to index, with `~` standing for the home directory.
+    pub roots: Vec<String>,
+    /// `.gitignore`-style patterns left out of the index, on top of the
+    /// `.gitignore` and `.ignore` files found under the roots.
+    pub exclude: Vec<String>,
+    pub show_hidden: bool,
+    /// Shorter queries don't search files.
+    pub min_query_length: usize,
+    pub max_results: usize,
+}
+
+impl Default for FilesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            roots: vec!["~".to_string()],
+            exclude: Vec::new(),
+            show_hidden: false,
+            min_query_length: 2,
+            max_results: 20,
+        }
+    }
+}
+
+/// Recently used files, as recorded by GTK applications.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RecentConfig {
+    pub enabled: bool,
+    pub max_results: usize,
+}
+
+impl Default for RecentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_results: 8,
+        }
+    }
+}
+
+/// Bookmarks and history of Firefox and Chromium-based browsers.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BrowsersConfig {
+    pub enabled: bool,
+    /// Search visited pages as well as bookmarks.
+    pub history: bool,
+    /// How many of the most recently visited pages are read per profile.
+    pub history_limit: usize,
+    /// Profiles to read. When empty, the profiles of the installed
+    /// browsers are found automatically.
+    pub profiles: Vec<BrowserProfile>,
+    /// Shorter queries don't search the browsers.
+    pub min_query_length: usize,
+    pub max_results: usize,
+}
+
+impl Default for BrowsersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            history: true,
+            history_limit: 5000,
+            profiles: Vec::new(),
+            min_query_length: 2,
+            max_results: 10,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BrowserProfile {
+    pub browser: BrowserKind,
+    /// The profile directory, with `~` standing for the home directory.
+    pub path: String,
+    /// Shown next to the results, instead of the directory name.
+    pub name: Option<String>,
+    /// Opens a URL in this profile once the URL is appended. Defaults to
+    /// the browser's usual executable, told to use this profile.
+    pub command: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BrowserKind {
+    Firefox,
+    /// Chrome, Chromium, Brave, Edge, Vivaldi and other Chromium-based
+    /// browsers.
+    Chromium,
+}
+
+/// A URL or path listed as a command, opened with its placeholders filled
+/// in: `{query}` or `{argument}` for text asked for when it runs,
+/// `{clipboard}` for the clipboard's text and `{date}` for today's date,
+/// optionally formatted like `{date:%d.%m.%Y}`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Quicklink {
+    pub name: String,
+    pub url: String,
+    pub alias: Option<String>,
+    /// Id of the application to open it with instead of the default one.
+    pub app: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// A `RUST_LOG`-style filter. The `RUST_LOG` variable takes precedence.
+    pub filter: String,
+    /// Write the log file as JSON lines instead of plain text.
+    pub json: bool,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            filter: "info".to_string(),
+            json: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> PathBuf {
+        let proj_dirs = ProjectDirs::from(QUALIFIER, ORGANISATION, APPLICATION)
+            .expect("Could not find config directory");
+        proj_dirs.config_dir().join("config.toml")
+    }
+
+    /// Reads the config file, falling back to defaults when it doesn't exist.
+    pub fn load() -> Result<Self> {
+        let path = Self::path();
+        match fs::read_to_string(&path) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Invalid config file {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct FallbacksConfig {
+    /// The fallback commands are listed when fewer results than this
+    /// match a query.
+    pub min_results: usize,
+    /// Listed in this order, each running with the query as its argument.
+    pub commands: Vec<FallbackCommand>,
+}
+
+impl Default for FallbacksConfig {
+    fn default() -> Self {
+        Self {
+            min_results: 3,
+            commands: vec![
+                FallbackCommand::WebSearch {
+                    name: default_web_search_name(),
+                    url: default_web_search_url(),
+                    app: None,
+                },
+                FallbackCommand::SearchFiles,
+                FallbackCommand::Shell { terminal: false },
+            ],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum FallbackCommand {
+    /// Opens a search URL, a template like the quicklinks' ones.
+    WebSearch {
+        #[serde(default = "default_web_search_name")]
+        name: String,
+        #[serde(default = "default_web_search_url")]
+        url: String,
+        /// Id of the application to open it with instead of the default
+        /// browser.
+        app: Option<String>,
+    },
+    /// Lists the indexed files whose path contains the query.
+    SearchFiles,
+    /// Runs the query as a shell command.
+    Shell {
+        #[serde(default)]
+        terminal: bool,
+    },
+    /// Creates a note named after the query in `folder` and opens it.
+    CreateNote {
+        folder: String,
+        #[serde(default = "default_note_extension")]
+        extension: String,
+    },
+}
+
+fn default_web_search_name() -> String {
+    "Search the Web".to_string()
+}
+
+fn default_web_search_url() -> String {
+    "https://duckduckgo.com/?q={query}".to_string()
+}
+
+fn default_note_extension() -> String {
+    "md".to_string()
+}
+
+/// Clipboard history, recorded by running `iced_raycast --clipboard-daemon`
+/// in the background.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ClipboardConfig {
+    pub enabled: bool,
+    /// Query prefix listing the history, e.g. `clip invoice`.
+    pub prefix: String,
+    /// Entries kept, not counting pinned ones.
+    pub max_entries: usize,
+    /// Larger images aren't recorded, in bytes.
+    pub max_image_size: usize,
+    /// Run after copying an entry to paste it into the focused window.
+    /// Empty to only copy it.
+    pub paste_command: Vec<String>,
+    /// Applications whose copies aren't recorded, by app id or class.
+    pub ignore_apps: Vec<String>,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prefix: "clip ".to_string(),
+            max_entries: 200,
+            max_image_size: 10 * 1024 * 1024,
+            paste_command: ["wtype", "-M", "ctrl", "-k", "v", "-m", "ctrl"]
+                .map(String::from)
+                .to_vec(),
+            ignore_apps: Vec::new(),
+        }
+    }
+}
+
+/// Text snippets, saved in `snippets.json` in the data directory.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SnippetsConfig {
+    pub enabled: bool,
+    /// Types text into the focused window, given as its last argument.
+    /// Empty to copy snippets instead.
+    pub type_command: Vec<String>,
+    /// Moves the cursor one character left, run to put it where the
+    /// snippet's `{cursor}` is.
+    pub cursor_left_command: Vec<String>,
+}
+
+impl Default for SnippetsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            type_command: ["wtype", "--"].map(String::from).to_vec(),
+            cursor_left_command: ["wtype", "-k", "Left"].map(String::from).to_vec(),
+        }
+    }
+}
+
+/// The emoji and symbol picker.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EmojiConfig {
+    pub enabled: bool,
+    /// Query prefix listing emoji, e.g. `:smile`.
+    pub prefix: String,
+    /// Applied to the emoji that come in several skin tones.
+    pub skin_tone: Option<SkinTone>,
+    /// Emoji per row of the grid.
+    pub columns: usize,
+}
+
+impl Default for EmojiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            prefix: ":".to_string(),
+            skin_tone: None,
+            columns: 8,
+        }
+    }
+}
+
+/// The Fitzpatrick skin tone modifiers, lightest first.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SkinTone {
+    Light,
+    MediumLight,
+    Medium,
+    MediumDark,
+    Dark,
+}
+
+impl SkinTone {
+    pub const ALL: [SkinTone; 5] = [
+        SkinTone::Light,
+        SkinTone::MediumLight,
+        SkinTone::Medium,
+        SkinTone::MediumDark,
+        SkinTone::Dark,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SkinTone::Light => "Light",
+            SkinTone::MediumLight => "Medium-Light",
+            SkinTone::Medium => "Medium",
+            SkinTone::MediumDark => "Medium-Dark",
+            SkinTone::Dark => "Dark",
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Extra information about an entity, shown in the detail pane next to the
+/// result list. Applications fill it from their desktop entry; plugins can
+/// supply their own.
+#[derive(Debug, Clone, Default)]
+pub struct Detail {
+    /// Free-form markdown rendered at the top of the pane.
+    pub markdown: Option<String>,
+    /// Label/value rows rendered below the markdown.
+    pub metadata: Vec<(String, String)>,
+}
+
+impl Detail {
+    pub fn with_markdown(mut self, markdown: impl Into<String>) -> Self {
+        self.markdown = Some(markdown.into());
+        self
+    }
+
+    pub fn with_row(mut self, label: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.push((label.into(), value.into()));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.markdown.is_none() && self.metadata.is_empty()
+    }
+}
+
+/// `text` as a markdown code block, so it's shown verbatim.
+pub(crate) fn code_block(text: &str) -> String {
+    // A fence longer than any run of backticks in the text.
+    let longest = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{fence}\n{text}\n{fence}")
+}
+
+/// Formats a unix timestamp relative to now, e.g. "3 hours ago".
+pub(crate) fn format_relative_time(timestamp: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let elapsed = now.saturating_sub(timestamp);
+
+    let (value, unit) = match elapsed {
+        0..60 => return "just now".to_string(),
+        60..3_600 => (elapsed / 60, "minute"),
+        3_600..86_400 => (elapsed / 3_600, "hour"),
+        86_400..2_592_000 => (elapsed / 86_400, "day"),
+        2_592_000..31_536_000 => (elapsed / 2_592_000, "month"),
+        _ => (elapsed / 31_536_000, "year"),
+    };
+
+    format!("{value} {unit}{} ago", if value == 1 { "" } else { "s" })
+}
+
+/// Formats a size in bytes, e.g. "1.5 KB".
+pub(crate) fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} bytes");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::{Detail, format_relative_time, format_size};
+
+    #[test]
+    fn builds_details_and_relative_times() {
+        assert!(Detail::default().is_empty());
+        let detail = Detail::default()
+            .with_row("Plugin", "Files")
+            .with_markdown("# Title");
+        assert!(!detail.is_empty());
+        assert_eq!(detail.markdown.as_deref(), Some("# Title"));
+        assert_eq!(detail.metadata, [("Plugin".into(), "Files".into())]);
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(format_relative_time(now), "just now");
+        assert_eq!(format_relative_time(now - 60), "1 minute ago");
+        assert_eq!(format_relative_time(now - 3 * 3_600), "3 hours ago");
+        assert_eq!(format_relative_time(now - 2 * 86_400), "2 days ago");
+        assert_eq!(format_relative_time(0).split(' ').nth(1), Some("years"));
+    }
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(512), "512 bytes");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024 * 1024), "5.0 GB");
+    }
+}
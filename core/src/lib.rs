@@ -1,15 +1,40 @@
+use std::time::Instant;
+
 use crate::plugins::CommandEntity;
+pub use crate::plugins::{Action, Outcome, Plugins, Provider, SecondaryAction, ShellMode};
 
-pub use crate::common::Image;
+pub use crate::common::{IconSize, Image};
+pub use activation::ActivationContext;
 use anyhow::Result;
 pub use application::App;
 pub use application::Application;
+pub use application::spawn;
+pub use clipboard::run_clipboard_daemon;
 pub use common::AppState;
-
+pub use config::{
+    BrowserKind, BrowserProfile, BrowsersConfig, ClipboardConfig, Config, EmojiConfig,
+    FallbackCommand, FallbacksConfig, FilesConfig, KeybindingPreset, KeybindingsConfig,
+    LaunchConfig, LaunchOverride, LaunchStrategy, LoggingConfig, Quicklink, RecentConfig,
+    SectionsConfig, ShellConfig, SkinTone, SnippetsConfig, TerminalConfig, TerminalProfile,
+};
+pub use detail::Detail;
+pub use logging::init_logging;
+pub use open::{copy, open, open_with, reveal};
+pub use section::Section;
+
+mod activation;
 mod application;
+mod clipboard;
 mod common;
+mod config;
+mod detail;
+mod logging;
+#[cfg(target_os = "linux")]
+mod mime;
+mod open;
 mod plugins;
 pub mod search;
+mod section;
 
 const QUALIFIER: &str = "com";
 const ORGANISATION: &str = "lcvitor";
@@ -36,39 +61,99 @@ impl Entity {
         }
     }
 
-    pub fn icon(&self) -> Option<Image> {
+    pub fn section(&self) -> Section {
+        match self {
+            Entity::Application(_) => Section::Applications,
+            Entity::Command(cmd) => cmd
+                .plugin
+                .clone()
+                .map_or(Section::Commands, Section::Plugin),
+        }
+    }
+
+    /// Resolves the entity's icon for `icon_size`. May block on disk I/O.
+    pub fn icon(&self, icon_size: IconSize) -> Option<Image> {
         match self {
-            Entity::Application(app) => app.icon(),
-            Entity::Command(cmd) => cmd.image.clone(),
+            Entity::Application(app) => app.icon(icon_size),
+            Entity::Command(cmd) => cmd
+                .image
+                .clone()
+                .or_else(|| application::themed_icon(&cmd.icon_names, icon_size)),
         }
     }
 
-    pub fn execute(&self, argument: Option<String>) -> Result<()> {
+    /// Builds the content of the detail pane, including usage stats.
+    pub fn detail(&self, app_state: &AppState) -> Detail {
+        let mut detail = match self {
+            Entity::Application(app) => app.detail(),
+            Entity::Command(cmd) => cmd.detail.clone().unwrap_or_default(),
+        };
+
+        if let Some(usage) = app_state.usage_stats.get(self.name()) {
+            detail = detail
+                .with_row("Launches", usage.count.to_string())
+                .with_row("Last used", detail::format_relative_time(usage.last_used));
+        }
+
+        detail
+    }
+
+    /// Runs the entity with the values of its [`arguments`](Self::arguments).
+    pub fn execute(
+        &self,
+        arguments: Vec<String>,
+        launch: &LaunchConfig,
+        activation: &ActivationContext,
+    ) -> Result<Outcome> {
         match self {
-            Entity::Application(app) => app.execute(argument),
+            Entity::Application(app) => app
+                .execute(arguments.into_iter().next(), launch, activation)
+                .map(|()| Outcome::Done),
             Entity::Command(cmd) => {
-                println!(
-                    "Executing command {} with argument {:?}",
-                    cmd.name, argument
-                );
-                Ok(())
+                tracing::info!(command = %cmd.name, ?arguments, "Executing command");
+                cmd.action.run(arguments, launch, activation)
             }
         }
     }
 
-    pub fn needs_argument(&self) -> bool {
+    /// Alternatives to running the entity, listed in the actions panel.
+    pub fn secondary_actions(&self) -> &[SecondaryAction] {
         match self {
-            Entity::Application(_) => false,
-            Entity::Command(cmd) => cmd.needs_argument,
+            Entity::Application(_) => &[],
+            Entity::Command(cmd) => &cmd.secondary_actions,
+        }
+    }
+
+    /// Text shown in place of the icon, like an emoji.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Entity::Application(_) => None,
+            Entity::Command(cmd) => cmd.symbol.as_deref(),
+        }
+    }
+
+    /// Names of the arguments asked for before running it.
+    pub fn arguments(&self) -> &[String] {
+        match self {
+            Entity::Application(_) => &[],
+            Entity::Command(cmd) => &cmd.arguments,
         }
     }
 }
 
 pub fn get_entities() -> Vec<Entity> {
+    let _span = tracing::info_span!("get_entities").entered();
+
+    let started = Instant::now();
     let mut entities: Vec<Entity> = App::lookup_applications()
         .into_iter()
         .map(Entity::Application)
         .collect();
+    tracing::info!(
+        count = entities.len(),
+        elapsed = ?started.elapsed(),
+        "Scanned applications"
+    );
 
     let mut command_id_counter = 0;
 
@@ -78,7 +163,18 @@ pub fn get_entities() -> Vec<Entity> {
         alias: None,
         description: Some("This is the first fake command.".to_string()),
         image: None,
-        needs_argument: false,
+        icon_names: Vec::new(),
+        arguments: Vec::new(),
+        plugin: None,
+        detail: Some(
+            Detail::default()
+                .with_markdown("A **fake** command used to try out the detail pane.")
+                .with_row("Plugin", "Examples"),
+        ),
+        private: false,
+        symbol: None,
+        action: Action::None,
+        secondary_actions: Vec::new(),
     };
     command_id_counter += 1;
 
@@ -88,7 +184,14 @@ pub fn get_entities() -> Vec<Entity> {
         alias: Some("fct".to_string()),
         description: Some("This is the second fake command, with an alias.".to_string()),
         image: None,
-        needs_argument: false,
+        icon_names: Vec::new(),
+        arguments: Vec::new(),
+        plugin: None,
+        detail: None,
+        private: false,
+        symbol: None,
+        action: Action::None,
+        secondary_actions: Vec::new(),
     };
     command_id_counter += 1;
 
@@ -98,7 +201,14 @@ pub fn get_entities() -> Vec<Entity> {
         alias: None,
         description: Some("A third example of a fake command.".to_string()),
         image: None,
-        needs_argument: true, // This one needs an argument
+        icon_names: Vec::new(),
+        arguments: vec!["Argument".to_string()], // This one needs an argument
+        plugin: Some("Examples".to_string()),
+        detail: None,
+        private: false,
+        symbol: None,
+        action: Action::None,
+        secondary_actions: Vec::new(),
     };
 
     entities.push(Entity::Command(fake_command_1));
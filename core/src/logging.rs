@@ -0,0 +1,205 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use tracing_subscriber::{
+    EnvFilter, Layer, filter::ParseError, fmt, layer::SubscriberExt, util::SubscriberInitExt,
+};
+
+use crate::{APPLICATION, ORGANISATION, QUALIFIER, config::LoggingConfig};
+
+/// Size at which the log file is rotated.
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+/// Rotated files kept next to the current one (`iced_raycast.log.1`, ...).
+const KEPT_LOGS: usize = 3;
+
+/// The log file in the data directory.
+fn log_path() -> PathBuf {
+    let proj_dirs = ProjectDirs::from(QUALIFIER, ORGANISATION, APPLICATION)
+        .expect("Could not find data directory");
+    proj_dirs.data_dir().join("logs").join("iced_raycast.log")
+}
+
+/// Installs the global `tracing` subscriber, logging to stderr and to a
+/// rotating file in the data directory.
+///
+/// `RUST_LOG` overrides `config.filter`. An invalid filter falls back to
+/// `info` and is reported once the subscriber is running.
+pub fn init_logging(config: &LoggingConfig) -> Result<()> {
+    let directives = filter_directives(config, std::env::var("RUST_LOG").ok());
+    let (filter, filter_error) = parse_filter(&directives);
+
+    let writer = Mutex::new(RotatingFile::new(log_path(), MAX_LOG_BYTES, KEPT_LOGS));
+    let file_layer = if config.json {
+        fmt::layer().json().with_writer(writer).boxed()
+    } else {
+        fmt::layer().with_ansi(false).with_writer(writer).boxed()
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(io::stderr))
+        .with(file_layer)
+        .try_init()
+        .context("Failed to install the log subscriber")?;
+
+    if let Some(e) = filter_error {
+        tracing::warn!("Invalid log filter {directives:?}: {e}");
+    }
+    Ok(())
+}
+
+/// The filter to use: `rust_log` when set, otherwise the configured one.
+fn filter_directives(config: &LoggingConfig, rust_log: Option<String>) -> String {
+    rust_log.unwrap_or_else(|| config.filter.clone())
+}
+
+/// Parses `directives`, falling back to `info` and returning the error if
+/// they are invalid.
+fn parse_filter(directives: &str) -> (EnvFilter, Option<ParseError>) {
+    match EnvFilter::try_new(directives) {
+        Ok(filter) => (filter, None),
+        Err(e) => (EnvFilter::new("info"), Some(e)),
+    }
+}
+
+/// An append-only file that is renamed to `<name>.1` once it reaches
+/// `max_bytes`, shifting older files up and dropping the ones past `keep`.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    pub fn new(path: PathBuf, max_bytes: u64, keep: usize) -> Self {
+        Self {
+            path,
+            max_bytes,
+            keep,
+            file: None,
+            size: 0,
+        }
+    }
+
+    fn open(&mut self) -> io::Result<&mut File> {
+        if self.file.is_none() {
+            if let Some(parent) = self.path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            self.size = file.metadata()?.len();
+            self.file = Some(file);
+        }
+        Ok(self.file.as_mut().expect("log file was just opened"))
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file = None;
+        for index in (1..self.keep).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        if self.keep == 0 {
+            fs::remove_file(&self.path)
+        } else {
+            fs::rename(&self.path, rotated_path(&self.path, 1))
+        }
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{index}"));
+    path.with_file_name(name)
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.open()?;
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.open()?.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.file.as_mut() {
+            Some(file) => file.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, io::Write};
+
+    use super::{RotatingFile, filter_directives, parse_filter, rotated_path};
+    use crate::common::test_dir::TempDir;
+    use crate::config::{Config, LoggingConfig};
+
+    #[test]
+    fn reads_filter_from_config_unless_rust_log_is_set() {
+        let config: Config =
+            toml::from_str("[logging]\nfilter = \"warn,core=debug\"\njson = true").unwrap();
+        assert!(config.logging.json);
+        assert_eq!(filter_directives(&config.logging, None), "warn,core=debug");
+        assert_eq!(
+            filter_directives(&config.logging, Some("trace".to_string())),
+            "trace"
+        );
+
+        let defaults = LoggingConfig::default();
+        assert_eq!(filter_directives(&defaults, None), "info");
+        assert!(!defaults.json);
+    }
+
+    #[test]
+    fn falls_back_to_info_on_invalid_filters() {
+        let (filter, error) = parse_filter("warn,core=debug");
+        assert!(error.is_none());
+        assert_eq!(filter.to_string(), "core=debug,warn");
+
+        let (filter, error) = parse_filter("core=loudest");
+        assert!(error.is_some());
+        assert_eq!(filter.to_string(), "info");
+    }
+
+    #[test]
+    fn rotates_and_keeps_limited_history() {
+        let dir = TempDir::new("log");
+        let path = dir.join("test.log");
+
+        let mut file = RotatingFile::new(path.clone(), 10, 2);
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 1)).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path, 2)).unwrap(),
+            "second\n"
+        );
+        assert!(!rotated_path(&path, 3).exists());
+    }
+}
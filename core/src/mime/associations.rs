@@ -0,0 +1,179 @@
+//! User and system choices of applications per MIME type, from the
+//! `mimeapps.list` files.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use super::database::MimeDatabase;
+use crate::common::xdg;
+
+#[derive(Debug, Default)]
+pub(crate) struct Associations {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, HashSet<String>>,
+}
+
+impl Associations {
+    /// Reads the `mimeapps.list` files of the XDG config and data dirs.
+    pub fn from_env(db: &MimeDatabase) -> Self {
+        let desktops = xdg::current_desktops();
+        let config = xdg::config_dirs();
+        let data = xdg::data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("applications"));
+        Self::load(&list_files(config.into_iter().chain(data), &desktops), db)
+    }
+
+    /// Merges `files`, most important first. Types are stored unaliased.
+    pub fn load(files: &[PathBuf], db: &MimeDatabase) -> Self {
+        let mut associations = Self::default();
+        for file in files {
+            if let Ok(content) = fs::read_to_string(file) {
+                associations.merge(&content, db);
+            }
+        }
+        associations
+    }
+
+    /// Defaults and additions only count when no more important file
+    /// removed them; a removal applies to this file and the ones after it.
+    fn merge(&mut self, content: &str, db: &MimeDatabase) {
+        let mut section = "";
+        let mut removed_here: Vec<(String, Vec<String>)> = Vec::new();
+
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = name;
+                continue;
+            }
+            let Some((mime, ids)) = line.split_once('=') else {
+                continue;
+            };
+            let mime = db.unalias(mime.trim()).to_string();
+            // The lists name desktop files; applications go by the file's
+            // id, without the extension.
+            let ids: Vec<String> = ids
+                .split(';')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| id.strip_suffix(".desktop").unwrap_or(id).to_string())
+                .collect();
+
+            let target = match section {
+                "Default Applications" => &mut self.defaults,
+                "Added Associations" => &mut self.added,
+                "Removed Associations" => {
+                    removed_here.push((mime, ids));
+                    continue;
+                }
+                _ => continue,
+            };
+            let removed = self.removed.get(&mime);
+            let list = target.entry(mime.clone()).or_default();
+            for id in ids {
+                if !list.contains(&id) && !removed.is_some_and(|r| r.contains(&id)) {
+                    list.push(id);
+                }
+            }
+        }
+
+        for (mime, ids) in removed_here {
+            self.removed.entry(mime).or_default().extend(ids);
+        }
+    }
+
+    pub fn defaults(&self, mime: &str) -> &[String] {
+        self.defaults.get(mime).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn added(&self, mime: &str) -> &[String] {
+        self.added.get(mime).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn is_removed(&self, mime: &str, id: &str) -> bool {
+        self.removed.get(mime).is_some_and(|ids| ids.contains(id))
+    }
+}
+
+/// `$desktop-mimeapps.list` then `mimeapps.list` in each of `dirs`, in
+/// the order the spec gives them precedence.
+fn list_files(dirs: impl Iterator<Item = PathBuf>, desktops: &[String]) -> Vec<PathBuf> {
+    dirs.flat_map(|dir| {
+        desktops
+            .iter()
+            .map(|desktop| format!("{desktop}-mimeapps.list"))
+            .chain(["mimeapps.list".to_string()])
+            .map(move |name| dir.join(name))
+            .collect::<Vec<_>>()
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf};
+
+    use super::{Associations, list_files};
+    use crate::common::test_dir::TempDir;
+    use crate::mime::database::MimeDatabase;
+
+    #[test]
+    fn orders_desktop_specific_lists_first() {
+        let files = list_files(
+            [PathBuf::from("/config"), PathBuf::from("/data")].into_iter(),
+            &["gnome".to_string()],
+        );
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("/config/gnome-mimeapps.list"),
+                PathBuf::from("/config/mimeapps.list"),
+                PathBuf::from("/data/gnome-mimeapps.list"),
+                PathBuf::from("/data/mimeapps.list"),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_lists_by_precedence() {
+        let root = TempDir::new("mimeapps");
+        fs::create_dir_all(root.join("mime")).unwrap();
+        fs::write(root.join("mime/aliases"), "text/xml application/xml\n").unwrap();
+        let db = MimeDatabase::load(&[root.join("mime")]);
+
+        let user = root.join("user.list");
+        let system = root.join("system.list");
+        fs::write(
+            &user,
+            "[Default Applications]\n\
+             text/plain=editor.desktop;\n\
+             [Added Associations]\n\
+             text/xml=viewer.desktop;\n\
+             [Removed Associations]\n\
+             text/plain=ide.desktop;\n",
+        )
+        .unwrap();
+        fs::write(
+            &system,
+            "# system defaults\n\
+             [Default Applications]\n\
+             text/plain=ide.desktop;notes.desktop\n\
+             application/xml=browser.desktop\n",
+        )
+        .unwrap();
+
+        let associations = Associations::load(&[user, system], &db);
+        assert_eq!(associations.defaults("text/plain"), ["editor", "notes"]);
+        assert_eq!(associations.defaults("application/xml"), ["browser"]);
+        assert_eq!(associations.added("application/xml"), ["viewer"]);
+        assert!(associations.is_removed("text/plain", "ide"));
+        assert!(!associations.is_removed("text/plain", "editor"));
+    }
+}
@@ -0,0 +1,460 @@
+//! The shared-mime-info database: file name globs, content magic, aliases
+//! and the subclass hierarchy.
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::{self, File},
+    io::Read,
+    os::unix::fs::FileTypeExt,
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use super::magic::{self, MagicSection};
+
+pub(crate) const DIRECTORY: &str = "inode/directory";
+const TEXT: &str = "text/plain";
+const BINARY: &str = "application/octet-stream";
+
+/// Files larger than this aren't read further for sniffing, whatever the
+/// magic rules ask for.
+const MAX_SNIFF: usize = 64 * 1024;
+
+#[derive(Debug)]
+struct Glob {
+    weight: u32,
+    mime: String,
+    pattern: String,
+}
+
+pub(crate) struct MimeDatabase {
+    globs: Vec<Glob>,
+    matcher: GlobSet,
+    magic: Vec<MagicSection>,
+    sniff_len: usize,
+    aliases: HashMap<String, String>,
+    parents: HashMap<String, Vec<String>>,
+    icons: HashMap<String, String>,
+    generic_icons: HashMap<String, String>,
+}
+
+impl MimeDatabase {
+    /// Loads the `mime` directories under the XDG data dirs.
+    pub fn from_env() -> Self {
+        let dirs: Vec<PathBuf> = crate::common::xdg::data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("mime"))
+            .collect();
+        Self::load(&dirs)
+    }
+
+    /// Loads and merges `dirs`, most important first. Missing files are
+    /// skipped.
+    pub fn load(dirs: &[PathBuf]) -> Self {
+        let mut globs: Vec<(Glob, bool)> = Vec::new();
+        let mut magic = Vec::new();
+        let mut aliases = HashMap::new();
+        let mut parents: HashMap<String, Vec<String>> = HashMap::new();
+        let mut icons = HashMap::new();
+        let mut generic_icons = HashMap::new();
+
+        // Less important directories first, so `__NOGLOBS__` in a more
+        // important one can drop what they defined.
+        for dir in dirs.iter().rev() {
+            if let Ok(content) = fs::read_to_string(dir.join("globs2")) {
+                parse_globs(&content, &mut globs);
+            }
+            if let Some(sections) = fs::read(dir.join("magic"))
+                .ok()
+                .and_then(|data| magic::parse(&data))
+            {
+                magic.extend(sections);
+            }
+            for (alias, mime) in read_pairs(&dir.join("aliases")) {
+                aliases.insert(alias, mime);
+            }
+            icons.extend(read_icons(&dir.join("icons")));
+            generic_icons.extend(read_icons(&dir.join("generic-icons")));
+            for (mime, parent) in read_pairs(&dir.join("subclasses")) {
+                let list = parents.entry(mime).or_default();
+                if !list.contains(&parent) {
+                    list.push(parent);
+                }
+            }
+        }
+
+        let mut builder = GlobSetBuilder::new();
+        let mut kept = Vec::with_capacity(globs.len());
+        for (glob, case_sensitive) in globs {
+            let Ok(pattern) = GlobBuilder::new(&glob.pattern)
+                .case_insensitive(!case_sensitive)
+                .literal_separator(true)
+                .backslash_escape(true)
+                .build()
+            else {
+                tracing::debug!(pattern = %glob.pattern, "Skipping invalid MIME glob");
+                continue;
+            };
+            builder.add(pattern);
+            kept.push(glob);
+        }
+        let matcher = builder.build().unwrap_or_else(|e| {
+            tracing::warn!("Failed to build the MIME glob set: {e}");
+            GlobSet::empty()
+        });
+
+        // The sort is stable, so among equal priorities the more important
+        // directory, loaded last, comes first once reversed.
+        magic.reverse();
+        magic.sort_by_key(|section| std::cmp::Reverse(section.priority));
+        let sniff_len = magic
+            .iter()
+            .map(MagicSection::extent)
+            .max()
+            .unwrap_or(0)
+            .min(MAX_SNIFF);
+
+        Self {
+            globs: kept,
+            matcher,
+            magic,
+            sniff_len,
+            aliases,
+            parents,
+            icons,
+            generic_icons,
+        }
+    }
+
+    /// The MIME type of the file at `path`, from its name and, when that's
+    /// not conclusive, its content. Only regular files are read: opening a
+    /// FIFO would block until something writes to it.
+    pub fn guess(&self, path: &Path) -> String {
+        if let Ok(metadata) = fs::metadata(path) {
+            let file_type = metadata.file_type();
+            let special = if file_type.is_dir() {
+                Some(DIRECTORY)
+            } else if file_type.is_fifo() {
+                Some("inode/fifo")
+            } else if file_type.is_socket() {
+                Some("inode/socket")
+            } else if file_type.is_char_device() {
+                Some("inode/chardevice")
+            } else if file_type.is_block_device() {
+                Some("inode/blockdevice")
+            } else {
+                None
+            };
+            if let Some(special) = special {
+                return special.to_string();
+            }
+        }
+
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        let candidates = self.guess_name(&name);
+        if let [only] = candidates.as_slice() {
+            return only.to_string();
+        }
+
+        let data = read_head(path, self.sniff_len.max(TEXT_SNIFF));
+        if let Some(sniffed) = self.sniff(&data) {
+            // A glob match that is a more specific kind of what the content
+            // says wins, e.g. a `.svg` sniffed as XML.
+            return candidates
+                .iter()
+                .find(|candidate| **candidate == sniffed || self.is_subclass(candidate, sniffed))
+                .unwrap_or(&sniffed)
+                .to_string();
+        }
+        if let Some(first) = candidates.first() {
+            return first.to_string();
+        }
+        if looks_like_text(&data) { TEXT } else { BINARY }.to_string()
+    }
+
+    /// The types whose globs match `name` best: highest weight, then
+    /// longest pattern. More than one means the name is ambiguous.
+    pub fn guess_name(&self, name: &str) -> Vec<&str> {
+        let mut best: Option<(u32, usize)> = None;
+        let mut candidates: Vec<&str> = Vec::new();
+
+        for index in self.matcher.matches(name) {
+            let glob = &self.globs[index];
+            let rank = (glob.weight, glob.pattern.len());
+            if best.is_some_and(|best| rank < best) {
+                continue;
+            }
+            if best != Some(rank) {
+                best = Some(rank);
+                candidates.clear();
+            }
+            if !candidates.contains(&glob.mime.as_str()) {
+                candidates.push(&glob.mime);
+            }
+        }
+        candidates
+    }
+
+    /// The type of the highest-priority magic section matching `data`.
+    fn sniff(&self, data: &[u8]) -> Option<&str> {
+        if data.is_empty() {
+            return None;
+        }
+        self.magic
+            .iter()
+            .find(|section| section.matches(data))
+            .map(|section| section.mime.as_str())
+    }
+
+    /// The canonical name for `mime`, which may be an alias.
+    pub fn unalias<'a>(&'a self, mime: &'a str) -> &'a str {
+        self.aliases.get(mime).map_or(mime, String::as_str)
+    }
+
+    /// Every type `mime` is a kind of, nearest first. All text is also
+    /// `text/plain`, and everything but special files is a byte stream.
+    pub fn ancestors(&self, mime: &str) -> Vec<String> {
+        let mime = self.unalias(mime);
+        let mut seen: HashSet<&str> = HashSet::from([mime]);
+        let mut ancestors = Vec::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([mime]);
+
+        while let Some(current) = queue.pop_front() {
+            for parent in self.parents.get(current).into_iter().flatten() {
+                let parent = self.unalias(parent);
+                if seen.insert(parent) {
+                    ancestors.push(parent.to_string());
+                    queue.push_back(parent);
+                }
+            }
+        }
+
+        if mime.starts_with("text/") && !seen.contains(TEXT) {
+            ancestors.push(TEXT.to_string());
+        }
+        let special = ["inode/", "x-scheme-handler/"]
+            .iter()
+            .any(|prefix| mime.starts_with(prefix));
+        if !special && !seen.contains(BINARY) {
+            ancestors.push(BINARY.to_string());
+        }
+        ancestors
+    }
+
+    /// Icon theme names for `mime`, most specific first: its own icon, then
+    /// the generic icon of its kind, e.g. `text-x-python` then
+    /// `text-x-generic`.
+    pub fn icon_names(&self, mime: &str) -> Vec<String> {
+        let mime = self.unalias(mime);
+        let specific = self
+            .icons
+            .get(mime)
+            .cloned()
+            .unwrap_or_else(|| mime.replace('/', "-"));
+        let generic = self.generic_icons.get(mime).cloned().unwrap_or_else(|| {
+            let media = mime.split('/').next().unwrap_or_default();
+            format!("{media}-x-generic")
+        });
+
+        let mut names = vec![specific];
+        if !names.contains(&generic) {
+            names.push(generic);
+        }
+        names
+    }
+
+    fn is_subclass(&self, mime: &str, of: &str) -> bool {
+        self.ancestors(mime).iter().any(|ancestor| ancestor == of)
+    }
+}
+
+/// Enough of a file to tell text from binary when nothing else matched.
+const TEXT_SNIFF: usize = 512;
+
+fn read_head(path: &Path, len: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(len);
+    if let Ok(file) = File::open(path) {
+        let _ = file.take(len as u64).read_to_end(&mut data);
+    }
+    data
+}
+
+/// No NUL bytes and valid UTF-8, allowing for a character cut off at the
+/// end of the sample.
+fn looks_like_text(data: &[u8]) -> bool {
+    !data.contains(&0)
+        && match std::str::from_utf8(data) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        }
+}
+
+/// `weight:type:glob[:flags]` lines. `__NOGLOBS__` drops the globs that
+/// less important directories gave the type.
+fn parse_globs(content: &str, globs: &mut Vec<(Glob, bool)>) {
+    for line in content.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.splitn(4, ':');
+        let (Some(weight), Some(mime), Some(pattern)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let Ok(weight) = weight.parse() else { continue };
+
+        if pattern == "__NOGLOBS__" {
+            globs.retain(|(glob, _)| glob.mime != mime);
+            continue;
+        }
+        let case_sensitive = fields
+            .next()
+            .is_some_and(|flags| flags.split(',').any(|flag| flag == "cs"));
+
+        let duplicate = globs
+            .iter()
+            .any(|(glob, _)| glob.mime == mime && glob.pattern == pattern);
+        if !duplicate {
+            globs.push((
+                Glob {
+                    weight,
+                    mime: mime.to_string(),
+                    pattern: pattern.to_string(),
+                },
+                case_sensitive,
+            ));
+        }
+    }
+}
+
+/// The `first second` lines of `aliases` and `subclasses`.
+fn read_pairs(path: &Path) -> Vec<(String, String)> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let (first, second) = line.split_once(' ')?;
+            Some((first.to_string(), second.trim().to_string()))
+        })
+        .collect()
+}
+
+/// The `type:icon` lines of `icons` and `generic-icons`.
+fn read_icons(path: &Path) -> Vec<(String, String)> {
+    let Ok(content) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    content
+        .lines()
+        .filter_map(|line| {
+            let (mime, icon) = line.split_once(':')?;
+            Some((mime.to_string(), icon.trim().to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, os::unix::net::UnixListener};
+
+    use super::MimeDatabase;
+    use crate::common::test_dir::TempDir;
+
+    /// A magic file with a single `[50:type]` section matching `value` at
+    /// offset 0.
+    fn magic(mime: &str, value: &[u8]) -> Vec<u8> {
+        let mut data = format!("MIME-Magic\0\n[50:{mime}]\n>0=").into_bytes();
+        data.extend((value.len() as u16).to_be_bytes());
+        data.extend(value);
+        data.push(b'\n');
+        data
+    }
+
+    #[test]
+    fn guesses_from_globs_then_content() {
+        let root = TempDir::new("mime-db");
+        let (system, user, files) = (root.join("system"), root.join("user"), root.join("files"));
+        for dir in [&system, &user, &files] {
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        fs::write(
+            system.join("globs2"),
+            "# comment\n\
+             50:image/png:*.png\n\
+             50:text/x-c:*.c:cs\n\
+             50:text/x-c++:*.C:cs\n\
+             50:application/xml:*.xml\n\
+             50:image/svg+xml:*.svg\n\
+             50:text/x-old:*.old\n\
+             10:text/x-makefile:makefile\n\
+             60:application/x-one:*.amb\n\
+             60:application/x-two:*.amb\n",
+        )
+        .unwrap();
+        fs::write(user.join("globs2"), "50:text/x-old:__NOGLOBS__\n").unwrap();
+        fs::write(system.join("subclasses"), "image/svg+xml application/xml\n").unwrap();
+        fs::write(system.join("aliases"), "text/xml application/xml\n").unwrap();
+        fs::write(system.join("magic"), magic("application/xml", b"<?xml")).unwrap();
+        let db = MimeDatabase::load(&[user, system.clone()]);
+
+        assert_eq!(db.guess_name("Photo.PNG"), ["image/png"]);
+        assert_eq!(db.guess_name("main.c"), ["text/x-c"]);
+        assert_eq!(db.guess_name("main.C"), ["text/x-c++"]);
+        assert_eq!(db.guess_name("Makefile"), ["text/x-makefile"]);
+        assert_eq!(
+            db.guess_name("x.amb"),
+            ["application/x-one", "application/x-two"]
+        );
+        assert!(db.guess_name("notes.old").is_empty());
+
+        let write = |name: &str, content: &[u8]| {
+            let path = files.join(name);
+            fs::write(&path, content).unwrap();
+            path
+        };
+        // Conclusive globs aren't second-guessed.
+        assert_eq!(db.guess(&write("a.png", b"<?xml")), "image/png");
+        // Ambiguous or missing globs fall back to the content...
+        assert_eq!(db.guess(&write("b.amb", b"<?xml ")), "application/xml");
+        assert_eq!(db.guess(&write("c.amb", b"plain")), "application/x-one");
+        assert_eq!(db.guess(&write("README", b"hello\n")), "text/plain");
+        assert_eq!(
+            db.guess(&write("blob", b"\0\x01\x02")),
+            "application/octet-stream"
+        );
+        assert_eq!(db.guess(&files), "inode/directory");
+        let socket = files.join("c.amb");
+        fs::remove_file(&socket).unwrap();
+        let _listener = UnixListener::bind(&socket).unwrap();
+        assert_eq!(db.guess(&socket), "inode/socket");
+
+        assert_eq!(db.unalias("text/xml"), "application/xml");
+        assert_eq!(
+            db.ancestors("image/svg+xml"),
+            ["application/xml", "application/octet-stream"]
+        );
+        assert_eq!(
+            db.ancestors("text/x-c"),
+            ["text/plain", "application/octet-stream"]
+        );
+        assert!(db.ancestors("inode/directory").is_empty());
+
+        fs::write(system.join("generic-icons"), "text/x-c:text-x-script\n").unwrap();
+        fs::write(system.join("icons"), "application/x-one:one-icon\n").unwrap();
+        let db = MimeDatabase::load(&[system]);
+        assert_eq!(db.icon_names("text/x-c"), ["text-x-c", "text-x-script"]);
+        assert_eq!(db.icon_names("image/png"), ["image-png", "image-x-generic"]);
+        assert_eq!(
+            db.icon_names("application/x-one"),
+            ["one-icon", "application-x-generic"]
+        );
+    }
+}
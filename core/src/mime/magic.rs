@@ -0,0 +1,271 @@
+//! The binary `magic` file of shared-mime-info, used to recognise files by
+//! their content.
+
+const HEADER: &[u8] = b"MIME-Magic\0\n";
+
+/// The rules for one MIME type.
+#[derive(Debug)]
+pub(super) struct MagicSection {
+    pub priority: u32,
+    pub mime: String,
+    rules: Vec<MagicRule>,
+}
+
+/// Compares `value` (with `mask` applied) against the data at every offset
+/// in `offset..offset + range`. Rules indented deeper than the previous one
+/// only apply when it matched.
+#[derive(Debug)]
+struct MagicRule {
+    indent: u32,
+    offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    range: usize,
+}
+
+impl MagicSection {
+    pub fn matches(&self, data: &[u8]) -> bool {
+        any_match(&self.rules, 0, data)
+    }
+
+    /// How many leading bytes of a file the rules look at.
+    pub fn extent(&self) -> usize {
+        self.rules
+            .iter()
+            .filter_map(MagicRule::end)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl MagicRule {
+    /// The end of the last window the rule compares, or `None` if it is
+    /// past what an offset can address.
+    fn end(&self) -> Option<usize> {
+        self.offset
+            .checked_add(self.range - 1)?
+            .checked_add(self.value.len())
+    }
+
+    fn matches(&self, data: &[u8]) -> bool {
+        (self.offset..self.offset.saturating_add(self.range)).any(|start| {
+            let Some(window) = start
+                .checked_add(self.value.len())
+                .and_then(|end| data.get(start..end))
+            else {
+                return false;
+            };
+            match &self.mask {
+                Some(mask) => window
+                    .iter()
+                    .zip(&self.value)
+                    .zip(mask)
+                    .all(|((byte, value), mask)| byte & mask == value & mask),
+                None => window == self.value.as_slice(),
+            }
+        })
+    }
+}
+
+/// Whether any rule at `indent` matches along with, if it has any, one of
+/// the rules nested under it.
+fn any_match(rules: &[MagicRule], indent: u32, data: &[u8]) -> bool {
+    let mut i = 0;
+    while i < rules.len() {
+        let end = rules[i + 1..]
+            .iter()
+            .position(|rule| rule.indent <= indent)
+            .map_or(rules.len(), |p| i + 1 + p);
+
+        let children = &rules[i + 1..end];
+        if rules[i].indent == indent
+            && rules[i].matches(data)
+            && (children.is_empty() || any_match(children, indent + 1, data))
+        {
+            return true;
+        }
+        i = end;
+    }
+    false
+}
+
+/// Parses a magic file. Returns `None` when the header is missing; broken
+/// sections are skipped.
+pub(super) fn parse(data: &[u8]) -> Option<Vec<MagicSection>> {
+    let mut parser = Parser {
+        data: data.strip_prefix(HEADER)?,
+    };
+
+    let mut sections = Vec::new();
+    while !parser.data.is_empty() {
+        match parser.section() {
+            Some(section) => sections.push(section),
+            // Resynchronise on the next section header.
+            None => parser.skip_to_section(),
+        }
+    }
+    Some(sections)
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+}
+
+impl Parser<'_> {
+    fn section(&mut self) -> Option<MagicSection> {
+        self.expect(b'[')?;
+        let priority = self.number()?;
+        self.expect(b':')?;
+        let end = self.data.iter().position(|&b| b == b']')?;
+        let mime = String::from_utf8(self.data[..end].to_vec()).ok()?;
+        self.data = &self.data[end + 1..];
+        self.expect(b'\n')?;
+
+        let mut rules = Vec::new();
+        while !self.data.is_empty() && self.data[0] != b'[' {
+            rules.push(self.rule()?);
+        }
+        Some(MagicSection {
+            priority: priority as u32,
+            mime,
+            rules,
+        })
+    }
+
+    /// `[indent]>offset=<u16 length><value>[&mask][~word size][+range]\n`
+    fn rule(&mut self) -> Option<MagicRule> {
+        let indent = if self.data.first() == Some(&b'>') {
+            0
+        } else {
+            self.number()? as u32
+        };
+        self.expect(b'>')?;
+        let offset = self.number()?;
+        self.expect(b'=')?;
+
+        let length = u16::from_be_bytes([*self.data.first()?, *self.data.get(1)?]) as usize;
+        let mut value = self.data.get(2..2 + length)?.to_vec();
+        self.data = &self.data[2 + length..];
+
+        let mut mask = None;
+        if self.data.first() == Some(&b'&') {
+            mask = Some(self.data.get(1..1 + length)?.to_vec());
+            self.data = &self.data[1 + length..];
+        }
+
+        let mut word_size = 1;
+        if self.data.first() == Some(&b'~') {
+            self.data = &self.data[1..];
+            word_size = self.number()?;
+        }
+
+        let mut range = 1;
+        if self.data.first() == Some(&b'+') {
+            self.data = &self.data[1..];
+            range = self.number()?;
+        }
+        // Offsets this large only come from a corrupt file. Checked before
+        // the line ends, so the parser resynchronises on the next section.
+        offset.checked_add(range.max(1) - 1)?.checked_add(length)?;
+        self.expect(b'\n')?;
+
+        // Values are stored big-endian; multi-byte words are compared in
+        // host order.
+        if cfg!(target_endian = "little") && matches!(word_size, 2 | 4) {
+            for bytes in std::iter::once(&mut value).chain(mask.as_mut()) {
+                for word in bytes.chunks_exact_mut(word_size) {
+                    word.reverse();
+                }
+            }
+        }
+
+        Some(MagicRule {
+            indent,
+            offset,
+            value,
+            mask,
+            range: range.max(1),
+        })
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        let rest = self.data.strip_prefix(&[byte])?;
+        self.data = rest;
+        Some(())
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let digits = self.data.iter().take_while(|b| b.is_ascii_digit()).count();
+        let number = std::str::from_utf8(&self.data[..digits])
+            .ok()?
+            .parse()
+            .ok()?;
+        self.data = &self.data[digits..];
+        Some(number)
+    }
+
+    fn skip_to_section(&mut self) {
+        let next = self
+            .data
+            .windows(2)
+            .position(|w| w == b"\n[")
+            .map_or(self.data.len(), |p| p + 1);
+        self.data = &self.data[next..];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse;
+
+    /// Builds a rule line the way `update-mime-database` writes it.
+    fn rule(prefix: &str, value: &[u8], suffix: &[u8]) -> Vec<u8> {
+        let mut line = prefix.as_bytes().to_vec();
+        line.extend((value.len() as u16).to_be_bytes());
+        line.extend(value);
+        line.extend(suffix);
+        line.push(b'\n');
+        line
+    }
+
+    #[test]
+    fn parses_and_matches_nested_rules() {
+        let mut data = b"MIME-Magic\0\n[80:image/png]\n".to_vec();
+        data.extend(rule(">0=", b"\x89PNG", b""));
+        data.extend(b"[50:application/x-fixture]\n");
+        data.extend(rule(">0=", b"FIX", b""));
+        data.extend(rule("1>8=", b"v2", b"+4"));
+        data.extend(b"[40:text/x-masked]\n");
+        data.extend(rule(">0=", b"AB", b"&\xdf\xdf"));
+
+        let sections = parse(&data).unwrap();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].priority, 80);
+        assert_eq!(sections[1].mime, "application/x-fixture");
+        assert_eq!(sections[1].extent(), 8 + 3 + 2);
+
+        assert!(sections[0].matches(b"\x89PNG\r\n"));
+        assert!(!sections[0].matches(b"GIF89a"));
+        // The nested rule must match too, anywhere in its range.
+        assert!(sections[1].matches(b"FIX.......v2"));
+        assert!(!sections[1].matches(b"FIX.v2"));
+        assert!(!sections[1].matches(b"FIX"));
+        // The mask ignores the case bit.
+        assert!(sections[2].matches(b"ab"));
+
+        assert!(parse(b"not magic").is_none());
+    }
+
+    #[test]
+    fn skips_sections_with_overflowing_rules() {
+        let mut data = b"MIME-Magic\0\n[50:application/x-huge]\n".to_vec();
+        data.extend(rule(&format!(">{}=", usize::MAX), b"AB", b"+2"));
+        data.extend(b"[40:text/x-fixture]\n");
+        data.extend(rule(">0=", b"FIX", b""));
+
+        let sections = parse(&data).unwrap();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].mime, "text/x-fixture");
+        assert!(sections[0].matches(b"FIX"));
+    }
+}
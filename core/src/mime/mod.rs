@@ -0,0 +1,245 @@
+//! What a path or URL is, and which applications open it, following the
+//! shared-mime-info and MIME applications associations specs.
+
+mod associations;
+mod database;
+mod magic;
+
+use std::{collections::BTreeMap, path::Path, sync::OnceLock};
+
+use anyhow::{Context, Result, anyhow};
+
+use self::associations::Associations;
+use self::database::MimeDatabase;
+use crate::application::{App, Application};
+use crate::common::{local_path, scheme};
+use crate::{ActivationContext, LaunchConfig};
+
+static DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+
+fn database() -> &'static MimeDatabase {
+    DATABASE.get_or_init(MimeDatabase::from_env)
+}
+
+/// The MIME type of a path or URL. URLs other than `file://` get the
+/// `x-scheme-handler/<scheme>` pseudo-type.
+pub(crate) fn mime_type(target: &str) -> String {
+    match local_path(target) {
+        Some(path) => database().guess(Path::new(&path)),
+        None => format!("x-scheme-handler/{}", scheme(target).unwrap_or_default()),
+    }
+}
+
+/// Icon theme names for `mime`, most specific first.
+pub(crate) fn icon_names(mime: &str) -> Vec<String> {
+    database().icon_names(mime)
+}
+
+/// An application that can open a target.
+pub(crate) struct Handler {
+    pub app: App,
+    /// Whether a `mimeapps.list` makes it the default for the target's
+    /// type or one of its ancestors.
+    pub is_default: bool,
+}
+
+/// The installed applications that can open `target`, the most preferred
+/// first.
+pub(crate) fn handlers(target: &str) -> Vec<Handler> {
+    let db = database();
+    let associations = Associations::from_env(db);
+    handlers_among(&mime_type(target), db, &associations, App::installed())
+}
+
+/// The applications of `installed` that can open `mime`, ordered as by
+/// [`handler_ids`].
+fn handlers_among(
+    mime: &str,
+    db: &MimeDatabase,
+    associations: &Associations,
+    installed: Vec<App>,
+) -> Vec<Handler> {
+    let mut installed: BTreeMap<String, App> = installed
+        .into_iter()
+        .map(|app| (app.id().to_string(), app))
+        .collect();
+
+    let supported: BTreeMap<String, Vec<String>> = installed
+        .iter()
+        .map(|(id, app)| {
+            let types = app
+                .mime_types()
+                .iter()
+                .map(|mime| db.unalias(mime).to_string())
+                .collect();
+            (id.clone(), types)
+        })
+        .collect();
+
+    handler_ids(mime, db, associations, &supported)
+        .into_iter()
+        .filter_map(|(id, is_default)| {
+            let app = installed.remove(&id)?;
+            Some(Handler { app, is_default })
+        })
+        .collect()
+}
+
+/// Opens `target` with the application `app_id`, or the default one.
+pub(crate) fn open(
+    target: &str,
+    app_id: Option<&str>,
+    launch: &LaunchConfig,
+    activation: &ActivationContext,
+) -> Result<()> {
+    // Configured ids may name the desktop file.
+    let app_id = app_id.map(|id| id.strip_suffix(".desktop").unwrap_or(id));
+    let app = match app_id {
+        Some(id) => App::installed()
+            .into_iter()
+            .find(|app| app.id() == id)
+            .with_context(|| format!("{id} is not installed"))?,
+        None => handlers(target)
+            .into_iter()
+            .next()
+            .map(|handler| handler.app)
+            .ok_or_else(|| anyhow!("No application can open {target}"))?,
+    };
+    tracing::info!(app = %app.name(), %target, "Opening");
+
+    let target = local_path(target).unwrap_or_else(|| target.to_string());
+    app.open(&[target], launch, activation)
+}
+
+/// The ids of the applications for `mime`, most preferred first, and
+/// whether each is a default. Each type from `mime` up through its
+/// ancestors contributes its defaults, then its added associations, then
+/// the applications declaring it, skipping removed associations and
+/// applications missing from `supported`.
+fn handler_ids(
+    mime: &str,
+    db: &MimeDatabase,
+    associations: &Associations,
+    supported: &BTreeMap<String, Vec<String>>,
+) -> Vec<(String, bool)> {
+    let mime = db.unalias(mime).to_string();
+    let mut ids: Vec<(String, bool)> = Vec::new();
+
+    for mime in std::iter::once(mime.clone()).chain(db.ancestors(&mime)) {
+        let declaring = supported
+            .iter()
+            .filter(|(_, types)| types.contains(&mime))
+            .map(|(id, _)| (id, false));
+        let candidates = associations
+            .defaults(&mime)
+            .iter()
+            .map(|id| (id, true))
+            .chain(associations.added(&mime).iter().map(|id| (id, false)))
+            .chain(declaring);
+
+        for (id, is_default) in candidates {
+            if supported.contains_key(id)
+                && !associations.is_removed(&mime, id)
+                && !ids.iter().any(|(known, _)| known == id)
+            {
+                ids.push((id.clone(), is_default));
+            }
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::BTreeMap, fs};
+
+    use super::{handler_ids, handlers_among};
+    use crate::application::{App, Application};
+    use crate::common::test_dir::TempDir;
+    use crate::mime::{associations::Associations, database::MimeDatabase};
+
+    #[test]
+    fn orders_handlers_by_preference() {
+        let root = TempDir::new("mime-handlers");
+        fs::create_dir_all(root.join("mime")).unwrap();
+        fs::write(root.join("mime/subclasses"), "text/markdown text/plain\n").unwrap();
+        fs::write(
+            root.join("mimeapps.list"),
+            "[Default Applications]\n\
+             text/plain=missing.desktop;editor.desktop\n\
+             [Added Associations]\n\
+             text/markdown=notes.desktop\n\
+             [Removed Associations]\n\
+             text/plain=pager.desktop\n",
+        )
+        .unwrap();
+        let db = MimeDatabase::load(&[root.join("mime")]);
+        let associations = Associations::load(&[root.join("mimeapps.list")], &db);
+
+        let supported: BTreeMap<String, Vec<String>> = [
+            ("editor", vec!["text/plain"]),
+            ("markdown", vec!["text/markdown"]),
+            ("notes", vec![]),
+            ("pager", vec!["text/plain"]),
+            ("hex", vec!["application/octet-stream"]),
+        ]
+        .into_iter()
+        .map(|(id, types)| {
+            (
+                id.to_string(),
+                types.into_iter().map(String::from).collect(),
+            )
+        })
+        .collect();
+
+        assert_eq!(
+            handler_ids("text/markdown", &db, &associations, &supported),
+            [
+                ("notes".to_string(), false),
+                ("markdown".to_string(), false),
+                ("editor".to_string(), true),
+                ("hex".to_string(), false),
+            ]
+        );
+        assert!(handler_ids("x-scheme-handler/https", &db, &associations, &supported).is_empty());
+    }
+
+    #[test]
+    fn matches_associations_to_installed_desktop_entries() {
+        let root = TempDir::new("mime-entries");
+        fs::create_dir_all(root.join("mime")).unwrap();
+        fs::write(
+            root.join("mimeapps.list"),
+            "[Default Applications]\n\
+             text/plain=org.example.Editor.desktop\n\
+             [Removed Associations]\n\
+             text/plain=org.example.Pager.desktop\n",
+        )
+        .unwrap();
+        let db = MimeDatabase::load(&[root.join("mime")]);
+        let associations = Associations::load(&[root.join("mimeapps.list")], &db);
+
+        let installed: Vec<App> = ["Editor", "Pager", "Viewer"]
+            .into_iter()
+            .map(|name| {
+                App::from_desktop_file(
+                    &format!("/usr/share/applications/org.example.{name}.desktop"),
+                    &format!(
+                        "[Desktop Entry]\nType=Application\nName={name}\n\
+                         Exec={name}\nMimeType=text/plain;\n"
+                    ),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let handlers: Vec<_> = handlers_among("text/plain", &db, &associations, installed)
+            .into_iter()
+            .map(|handler| (handler.app.name().to_string(), handler.is_default))
+            .collect();
+        assert_eq!(
+            handlers,
+            [("Editor".to_string(), true), ("Viewer".to_string(), false)]
+        );
+    }
+}
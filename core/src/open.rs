@@ -0,0 +1,213 @@
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result, bail};
+
+use crate::{ActivationContext, LaunchConfig};
+
+/// Opens a path or URL with the desktop's default handler.
+pub fn open(target: &str) -> Result<()> {
+    open_with(
+        target,
+        None,
+        &LaunchConfig::default(),
+        &ActivationContext::default(),
+    )
+}
+
+/// Opens a path or URL with the application whose id is `app`, or the
+/// default one. On Linux the handler comes from the MIME database, with
+/// `xdg-open` as a fallback when none is found.
+pub fn open_with(
+    target: &str,
+    app: Option<&str>,
+    launch: &LaunchConfig,
+    activation: &ActivationContext,
+) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    match crate::mime::open(target, app, launch, activation) {
+        Ok(()) => return Ok(()),
+        Err(e) if app.is_none() => tracing::debug!("Falling back to xdg-open: {e:#}"),
+        Err(e) => return Err(e),
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = (launch, activation);
+        if app.is_some() {
+            bail!("Choosing the application is only supported on Linux");
+        }
+    }
+
+    system_open(target)
+}
+
+/// The applications that can open `target` as `(id, name, is_default)`,
+/// the most preferred first.
+pub(crate) fn applications_for(target: &str) -> Result<Vec<(String, String, bool)>> {
+    #[cfg(target_os = "linux")]
+    {
+        use crate::Application;
+
+        Ok(crate::mime::handlers(target)
+            .into_iter()
+            .map(|handler| {
+                let app = handler.app;
+                (
+                    app.id().to_string(),
+                    app.name().to_string(),
+                    handler.is_default,
+                )
+            })
+            .collect())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = target;
+        bail!("Choosing the application is only supported on Linux")
+    }
+}
+
+/// Hands `target` to the platform's generic opener.
+fn system_open(target: &str) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    let mut cmd = Command::new("xdg-open");
+
+    #[cfg(target_os = "macos")]
+    let mut cmd = Command::new("open");
+
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut c = Command::new("cmd");
+        c.args(["/c", "start", ""]);
+        c
+    };
+
+    cmd.arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to open {target}"))?;
+
+    Ok(())
+}
+
+/// Shows `path` selected in the file manager. Falls back to opening the
+/// folder that contains it.
+pub fn reveal(path: &Path, activation: &ActivationContext) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    match show_items(path, activation) {
+        Ok(()) => return Ok(()),
+        Err(e) => tracing::debug!("FileManager1 unavailable, opening the folder: {e:#}"),
+    }
+    let folder = path.parent().unwrap_or(path);
+    open_with(
+        &folder.to_string_lossy(),
+        None,
+        &LaunchConfig::default(),
+        activation,
+    )
+}
+
+/// Asks the file manager to select `path` through the
+/// `org.freedesktop.FileManager1` D-Bus interface.
+#[cfg(target_os = "linux")]
+fn show_items(path: &Path, activation: &ActivationContext) -> Result<()> {
+    const FILE_MANAGER: &str = "org.freedesktop.FileManager1";
+
+    let connection =
+        zbus::blocking::Connection::session().context("Failed to connect to the session bus")?;
+    let uris = vec![crate::common::file_uri(path)];
+    let startup_id = activation.token().unwrap_or_default();
+
+    connection.call_method(
+        Some(FILE_MANAGER),
+        "/org/freedesktop/FileManager1",
+        Some(FILE_MANAGER),
+        "ShowItems",
+        &(uris, startup_id),
+    )?;
+    Ok(())
+}
+
+/// Copies `text` to the clipboard with the platform's clipboard tool, which
+/// keeps serving it after the launcher exits.
+pub fn copy(text: &str) -> Result<()> {
+    copy_as(None, text.as_bytes())
+}
+
+/// Copies `data` to the clipboard as the MIME type `mime`, or as text when
+/// `None`. Only text can be copied outside Linux.
+pub(crate) fn copy_as(mime: Option<&str>, data: &[u8]) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    let argv: Vec<&str> = match (std::env::var_os("WAYLAND_DISPLAY").is_some(), mime) {
+        (true, None) => vec!["wl-copy"],
+        (true, Some(mime)) => vec!["wl-copy", "--type", mime],
+        (false, None) => vec!["xclip", "-selection", "clipboard"],
+        (false, Some(mime)) => vec!["xclip", "-selection", "clipboard", "-t", mime],
+    };
+
+    #[cfg(target_os = "macos")]
+    let argv: Vec<&str> = vec!["pbcopy"];
+
+    #[cfg(target_os = "windows")]
+    let argv: Vec<&str> = vec!["clip"];
+
+    #[cfg(not(target_os = "linux"))]
+    if mime.is_some() {
+        bail!("Only text can be copied on this platform");
+    }
+
+    let mut child = Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", argv[0]))?;
+
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(data)
+        .with_context(|| format!("Failed to write to {}", argv[0]))?;
+
+    let status = child.wait()?;
+    if !status.success() {
+        bail!("{} exited with {status}", argv[0]);
+    }
+    Ok(())
+}
+
+/// The text on the clipboard, read with the platform's clipboard tool.
+pub(crate) fn paste() -> Result<String> {
+    #[cfg(target_os = "linux")]
+    let argv: &[&str] = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        &["wl-paste", "--no-newline"]
+    } else {
+        &["xclip", "-selection", "clipboard", "-out"]
+    };
+
+    #[cfg(target_os = "macos")]
+    let argv: &[&str] = &["pbpaste"];
+
+    #[cfg(target_os = "windows")]
+    let argv: &[&str] = &["powershell", "-NoProfile", "-Command", "Get-Clipboard"];
+
+    let output = Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run {}", argv[0]))?;
+    if !output.status.success() {
+        bail!("{} exited with {}", argv[0], output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
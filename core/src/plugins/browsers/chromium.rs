@@ -0,0 +1,245 @@
+//! Chromium and the browsers built on it keep each profile in a folder of
+//! their config directory, listed in `Local State`, with bookmarks in a
+//! `Bookmarks` JSON file and history and icons in SQLite databases.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use rusqlite::params;
+use serde_json::Value;
+
+use super::{Favicons, Page, Profile, Snapshot, merge, pick_favicons};
+use crate::BrowserKind;
+
+/// Config directory, name and command of the Chromium-based browsers
+/// looked for.
+pub(super) const BROWSERS: [(&str, &str, &[&str]); 5] = [
+    ("google-chrome", "Google Chrome", &["google-chrome"]),
+    ("chromium", "Chromium", &["chromium"]),
+    ("BraveSoftware/Brave-Browser", "Brave", &["brave-browser"]),
+    ("microsoft-edge", "Microsoft Edge", &["microsoft-edge"]),
+    ("vivaldi", "Vivaldi", &["vivaldi"]),
+];
+
+/// Seconds from 1601-01-01, where Chromium's clock starts, to the unix
+/// epoch.
+const EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// The profiles of the browser whose config directory is `root`.
+pub(super) fn profiles(root: &Path, browser: &str, command: &[String]) -> Vec<Profile> {
+    let mut found: Vec<(String, String)> = fs::read_to_string(root.join("Local State"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|state| {
+            let cache = state
+                .get("profile")?
+                .get("info_cache")?
+                .as_object()?
+                .clone();
+            Some(
+                cache
+                    .into_iter()
+                    .map(|(dir, info)| {
+                        let name = info.get("name").and_then(Value::as_str).unwrap_or(&dir);
+                        (name.to_string(), dir)
+                    })
+                    .collect(),
+            )
+        })
+        .unwrap_or_else(|| {
+            // Without `Local State`, go by the usual folder names.
+            fs::read_dir(root)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|entry| entry.file_name().into_string().ok())
+                .filter(|dir| dir == "Default" || dir.starts_with("Profile "))
+                .map(|dir| (dir.clone(), dir))
+                .collect()
+        });
+    found.sort_by(|a, b| a.1.cmp(&b.1));
+
+    found
+        .into_iter()
+        .map(|(name, dir)| (name, root.join(&dir), dir))
+        .filter(|(_, path, _)| path.join("Bookmarks").is_file() || path.join("History").is_file())
+        .map(|(name, path, dir)| {
+            let mut command = command.to_vec();
+            command.push(format!("--profile-directory={dir}"));
+            Profile {
+                browser: BrowserKind::Chromium,
+                label: format!("{browser} ({name})"),
+                path,
+                command,
+            }
+        })
+        .collect()
+}
+
+/// The bookmarks and, with a `history_limit`, the most recently visited
+/// pages of the profile at `path`, with their favicons.
+pub(super) fn load(path: &Path, history_limit: Option<usize>) -> Result<(Vec<Page>, Favicons)> {
+    let bookmarks_file = path.join("Bookmarks");
+    let mut pages = if bookmarks_file.is_file() {
+        let content = fs::read_to_string(&bookmarks_file)
+            .with_context(|| format!("Failed to read {}", bookmarks_file.display()))?;
+        bookmarks(&content)
+            .with_context(|| format!("Failed to parse {}", bookmarks_file.display()))?
+    } else {
+        Vec::new()
+    };
+    if let Some(limit) = history_limit {
+        let history_file = path.join("History");
+        if history_file.is_file() {
+            pages = merge(pages, history(&history_file, limit)?);
+        }
+    }
+
+    let favicons = match favicons(&path.join("Favicons"), &pages) {
+        Ok(favicons) => favicons,
+        Err(e) => {
+            tracing::debug!("No Chromium favicons for {}: {e:#}", path.display());
+            HashMap::new()
+        }
+    };
+    Ok((pages, favicons))
+}
+
+fn bookmarks(content: &str) -> Result<Vec<Page>> {
+    let document: Value = serde_json::from_str(content)?;
+    let roots = document
+        .get("roots")
+        .and_then(Value::as_object)
+        .context("No bookmark roots")?;
+
+    let mut pages = Vec::new();
+    for root in roots.values().filter(|root| root.is_object()) {
+        let name = root.get("name").and_then(Value::as_str).unwrap_or_default();
+        collect(root, name, &mut pages);
+    }
+    Ok(pages)
+}
+
+/// Adds the bookmarks under the folder `node`, whose path is `folder`.
+fn collect(node: &Value, folder: &str, pages: &mut Vec<Page>) {
+    let children = node.get("children").and_then(Value::as_array);
+    for child in children.into_iter().flatten() {
+        let name = child
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        match child.get("type").and_then(Value::as_str) {
+            Some("url") => {
+                let Some(url) = child.get("url").and_then(Value::as_str) else {
+                    continue;
+                };
+                pages.push(Page {
+                    url: url.to_string(),
+                    title: name.to_string(),
+                    folder: (!folder.is_empty()).then(|| folder.to_string()),
+                    bookmarked: true,
+                    visits: 0,
+                    last_visit: 0,
+                });
+            }
+            Some("folder") => collect(child, &format!("{folder}/{name}"), pages),
+            _ => {}
+        }
+    }
+}
+
+fn history(database: &Path, limit: usize) -> Result<Vec<Page>> {
+    let history = Snapshot::open(database)?;
+    let mut statement = history.prepare(
+        "SELECT url, COALESCE(title, ''), visit_count, last_visit_time \
+         FROM urls \
+         WHERE hidden = 0 AND visit_count > 0 \
+         ORDER BY last_visit_time DESC LIMIT ?1",
+    )?;
+    let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let rows = statement.query_map(params![limit], |row| {
+        Ok(Page {
+            url: row.get(0)?,
+            title: row.get(1)?,
+            folder: None,
+            bookmarked: false,
+            visits: row.get(2)?,
+            last_visit: unix_seconds(row.get(3)?),
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+fn favicons(database: &Path, pages: &[Page]) -> Result<Favicons> {
+    let favicons = Snapshot::open(database)?;
+    let mut statement = favicons.prepare(
+        "SELECT m.page_url, b.image_data, b.width \
+         FROM icon_mapping m JOIN favicon_bitmaps b ON b.icon_id = m.icon_id",
+    )?;
+    let icons = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(Result::ok);
+
+    let wanted: HashSet<&str> = pages.iter().map(|page| page.url.as_str()).collect();
+    Ok(pick_favicons(icons, &wanted))
+}
+
+/// Chromium stores times as microseconds since 1601-01-01.
+fn unix_seconds(microseconds: i64) -> u64 {
+    u64::try_from(microseconds / 1_000_000 - EPOCH_OFFSET).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{load, profiles};
+
+    fn root() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/browsers/chromium")
+    }
+
+    #[test]
+    fn names_profiles_from_local_state() {
+        let profiles = profiles(&root(), "Chromium", &["chromium".to_string()]);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].label, "Chromium (Work)");
+        assert_eq!(
+            profiles[0].command,
+            ["chromium", "--profile-directory=Default"]
+        );
+    }
+
+    #[test]
+    fn reads_bookmarks_history_and_favicons() {
+        let (pages, favicons) = load(&root().join("Default"), Some(100)).unwrap();
+        let urls: Vec<&str> = pages.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://kibana.internal.example/app",
+                "https://wiki.internal.example/runbooks",
+                "https://status.example.com/"
+            ]
+        );
+
+        let kibana = &pages[0];
+        assert_eq!(kibana.title, "Kibana");
+        assert_eq!(kibana.folder.as_deref(), Some("Bookmarks bar"));
+        assert_eq!((kibana.visits, kibana.last_visit), (5, 1_709_337_600));
+        assert_eq!(pages[1].folder.as_deref(), Some("Bookmarks bar/Ops"));
+        assert_eq!(pages[2].title, "Status");
+        assert!(!pages[2].bookmarked);
+
+        assert_eq!(
+            favicons
+                .get("https://kibana.internal.example/app")
+                .map(Vec::as_slice),
+            Some(&b"\x89PNG kibana-16"[..])
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Firefox profiles, listed in `profiles.ini`, keep bookmarks and history
+//! in `places.sqlite` and page icons in `favicons.sqlite`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use rusqlite::params;
+
+use super::{Favicons, Page, Profile, Snapshot, merge, pick_favicons};
+use crate::BrowserKind;
+
+/// The profiles listed in `root/profiles.ini` that exist on disk.
+pub(super) fn profiles(root: &Path, command: &[String]) -> Vec<Profile> {
+    let Ok(content) = fs::read_to_string(root.join("profiles.ini")) else {
+        return Vec::new();
+    };
+
+    let mut profiles = Vec::new();
+    let mut sections: Vec<HashMap<&str, &str>> = Vec::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            sections.push(HashMap::new());
+            sections
+                .last_mut()
+                .expect("just pushed")
+                .insert("[", line.trim_matches(['[', ']']));
+        } else if let (Some(section), Some((key, value))) =
+            (sections.last_mut(), line.split_once('='))
+        {
+            section.insert(key.trim(), value.trim());
+        }
+    }
+
+    for section in &sections {
+        if !section
+            .get("[")
+            .is_some_and(|name| name.starts_with("Profile"))
+        {
+            continue;
+        }
+        let (Some(name), Some(path)) = (section.get("Name"), section.get("Path")) else {
+            continue;
+        };
+        let path = if section.get("IsRelative") == Some(&"1") {
+            root.join(path)
+        } else {
+            PathBuf::from(path)
+        };
+        if !path.join("places.sqlite").is_file() {
+            continue;
+        }
+
+        let mut command = command.to_vec();
+        command.extend(["-P".to_string(), name.to_string()]);
+        profiles.push(Profile {
+            browser: BrowserKind::Firefox,
+            label: format!("Firefox ({name})"),
+            path,
+            command,
+        });
+    }
+    profiles
+}
+
+/// The bookmarks and, with a `history_limit`, the most recently visited
+/// pages of the profile at `path`, with their favicons.
+pub(super) fn load(path: &Path, history_limit: Option<usize>) -> Result<(Vec<Page>, Favicons)> {
+    let places = Snapshot::open(&path.join("places.sqlite"))?;
+    let mut pages = bookmarks(&places)?;
+    if let Some(limit) = history_limit {
+        pages = merge(pages, history(&places, limit)?);
+    }
+
+    let favicons = match favicons(&path.join("favicons.sqlite"), &pages) {
+        Ok(favicons) => favicons,
+        Err(e) => {
+            tracing::debug!("No Firefox favicons for {}: {e:#}", path.display());
+            HashMap::new()
+        }
+    };
+    Ok((pages, favicons))
+}
+
+fn bookmarks(places: &Snapshot) -> Result<Vec<Page>> {
+    let mut folders: HashMap<i64, (i64, String, String)> = HashMap::new();
+    let mut statement = places.prepare(
+        "SELECT id, parent, COALESCE(title, ''), guid FROM moz_bookmarks WHERE type = 2",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get(0)?, (row.get(1)?, row.get(2)?, row.get(3)?)))
+    })?;
+    for row in rows {
+        let (id, folder) = row?;
+        folders.insert(id, folder);
+    }
+
+    let mut statement = places.prepare(
+        "SELECT p.url, COALESCE(b.title, p.title, ''), b.parent, p.visit_count, \
+                COALESCE(p.last_visit_date, 0) \
+         FROM moz_bookmarks b JOIN moz_places p ON p.id = b.fk \
+         WHERE b.type = 1 AND p.url NOT LIKE 'place:%'",
+    )?;
+    let rows = statement.query_map([], |row| {
+        Ok(Page {
+            url: row.get(0)?,
+            title: row.get(1)?,
+            folder: folder_path(&folders, row.get(2)?),
+            bookmarked: true,
+            visits: row.get(3)?,
+            last_visit: seconds(row.get(4)?),
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// The names of the folders from the root down to `id`, e.g.
+/// "Bookmarks Toolbar/Work".
+fn folder_path(folders: &HashMap<i64, (i64, String, String)>, mut id: i64) -> Option<String> {
+    let mut names = Vec::new();
+    // Bounded in case the tree has a cycle.
+    for _ in 0..folders.len() {
+        let Some((parent, title, guid)) = folders.get(&id) else {
+            break;
+        };
+        match guid.as_str() {
+            "root________" => break,
+            "menu________" => names.push("Bookmarks Menu"),
+            "toolbar_____" => names.push("Bookmarks Toolbar"),
+            "unfiled_____" => names.push("Other Bookmarks"),
+            "mobile______" => names.push("Mobile Bookmarks"),
+            _ => names.push(title.as_str()),
+        }
+        id = *parent;
+    }
+    names.reverse();
+    (!names.is_empty()).then(|| names.join("/"))
+}
+
+fn history(places: &Snapshot, limit: usize) -> Result<Vec<Page>> {
+    let mut statement = places.prepare(
+        "SELECT url, COALESCE(title, ''), visit_count, COALESCE(last_visit_date, 0) \
+         FROM moz_places \
+         WHERE hidden = 0 AND visit_count > 0 AND url NOT LIKE 'place:%' \
+         ORDER BY last_visit_date DESC LIMIT ?1",
+    )?;
+    let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let rows = statement.query_map(params![limit], |row| {
+        Ok(Page {
+            url: row.get(0)?,
+            title: row.get(1)?,
+            folder: None,
+            bookmarked: false,
+            visits: row.get(2)?,
+            last_visit: seconds(row.get(3)?),
+        })
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+fn favicons(database: &Path, pages: &[Page]) -> Result<Favicons> {
+    let favicons = Snapshot::open(database)?;
+    let mut statement = favicons.prepare(
+        "SELECT p.page_url, i.data, i.width \
+         FROM moz_pages_w_icons p \
+         JOIN moz_icons_to_pages ip ON ip.page_id = p.id \
+         JOIN moz_icons i ON i.id = ip.icon_id",
+    )?;
+    let icons = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(Result::ok);
+
+    let wanted: HashSet<&str> = pages.iter().map(|page| page.url.as_str()).collect();
+    Ok(pick_favicons(icons, &wanted))
+}
+
+/// Firefox stores times as microseconds since the unix epoch.
+fn seconds(microseconds: i64) -> u64 {
+    u64::try_from(microseconds / 1_000_000).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{load, profiles};
+
+    fn root() -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/browsers/firefox")
+    }
+
+    #[test]
+    fn lists_existing_profiles() {
+        let profiles = profiles(&root(), &["firefox".to_string()]);
+        assert_eq!(profiles.len(), 1);
+        assert_eq!(profiles[0].label, "Firefox (default-release)");
+        assert_eq!(profiles[0].path, root().join("abcd1234.default-release"));
+        assert_eq!(profiles[0].command, ["firefox", "-P", "default-release"]);
+    }
+
+    #[test]
+    fn reads_bookmarks_history_and_favicons() {
+        let (pages, favicons) = load(&root().join("abcd1234.default-release"), Some(100)).unwrap();
+        let urls: Vec<&str> = pages.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            [
+                "https://dash.internal.example/grafana",
+                "https://docs.rs/",
+                "https://news.example.com/"
+            ]
+        );
+
+        let grafana = &pages[0];
+        assert_eq!(grafana.title, "Grafana");
+        assert_eq!(grafana.folder.as_deref(), Some("Bookmarks Toolbar/Work"));
+        assert_eq!((grafana.visits, grafana.last_visit), (12, 1_709_251_200));
+        assert_eq!(pages[1].title, "Docs.rs");
+        assert_eq!(pages[1].folder.as_deref(), Some("Bookmarks Menu"));
+        assert!(pages[1].bookmarked && !pages[2].bookmarked);
+
+        // The 32 pixel icon is preferred; SVG icons are skipped.
+        assert_eq!(
+            favicons
+                .get("https://dash.internal.example/grafana")
+                .map(Vec::as_slice),
+            Some(&b"\x89PNG grafana-32"[..])
+        );
+        assert_eq!(favicons.len(), 1);
+
+        let (bookmarks, _) = load(&root().join("abcd1234.default-release"), None).unwrap();
+        assert_eq!(bookmarks.len(), 2);
+    }
+}
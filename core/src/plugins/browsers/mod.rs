@@ -0,0 +1,516 @@
+mod chromium;
+mod firefox;
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+    sync::{
+        Arc, PoisonError, RwLock, RwLockReadGuard,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+};
+
+use anyhow::{Context, Result};
+use directories::BaseDirs;
+use rusqlite::{Connection, OpenFlags};
+
+use super::{Action, CommandEntity, Provider, SecondaryAction, expand_home};
+use crate::{
+    BrowserKind, Detail, Entity, Image, config::BrowsersConfig, detail::format_relative_time,
+};
+
+/// A browser profile whose bookmarks and history are searched.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Profile {
+    pub browser: BrowserKind,
+    /// Shown next to results, e.g. "Firefox (default-release)".
+    pub label: String,
+    pub path: PathBuf,
+    /// Opens a URL in this profile once the URL is appended.
+    pub command: Vec<String>,
+}
+
+/// A bookmarked or visited page.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Page {
+    pub url: String,
+    pub title: String,
+    /// Where a bookmark is filed, e.g. "Bookmarks Toolbar/Work".
+    pub folder: Option<String>,
+    pub bookmarked: bool,
+    pub visits: u32,
+    /// Unix timestamp of the last visit, 0 if never visited.
+    pub last_visit: u64,
+}
+
+/// Favicon bytes by page URL.
+pub(super) type Favicons = HashMap<String, Vec<u8>>;
+
+struct ProfilePages {
+    profile: Profile,
+    pages: Vec<Page>,
+    favicons: Favicons,
+}
+
+/// Searches the bookmarks and history of the configured or installed
+/// browsers by title and URL.
+pub(super) struct BrowsersProvider {
+    profiles: Arc<RwLock<Vec<ProfilePages>>>,
+    min_query_length: usize,
+    max_results: usize,
+}
+
+impl BrowsersProvider {
+    pub fn new(config: &BrowsersConfig) -> Self {
+        let profiles = Arc::new(RwLock::new(Vec::new()));
+
+        let shared = Arc::clone(&profiles);
+        let config_copy = config.clone();
+        if let Err(e) = thread::Builder::new()
+            .name("browsers".to_string())
+            .spawn(move || {
+                let loaded = load_profiles(&config_copy);
+                *shared.write().unwrap_or_else(PoisonError::into_inner) = loaded;
+            })
+        {
+            tracing::error!("Failed to start loading browser data: {e}");
+        }
+
+        Self {
+            profiles,
+            min_query_length: config.min_query_length,
+            max_results: config.max_results,
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Vec<ProfilePages>> {
+        self.profiles.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Provider for BrowsersProvider {
+    fn name(&self) -> &str {
+        "Browsers"
+    }
+
+    fn search(&self, query: &str) -> Vec<Entity> {
+        let query = query.trim();
+        if query.chars().count() < self.min_query_length.max(1) {
+            return Vec::new();
+        }
+        let needle = query.to_lowercase();
+
+        let profiles = self.read();
+        let mut matches: Vec<(u32, &ProfilePages, &Page)> = profiles
+            .iter()
+            .flat_map(|profile| profile.pages.iter().map(move |page| (profile, page)))
+            .filter_map(|(profile, page)| Some((score(page, &needle)?, profile, page)))
+            .collect();
+        matches.sort_unstable_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then(b.2.last_visit.cmp(&a.2.last_visit))
+                .then(a.2.url.cmp(&b.2.url))
+        });
+
+        matches
+            .into_iter()
+            .take(self.max_results)
+            .map(|(_, profile, page)| entity(&profile.profile, page, &profile.favicons))
+            .collect()
+    }
+}
+
+fn load_profiles(config: &BrowsersConfig) -> Vec<ProfilePages> {
+    let profiles = if config.profiles.is_empty() {
+        discover()
+    } else {
+        config.profiles.iter().map(configured).collect()
+    };
+    let history_limit = config.history.then_some(config.history_limit);
+
+    profiles
+        .into_iter()
+        .filter_map(|profile| {
+            let loaded = match profile.browser {
+                BrowserKind::Firefox => firefox::load(&profile.path, history_limit),
+                BrowserKind::Chromium => chromium::load(&profile.path, history_limit),
+            };
+            match loaded {
+                Ok((pages, favicons)) => {
+                    tracing::debug!(profile = %profile.label, pages = pages.len(), "Loaded browser profile");
+                    Some(ProfilePages {
+                        profile,
+                        pages,
+                        favicons,
+                    })
+                }
+                Err(e) => {
+                    tracing::warn!(profile = %profile.label, "Failed to read browser profile: {e:#}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn configured(profile: &crate::BrowserProfile) -> Profile {
+    let path = expand_home(&profile.path);
+    let (browser, default_command) = match profile.browser {
+        BrowserKind::Firefox => (
+            "Firefox",
+            vec![
+                "firefox".to_string(),
+                "--profile".to_string(),
+                path.to_string_lossy().into_owned(),
+            ],
+        ),
+        // The profile's directory within Chromium's user data directory.
+        BrowserKind::Chromium => (
+            "Chromium",
+            vec![
+                "chromium".to_string(),
+                format!(
+                    "--profile-directory={}",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                ),
+            ],
+        ),
+    };
+    let name = profile.name.clone().unwrap_or_else(|| {
+        path.file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+    });
+
+    Profile {
+        browser: profile.browser,
+        label: format!("{browser} ({name})"),
+        command: profile.command.clone().unwrap_or(default_command),
+        path,
+    }
+}
+
+/// The profiles of the browsers installed in their usual places.
+fn discover() -> Vec<Profile> {
+    let Some(dirs) = BaseDirs::new() else {
+        return Vec::new();
+    };
+    let home = dirs.home_dir();
+    let config = dirs.config_dir();
+
+    let mut profiles = Vec::new();
+    for (root, command) in [
+        (home.join(".mozilla/firefox"), vec!["firefox"]),
+        (
+            home.join(".var/app/org.mozilla.firefox/.mozilla/firefox"),
+            vec!["flatpak", "run", "org.mozilla.firefox"],
+        ),
+        (
+            home.join("snap/firefox/common/.mozilla/firefox"),
+            vec!["firefox"],
+        ),
+    ] {
+        profiles.extend(firefox::profiles(&root, &owned(&command)));
+    }
+    for (dir, name, command) in chromium::BROWSERS {
+        profiles.extend(chromium::profiles(&config.join(dir), name, &owned(command)));
+    }
+    profiles
+}
+
+fn owned(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// Merges visited pages into the bookmarks, keeping the bookmarks' titles.
+fn merge(bookmarks: Vec<Page>, history: Vec<Page>) -> Vec<Page> {
+    let mut pages = bookmarks;
+    let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, page) in pages.iter().enumerate() {
+        index.entry(page.url.clone()).or_default().push(i);
+    }
+
+    for visited in history {
+        match index.get(&visited.url) {
+            Some(bookmarks) => {
+                for &i in bookmarks {
+                    pages[i].visits = pages[i].visits.max(visited.visits);
+                    pages[i].last_visit = pages[i].last_visit.max(visited.last_visit);
+                }
+            }
+            None => pages.push(visited),
+        }
+    }
+    pages
+}
+
+/// Ranks how well `page` matches `needle`, which must be lowercase:
+/// titles before URLs, bookmarks before history, then by popularity.
+fn score(page: &Page, needle: &str) -> Option<u32> {
+    let title = page.title.to_lowercase();
+    let url = page.url.to_lowercase();
+
+    let quality = match title.find(needle) {
+        Some(0) => 300,
+        Some(position) if title[..position].ends_with(|c: char| !c.is_alphanumeric()) => 200,
+        Some(_) => 150,
+        None if host(&url).contains(needle) => 120,
+        None if url.contains(needle) => 60,
+        None => return None,
+    };
+    let bookmark = if page.bookmarked { 100 } else { 0 };
+    Some(quality + bookmark + page.visits.min(50) * 2)
+}
+
+fn host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split(['/', '?', '#']).next().unwrap_or(rest)
+}
+
+fn entity(profile: &Profile, page: &Page, favicons: &Favicons) -> Entity {
+    let name = if page.title.is_empty() {
+        page.url.clone()
+    } else {
+        page.title.clone()
+    };
+
+    let mut detail = Detail::default()
+        .with_row("URL", page.url.as_str())
+        .with_row("Browser", profile.label.as_str());
+    if let Some(folder) = &page.folder {
+        detail = detail.with_row("Folder", folder.as_str());
+    }
+    if page.visits > 0 {
+        detail = detail.with_row("Visits", page.visits.to_string());
+    }
+    if page.last_visit > 0 {
+        detail = detail.with_row("Last Visited", format_relative_time(page.last_visit));
+    }
+
+    let mut argv = profile.command.clone();
+    argv.push(page.url.clone());
+
+    Entity::Command(CommandEntity {
+        name,
+        description: Some(page.url.clone()),
+        image: favicons.get(&page.url).cloned().map(Image::Bytes),
+        plugin: Some(
+            if page.bookmarked {
+                "Bookmarks"
+            } else {
+                "History"
+            }
+            .to_string(),
+        ),
+        detail: Some(detail),
+        action: Action::Spawn { argv },
+        secondary_actions: vec![
+            SecondaryAction::new(
+                "Open in Default Browser",
+                Action::Open {
+                    target: page.url.clone(),
+                },
+            ),
+            SecondaryAction::new(
+                "Copy URL",
+                Action::Copy {
+                    text: page.url.clone(),
+                },
+            ),
+        ],
+        ..Default::default()
+    })
+}
+
+/// A private copy of a browser database, so reading it neither waits on
+/// the running browser's locks nor disturbs it. Deleted when dropped.
+struct Snapshot {
+    connection: Connection,
+    dir: PathBuf,
+}
+
+impl Snapshot {
+    fn open(database: &Path) -> Result<Self> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        // The copy holds the whole browsing history, so it goes in a new
+        // directory only the user can read, in the runtime directory that
+        // is cleared on logout when there is one.
+        let dirs = BaseDirs::new().context("No home directory")?;
+        let parent = dirs.runtime_dir().unwrap_or(dirs.cache_dir());
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+        let dir = parent.join(format!(
+            "iced_raycast-{}-{}",
+            process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder
+            .create(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let copy = || -> Result<Connection> {
+            let name = database.file_name().context("Not a file")?;
+            let target = dir.join(name);
+            fs::copy(database, &target)
+                .with_context(|| format!("Failed to copy {}", database.display()))?;
+
+            // Recent changes may still sit in the write-ahead log.
+            let mut wal = name.to_os_string();
+            wal.push("-wal");
+            let wal_path = database.with_file_name(&wal);
+            if wal_path.is_file() {
+                fs::copy(&wal_path, dir.join(&wal))?;
+            }
+
+            let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+            Ok(Connection::open_with_flags(&target, flags)?)
+        };
+
+        match copy() {
+            Ok(connection) => Ok(Self { connection, dir }),
+            Err(e) => {
+                let _ = fs::remove_dir_all(&dir);
+                Err(e)
+            }
+        }
+    }
+}
+
+impl Deref for Snapshot {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.connection
+    }
+}
+
+impl Drop for Snapshot {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_dir_all(&self.dir) {
+            tracing::debug!("Failed to remove {}: {e}", self.dir.display());
+        }
+    }
+}
+
+/// Keeps, for each page in `wanted`, the icon closest to 32 pixels wide.
+/// SVG icons are skipped since they aren't rendered as images.
+fn pick_favicons(
+    icons: impl Iterator<Item = (String, Vec<u8>, u32)>,
+    wanted: &HashSet<&str>,
+) -> Favicons {
+    const PREFERRED_WIDTH: u32 = 32;
+
+    let mut best: HashMap<String, (u32, Vec<u8>)> = HashMap::new();
+    for (url, data, width) in icons {
+        if !wanted.contains(url.as_str()) || data.is_empty() || data.starts_with(b"<") {
+            continue;
+        }
+        let distance = width.abs_diff(PREFERRED_WIDTH);
+        if best
+            .get(&url)
+            .is_none_or(|(current, _)| distance < *current)
+        {
+            best.insert(url, (distance, data));
+        }
+    }
+    best.into_iter()
+        .map(|(url, (_, data))| (url, data))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use rusqlite::Connection;
+
+    use super::{Page, Snapshot, configured, merge, score};
+    use crate::{BrowserKind, BrowserProfile, common::test_dir::TempDir};
+
+    fn page(title: &str, url: &str, bookmarked: bool, visits: u32) -> Page {
+        Page {
+            url: url.to_string(),
+            title: title.to_string(),
+            folder: None,
+            bookmarked,
+            visits,
+            last_visit: u64::from(visits),
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn snapshots_are_private_and_removed() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = TempDir::new("browser-snapshot");
+        let database = root.join("places.sqlite");
+        Connection::open(&database)
+            .unwrap()
+            .execute_batch("CREATE TABLE t (x); INSERT INTO t VALUES (1);")
+            .unwrap();
+
+        let snapshot = Snapshot::open(&database).unwrap();
+        let dir = snapshot.dir.clone();
+        let mode = dir.metadata().unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        let x: i64 = snapshot
+            .query_row("SELECT x FROM t", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(x, 1);
+
+        drop(snapshot);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn opens_configured_profiles_in_that_profile() {
+        let profile = configured(&BrowserProfile {
+            browser: BrowserKind::Chromium,
+            path: "/home/me/.config/chromium/Profile 2".to_string(),
+            name: None,
+            command: None,
+        });
+        assert_eq!(profile.label, "Chromium (Profile 2)");
+        assert_eq!(
+            profile.command,
+            ["chromium", "--profile-directory=Profile 2"]
+        );
+    }
+
+    #[test]
+    fn ranks_titles_then_bookmarks_then_visits() {
+        let rank = |page: &Page| score(page, "dash");
+
+        let bookmark = page("Dashboards", "https://grafana.example/", true, 0);
+        let visited = page("Dashboards", "https://grafana.example/d", false, 10);
+        let in_title = page("Team dash", "https://grafana.example/t", false, 0);
+        let in_host = page("Grafana", "https://dash.example/", false, 0);
+        let in_path = page("Grafana", "https://grafana.example/dash", false, 0);
+
+        assert!(rank(&bookmark) > rank(&visited));
+        assert!(rank(&visited) > rank(&in_title));
+        assert!(rank(&in_title) > rank(&in_host));
+        assert!(rank(&in_host) > rank(&in_path));
+        assert_eq!(rank(&page("News", "https://news.example/", true, 9)), None);
+    }
+
+    #[test]
+    fn merges_history_into_bookmarks() {
+        let pages = merge(
+            vec![page("Docs", "https://docs.example/", true, 0)],
+            vec![
+                page("Docs – Home", "https://docs.example/", false, 7),
+                page("Other", "https://other.example/", false, 2),
+            ],
+        );
+        assert_eq!(pages.len(), 2);
+        assert_eq!((pages[0].title.as_str(), pages[0].visits), ("Docs", 7));
+        assert!(!pages[1].bookmarked);
+    }
+}
@@ -0,0 +1,283 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::ErrorKind,
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result};
+
+use super::{Action, CommandEntity, Outcome, Provider, SecondaryAction};
+use crate::{
+    ClipboardConfig, Detail, Entity, Image,
+    clipboard::{Clip, ClipContent, ClipStore, history_path, now},
+    detail::{code_block, format_relative_time, format_size},
+};
+
+const PLUGIN: &str = "Clipboard History";
+/// Characters of the first line used as an entry's name.
+const NAME_LENGTH: usize = 80;
+/// Characters of text shown in the detail pane.
+const PREVIEW_LENGTH: usize = 2000;
+/// Size of the image thumbnails, in pixels.
+const THUMBNAIL_SIZE: u32 = 64;
+
+/// Lists the clipboard history recorded by the clipboard daemon for
+/// queries starting with the prefix.
+pub(super) struct ClipboardProvider {
+    prefix: String,
+    paste: Vec<String>,
+    cache: Mutex<Cache>,
+}
+
+/// The history as of its last change, read again once it changes.
+#[derive(Default)]
+struct Cache {
+    modified: Option<SystemTime>,
+    clips: Vec<Clip>,
+    thumbnails: HashMap<u64, Option<Image>>,
+}
+
+impl ClipboardProvider {
+    pub fn new(config: &ClipboardConfig) -> Self {
+        Self {
+            prefix: config.prefix.clone(),
+            paste: config.paste_command.clone(),
+            cache: Mutex::default(),
+        }
+    }
+
+    fn entity(&self, clip: &Clip, thumbnail: Option<Image>) -> Entity {
+        let (name, icon) = match &clip.content {
+            ClipContent::Text(text) => (first_line(text), "edit-paste"),
+            ClipContent::Image { width, height, .. } => {
+                (format!("Image ({width}×{height})"), "image-x-generic")
+            }
+        };
+        let copied = format_relative_time(clip.copied);
+        let description = match &clip.source {
+            Some(source) => format!("{source} · {copied}"),
+            None => copied.clone(),
+        };
+
+        let mut detail = Detail::default();
+        detail = match &clip.content {
+            ClipContent::Text(text) => detail
+                .with_markdown(preview(text))
+                .with_row("Type", "Text")
+                .with_row("Characters", text.chars().count().to_string()),
+            ClipContent::Image { mime, data, .. } => detail
+                .with_row("Type", mime.as_str())
+                .with_row("Size", format_size(data.len() as u64)),
+        };
+        detail = detail.with_row("Copied", copied);
+        if let Some(source) = &clip.source {
+            detail = detail.with_row("Source", source.as_str());
+        }
+        if clip.pinned {
+            detail = detail.with_row("Pinned", "Yes");
+        }
+
+        let copy = Action::CopyClip { id: clip.id };
+        let (action, mut secondary_actions) = if self.paste.is_empty() {
+            (copy, Vec::new())
+        } else {
+            let paste = Action::PasteClip {
+                id: clip.id,
+                paste: self.paste.clone(),
+            };
+            (paste, vec![SecondaryAction::new("Copy to Clipboard", copy)])
+        };
+        let pin = if clip.pinned { "Unpin" } else { "Pin" };
+        secondary_actions.extend([
+            SecondaryAction::new(
+                pin,
+                Action::PinClip {
+                    id: clip.id,
+                    pinned: !clip.pinned,
+                },
+            ),
+            SecondaryAction::new("Delete", Action::DeleteClip { id: clip.id }),
+        ]);
+
+        Entity::Command(CommandEntity {
+            name,
+            description: Some(description),
+            image: thumbnail,
+            icon_names: vec![icon.to_string()],
+            plugin: Some(PLUGIN.to_string()),
+            detail: Some(detail),
+            private: true,
+            action,
+            secondary_actions,
+            ..Default::default()
+        })
+    }
+}
+
+impl Provider for ClipboardProvider {
+    fn name(&self) -> &str {
+        PLUGIN
+    }
+
+    fn prefix(&self) -> Option<&str> {
+        Some(&self.prefix)
+    }
+
+    fn search(&self, query: &str) -> Vec<Entity> {
+        let Some(needle) = query.trim_start().strip_prefix(self.prefix.as_str()) else {
+            return Vec::new();
+        };
+        let needle = needle.trim().to_lowercase();
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = cache.refresh() {
+            tracing::error!("Failed to read the clipboard history: {e:#}");
+        }
+        let Cache {
+            clips, thumbnails, ..
+        } = &mut *cache;
+
+        // Pinned entries first, then the most recently copied.
+        let mut matches: Vec<&Clip> = clips
+            .iter()
+            .filter(|clip| clip.content.matches(&needle))
+            .collect();
+        matches.sort_by_key(|clip| !clip.pinned);
+
+        matches
+            .into_iter()
+            .map(|clip| {
+                let thumbnail = match &clip.content {
+                    ClipContent::Text(_) => None,
+                    ClipContent::Image { data, .. } => thumbnails
+                        .entry(clip.id)
+                        .or_insert_with(|| thumbnail(data))
+                        .clone(),
+                };
+                self.entity(clip, thumbnail)
+            })
+            .collect()
+    }
+}
+
+impl Cache {
+    /// Reads the history again if it changed since it was last read.
+    fn refresh(&mut self) -> Result<()> {
+        let path = history_path();
+        let modified = match fs::metadata(&path) {
+            Ok(metadata) => Some(metadata.modified()?),
+            // Nothing was recorded yet.
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        if modified == self.modified {
+            return Ok(());
+        }
+
+        self.clips = match modified {
+            Some(_) => ClipStore::open_default()?.clips().to_vec(),
+            None => Vec::new(),
+        };
+        self.thumbnails
+            .retain(|id, _| self.clips.iter().any(|clip| clip.id == *id));
+        self.modified = modified;
+        Ok(())
+    }
+}
+
+/// The command opening the history.
+pub(super) fn command(config: &ClipboardConfig) -> Entity {
+    Entity::Command(CommandEntity {
+        name: PLUGIN.to_string(),
+        description: Some("Search what you copied".to_string()),
+        icon_names: vec!["edit-paste".to_string()],
+        detail: Some(Detail::default().with_row("Prefix", config.prefix.trim_end())),
+        action: Action::Search {
+            query: config.prefix.clone(),
+        },
+        ..Default::default()
+    })
+}
+
+/// Copies the entry `id` back to the clipboard, moving it to the top of
+/// the history.
+pub(super) fn copy(id: u64) -> Result<()> {
+    let mut store = ClipStore::open_default()?;
+    let clip = store
+        .get(id)
+        .context("The entry is no longer in the clipboard history")?;
+    match &clip.content {
+        ClipContent::Text(text) => crate::copy(text)?,
+        ClipContent::Image { mime, data, .. } => crate::open::copy_as(Some(mime), data)?,
+    }
+    store.update(|store| store.touch(id, now()))?;
+    Ok(())
+}
+
+/// Copies the entry `id`, leaving `paste` to paste it into the focused
+/// window once the launcher has closed.
+pub(super) fn paste(id: u64, paste: &[String]) -> Result<Outcome> {
+    copy(id)?;
+    Ok(Outcome::AfterClose {
+        argv: paste.to_vec(),
+    })
+}
+
+pub(super) fn set_pinned(id: u64, pinned: bool) -> Result<()> {
+    ClipStore::open_default()?
+        .update(|store| store.set_pinned(id, pinned))?
+        .then_some(())
+        .context("The entry is no longer in the clipboard history")
+}
+
+pub(super) fn delete(id: u64) -> Result<()> {
+    ClipStore::open_default()?
+        .update(|store| store.remove(id))?
+        .then_some(())
+        .context("The entry is no longer in the clipboard history")
+}
+
+/// The first non-blank line of `text`, shortened to fit a result.
+fn first_line(text: &str) -> String {
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default();
+    match line.char_indices().nth(NAME_LENGTH) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+/// The start of `text`, shown as it was copied.
+fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_LENGTH) {
+        Some((end, _)) => code_block(&format!("{}\n…", &text[..end])),
+        None => code_block(text),
+    }
+}
+
+/// A small copy of an image, cheaper to show in the list than the image.
+fn thumbnail(data: &[u8]) -> Option<Image> {
+    let image = image::load_from_memory(data)
+        .inspect_err(|e| tracing::debug!("Failed to decode a clipboard image: {e}"))
+        .ok()?;
+    let small = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE).into_rgba8();
+    Some(Image::Rgba(small.width(), small.height(), small.into_raw()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{first_line, preview};
+
+    #[test]
+    fn names_and_previews_text() {
+        assert_eq!(first_line("\n  cargo test\nmore"), "cargo test");
+        assert_eq!(first_line(&"é".repeat(100)), format!("{}…", "é".repeat(80)));
+        assert_eq!(preview("fn main() {}"), "```\nfn main() {}\n```");
+        assert_eq!(preview("a ```` b"), "`````\na ```` b\n`````");
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    cmp::Reverse,
+    fs,
+    sync::{LazyLock, Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use super::{Action, CommandEntity, Provider, SecondaryAction};
+use crate::{AppState, Detail, EmojiConfig, Entity, SkinTone};
+
+const EMOJI: &str = "Emoji";
+const SYMBOLS: &str = "Symbols";
+/// Categories of the Unicode symbols start with this, like "Symbols: Arrows".
+const SYMBOL_CATEGORY: &str = "Symbols:";
+/// Results listed at most, so that short queries and browsing without one
+/// don't lay out thousands of cells. Typing narrows down to the rest.
+const MAX_RESULTS: usize = 400;
+
+static DATA: LazyLock<Vec<Emoji>> = LazyLock::new(|| parse(include_str!("../../data/emoji.tsv")));
+
+/// An emoji or symbol of the bundled data.
+#[derive(Debug, PartialEq)]
+struct Emoji {
+    character: &'static str,
+    /// The CLDR short name, or the Unicode name of a symbol, in lowercase.
+    name: &'static str,
+    keywords: Vec<&'static str>,
+    category: &'static str,
+    /// The character with each skin tone, from light to dark; empty when
+    /// it has none.
+    tones: Vec<&'static str>,
+}
+
+impl Emoji {
+    fn is_symbol(&self) -> bool {
+        self.category.starts_with(SYMBOL_CATEGORY)
+    }
+
+    fn with_tone(&self, tone: SkinTone) -> Option<&'static str> {
+        let index = SkinTone::ALL.iter().position(|t| *t == tone)?;
+        self.tones.get(index).copied()
+    }
+
+    /// How well `word` matches, lower is better, or `None` if it doesn't.
+    fn rank(&self, word: &str) -> Option<u8> {
+        if self.name.split([' ', '-']).any(|w| w.starts_with(word)) {
+            Some(0)
+        } else if self.keywords.iter().any(|k| k.starts_with(word)) {
+            Some(1)
+        } else if self.name.contains(word) {
+            Some(2)
+        } else {
+            None
+        }
+    }
+}
+
+/// Lists emoji and symbols by name and keyword for queries starting with
+/// the prefix, the most used first.
+pub(super) struct EmojiProvider {
+    prefix: String,
+    skin_tone: Option<SkinTone>,
+    columns: usize,
+    usage: Mutex<Usage>,
+}
+
+/// The usage counts as of the last change to the state file, read again
+/// once it changes rather than on every query.
+#[derive(Default)]
+struct Usage {
+    modified: Option<SystemTime>,
+    app_state: AppState,
+}
+
+impl Usage {
+    fn refresh(&mut self) {
+        let modified = fs::metadata(AppState::get_path())
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified != self.modified {
+            self.app_state = AppState::load();
+            self.modified = modified;
+        }
+    }
+
+    fn count(&self, name: &str) -> u32 {
+        self.app_state.usage_stats.get(name).map_or(0, |u| u.count)
+    }
+}
+
+impl EmojiProvider {
+    pub fn new(config: &EmojiConfig) -> Self {
+        Self {
+            prefix: config.prefix.clone(),
+            skin_tone: config.skin_tone,
+            columns: config.columns.max(1),
+            usage: Mutex::default(),
+        }
+    }
+
+    /// The best [`MAX_RESULTS`] matches of `needle`, as entities.
+    fn results(&self, needle: &str, uses: impl Fn(&str) -> u32) -> Vec<Entity> {
+        search(&DATA, needle, uses)
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|emoji| self.entity(emoji))
+            .collect()
+    }
+
+    fn entity(&self, emoji: &Emoji) -> Entity {
+        let shown = self
+            .skin_tone
+            .and_then(|tone| emoji.with_tone(tone))
+            .unwrap_or(emoji.character);
+        let category = emoji
+            .category
+            .strip_prefix(SYMBOL_CATEGORY)
+            .map_or(emoji.category, str::trim);
+
+        let mut detail = Detail::default()
+            .with_markdown(format!("# {shown}"))
+            .with_row("Category", category);
+        if !emoji.keywords.is_empty() {
+            detail = detail.with_row("Keywords", emoji.keywords.join(", "));
+        }
+        detail = detail.with_row("Code Points", code_points(shown));
+
+        let mut secondary_actions = Vec::new();
+        if shown != emoji.character {
+            secondary_actions.push(SecondaryAction::new(
+                "Copy Without Skin Tone",
+                Action::Copy {
+                    text: emoji.character.to_string(),
+                },
+            ));
+        }
+        for (tone, variant) in SkinTone::ALL.iter().zip(&emoji.tones) {
+            if *variant != shown {
+                secondary_actions.push(SecondaryAction::new(
+                    format!("Copy with {} Skin Tone", tone.name()),
+                    Action::Copy {
+                        text: variant.to_string(),
+                    },
+                ));
+            }
+        }
+
+        let (plugin, icon) = if emoji.is_symbol() {
+            (SYMBOLS, "accessories-character-map")
+        } else {
+            (EMOJI, "face-smile")
+        };
+        Entity::Command(CommandEntity {
+            name: emoji.name.to_string(),
+            description: Some(category.to_string()),
+            icon_names: vec![icon.to_string()],
+            plugin: Some(plugin.to_string()),
+            detail: Some(detail),
+            symbol: Some(shown.to_string()),
+            action: Action::Copy {
+                text: shown.to_string(),
+            },
+            secondary_actions,
+            ..Default::default()
+        })
+    }
+}
+
+impl Provider for EmojiProvider {
+    fn name(&self) -> &str {
+        EMOJI
+    }
+
+    fn prefix(&self) -> Option<&str> {
+        Some(&self.prefix)
+    }
+
+    fn columns(&self) -> Option<usize> {
+        Some(self.columns)
+    }
+
+    fn search(&self, query: &str) -> Vec<Entity> {
+        let Some(needle) = query.trim_start().strip_prefix(self.prefix.as_str()) else {
+            return Vec::new();
+        };
+        let mut usage = self.usage.lock().unwrap_or_else(PoisonError::into_inner);
+        usage.refresh();
+        self.results(needle, |name| usage.count(name))
+    }
+}
+
+/// The command opening the picker.
+pub(super) fn command(config: &EmojiConfig) -> Entity {
+    Entity::Command(CommandEntity {
+        name: EMOJI.to_string(),
+        description: Some("Search emoji and symbols to copy".to_string()),
+        icon_names: vec!["face-smile".to_string()],
+        detail: Some(Detail::default().with_row("Prefix", config.prefix.as_str())),
+        action: Action::Search {
+            query: config.prefix.clone(),
+        },
+        ..Default::default()
+    })
+}
+
+/// Entries matching every word of `needle`, best matches first, then the
+/// most used, then in the order of the data. An emoji pasted as the needle
+/// finds itself.
+fn search<'a>(data: &'a [Emoji], needle: &str, uses: impl Fn(&str) -> u32) -> Vec<&'a Emoji> {
+    let needle = needle.trim();
+    let lowercase = needle.to_lowercase();
+    let words: Vec<&str> = lowercase.split_whitespace().collect();
+
+    let mut matches: Vec<(u8, Reverse<u32>, usize, &Emoji)> = data
+        .iter()
+        .enumerate()
+        .filter_map(|(index, emoji)| {
+            let rank = if emoji.character == needle || emoji.tones.contains(&needle) {
+                0
+            } else {
+                words
+                    .iter()
+                    .try_fold(0, |rank, word| Some(rank.max(emoji.rank(word)?)))?
+            };
+            Some((rank, Reverse(uses(emoji.name)), index, emoji))
+        })
+        .collect();
+    matches.sort_unstable_by_key(|(rank, uses, index, _)| (*rank, *uses, *index));
+    matches.into_iter().map(|(.., emoji)| emoji).collect()
+}
+
+fn parse(data: &'static str) -> Vec<Emoji> {
+    data.lines()
+        // Comments start with "# ", unlike the keycap "#️⃣".
+        .filter(|line| !line.is_empty() && *line != "#" && !line.starts_with("# "))
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let emoji = Emoji {
+                character: fields.next()?,
+                name: fields.next()?,
+                keywords: split(fields.next()?, '|'),
+                category: fields.next()?,
+                tones: split(fields.next().unwrap_or_default(), ' '),
+            };
+            Some(emoji)
+        })
+        .collect()
+}
+
+fn split(field: &str, separator: char) -> Vec<&str> {
+    field.split(separator).filter(|s| !s.is_empty()).collect()
+}
+
+/// `text` as code points, like "U+1F44D U+1F3FD".
+fn code_points(text: &str) -> String {
+    text.chars()
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DATA, EmojiProvider, MAX_RESULTS, SkinTone, code_points, search};
+    use crate::EmojiConfig;
+
+    #[test]
+    fn finds_emoji_by_name_keyword_and_usage() {
+        let thumbs_up = DATA.iter().find(|e| e.name == "thumbs up").unwrap();
+        assert_eq!(thumbs_up.keywords[0], "+1");
+        assert_eq!(thumbs_up.with_tone(SkinTone::Medium), Some("👍🏽"));
+        assert_eq!(code_points("👍🏽"), "U+1F44D U+1F3FD");
+        assert!(DATA.iter().any(|e| e.name == "keycap: #"));
+        assert!(
+            DATA.iter()
+                .any(|e| e.name == "rightwards arrow" && e.is_symbol())
+        );
+
+        let unused = |_: &str| 0;
+        assert_eq!(search(&DATA, "thumbs", unused)[0].character, "👍");
+        assert_eq!(search(&DATA, "  Thumbs Do", unused)[0].character, "👎");
+        assert_eq!(search(&DATA, "👍🏾", unused)[0].character, "👍");
+        assert!(search(&DATA, "thumbs zzz", unused).is_empty());
+        assert!(
+            search(&DATA, "smile", unused)
+                .iter()
+                .any(|e| e.character == "😄")
+        );
+
+        let used = |name: &str| u32::from(name == "thumbs down");
+        assert_eq!(search(&DATA, "thumbs", used)[0].character, "👎");
+        assert_eq!(search(&DATA, "", used)[0].character, "👎");
+        assert_eq!(search(&DATA, "", unused).len(), DATA.len());
+
+        let provider = EmojiProvider::new(&EmojiConfig::default());
+        assert_eq!(provider.results("", unused).len(), MAX_RESULTS);
+        assert_eq!(provider.results("thumbs", unused).len(), 2);
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result, bail};
+
+use super::{Action, CommandEntity, FileIndex, ShellMode, display_path, expand_home};
+use crate::{ActivationContext, Detail, Entity, FallbackCommand, LaunchConfig};
+
+/// The configured fallback commands, in order. They need an argument,
+/// which is the query they're listed for.
+pub(super) fn commands(
+    commands: &[FallbackCommand],
+    file_index: Option<&FileIndex>,
+    hold: bool,
+) -> Vec<Entity> {
+    commands
+        .iter()
+        .filter_map(|command| {
+            let (name, description, icon, detail, action) = match command {
+                FallbackCommand::WebSearch { name, url, app } => {
+                    let mut detail = Detail::default().with_row("URL", url.as_str());
+                    if let Some(app) = app {
+                        detail = detail.with_row("Opens With", app.as_str());
+                    }
+                    let action = Action::Quicklink {
+                        url: url.clone(),
+                        app: app.clone(),
+                    };
+                    (name.clone(), url.clone(), "web-browser", detail, action)
+                }
+                // Without the files provider there's no index to search.
+                FallbackCommand::SearchFiles => (
+                    "Search Files".to_string(),
+                    "Find files whose path contains the query".to_string(),
+                    "system-search",
+                    Detail::default(),
+                    Action::SearchFiles {
+                        index: file_index?.clone(),
+                    },
+                ),
+                FallbackCommand::Shell { terminal } => {
+                    let (name, mode) = if *terminal {
+                        ("Run in Terminal", ShellMode::Terminal { hold })
+                    } else {
+                        ("Run in Shell", ShellMode::Silent)
+                    };
+                    (
+                        name.to_string(),
+                        "Run the query as a shell command".to_string(),
+                        "utilities-terminal",
+                        Detail::default().with_row("Shell", super::shell::shell()),
+                        Action::RunArgument { mode },
+                    )
+                }
+                FallbackCommand::CreateNote { folder, extension } => (
+                    "Create Note".to_string(),
+                    display_path(&expand_home(folder)),
+                    "document-new",
+                    Detail::default().with_row("Folder", folder.as_str()),
+                    Action::CreateNote {
+                        folder: folder.clone(),
+                        extension: extension.clone(),
+                    },
+                ),
+            };
+
+            Some(Entity::Command(CommandEntity {
+                name,
+                description: Some(description),
+                icon_names: vec![icon.to_string()],
+                arguments: vec!["Query".to_string()],
+                detail: (!detail.is_empty()).then_some(detail),
+                action,
+                ..Default::default()
+            }))
+        })
+        .collect()
+}
+
+/// Creates the note `title` in `folder`, unless it exists, and opens it.
+pub(super) fn create_note(
+    folder: &str,
+    extension: &str,
+    title: &str,
+    launch: &LaunchConfig,
+    activation: &ActivationContext,
+) -> Result<()> {
+    let path = note_path(folder, extension, title)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let content = if extension == "md" {
+        format!("# {}\n\n", title.trim())
+    } else {
+        String::new()
+    };
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+    {
+        Ok(mut file) => file
+            .write_all(content.as_bytes())
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        // Opening the existing note is as good.
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e).with_context(|| format!("Failed to create {}", path.display())),
+    }
+
+    tracing::info!(path = %path.display(), "Opening note");
+    crate::open_with(&path.to_string_lossy(), None, launch, activation)
+}
+
+/// The file for the note `title`, with characters file names can't hold
+/// replaced.
+fn note_path(folder: &str, extension: &str, title: &str) -> Result<PathBuf> {
+    let name: String = title
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '\0' => '-',
+            c => c,
+        })
+        .collect();
+    if name.is_empty() || name.chars().all(|c| c == '.') {
+        bail!("A note needs a title");
+    }
+
+    let file = match extension.trim_start_matches('.') {
+        "" => name,
+        extension => format!("{name}.{extension}"),
+    };
+    Ok(expand_home(folder).join(file))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{FileIndex, commands, note_path};
+    use crate::{Action, Entity, FallbackCommand, FallbacksConfig};
+
+    #[test]
+    fn keeps_the_configured_order() {
+        let config = FallbacksConfig::default();
+        let index = FileIndex::default();
+        let names = |file_index| -> Vec<String> {
+            commands(&config.commands, file_index, false)
+                .iter()
+                .map(|entity| entity.name().to_string())
+                .collect()
+        };
+        assert_eq!(
+            names(Some(&index)),
+            ["Search the Web", "Search Files", "Run in Shell"]
+        );
+        assert_eq!(names(None), ["Search the Web", "Run in Shell"]);
+
+        let note = commands(
+            &[FallbackCommand::CreateNote {
+                folder: "/notes".to_string(),
+                extension: "md".to_string(),
+            }],
+            None,
+            false,
+        );
+        let Entity::Command(note) = &note[0] else {
+            panic!("fallbacks are commands");
+        };
+        assert_eq!(note.arguments, ["Query"]);
+        assert!(matches!(&note.action, Action::CreateNote { folder, .. } if folder == "/notes"));
+    }
+
+    #[test]
+    fn names_notes_after_the_query() {
+        assert_eq!(
+            note_path("/notes", "md", " Ideas / plans ").unwrap(),
+            PathBuf::from("/notes/Ideas - plans.md")
+        );
+        assert_eq!(
+            note_path("/notes", "", "todo").unwrap(),
+            PathBuf::from("/notes/todo")
+        );
+        assert!(note_path("/notes", "md", "..").is_err());
+        assert!(note_path("/notes", "md", "  ").is_err());
+    }
+}
@@ -0,0 +1,440 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard, mpsc},
+    thread,
+    time::{Instant, UNIX_EPOCH},
+};
+
+use ignore::{
+    DirEntry, WalkBuilder,
+    gitignore::Gitignore,
+    overrides::{Override, OverrideBuilder},
+};
+use notify::{
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+    event::{ModifyKind, RenameMode},
+};
+
+use crate::config::FilesConfig;
+use crate::plugins::expand_home;
+
+/// Entries past this are left out of the index.
+const MAX_ENTRIES: usize = 500_000;
+
+#[derive(Debug, Clone)]
+pub(super) struct IndexedFile {
+    /// The lowercased file name, matched against queries.
+    pub name: String,
+    pub is_dir: bool,
+    /// Unix timestamp of the last modification.
+    pub modified: u64,
+}
+
+impl IndexedFile {
+    fn new(entry: &DirEntry) -> Option<Self> {
+        let metadata = entry.metadata().ok()?;
+        Some(Self {
+            name: entry.file_name().to_string_lossy().to_lowercase(),
+            is_dir: metadata.is_dir(),
+            modified: modified(&metadata),
+        })
+    }
+}
+
+fn modified(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Indexed paths, ordered so that a directory is followed by its contents.
+pub(super) type Files = BTreeMap<PathBuf, IndexedFile>;
+
+/// The files under the configured roots, built in the background and kept
+/// up to date with inotify. Clones share the same files.
+#[derive(Clone, Default)]
+pub struct FileIndex {
+    files: Arc<RwLock<Files>>,
+}
+
+impl fmt::Debug for FileIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileIndex").finish_non_exhaustive()
+    }
+}
+
+impl FileIndex {
+    pub(crate) fn spawn(config: &FilesConfig) -> Self {
+        let files = Arc::new(RwLock::new(Files::new()));
+        let rules = IndexRules::new(config);
+
+        let shared = Arc::clone(&files);
+        if let Err(e) = thread::Builder::new()
+            .name("file-index".to_string())
+            .spawn(move || run(rules, &shared))
+        {
+            tracing::error!("Failed to start the file indexer: {e}");
+        }
+
+        Self { files }
+    }
+
+    pub(super) fn read(&self) -> RwLockReadGuard<'_, Files> {
+        read(&self.files)
+    }
+}
+
+fn read(files: &RwLock<Files>) -> RwLockReadGuard<'_, Files> {
+    files.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write(files: &RwLock<Files>) -> RwLockWriteGuard<'_, Files> {
+    files.write().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn run(rules: IndexRules, files: &RwLock<Files>) {
+    let (tx, rx) = mpsc::channel();
+    let watches = match notify::recommended_watcher(tx) {
+        Ok(watcher) => Some(Watches {
+            watcher,
+            exhausted: false,
+        }),
+        Err(e) => {
+            tracing::warn!("Can't watch for file changes, the index won't update: {e}");
+            None
+        }
+    };
+
+    let mut indexer = Indexer { rules, watches };
+    let fresh = indexer.scan_roots();
+    *write(files) = fresh;
+
+    if indexer.watches.is_none() {
+        return;
+    }
+    for result in rx {
+        match result {
+            Ok(event) => indexer.handle(files, event),
+            Err(e) => tracing::debug!("File watcher error: {e}"),
+        }
+    }
+}
+
+/// Decides which paths belong in the index.
+struct IndexRules {
+    /// Each root with the `exclude` patterns anchored at it.
+    roots: Vec<(PathBuf, Override)>,
+    show_hidden: bool,
+}
+
+impl IndexRules {
+    fn new(config: &FilesConfig) -> Self {
+        let roots = config
+            .roots
+            .iter()
+            .filter_map(|root| {
+                let path = expand_home(root);
+                if !path.is_dir() {
+                    tracing::warn!("File search root {} is not a directory", path.display());
+                    return None;
+                }
+                let overrides = exclusions(&path, &config.exclude);
+                Some((path, overrides))
+            })
+            .collect();
+
+        Self {
+            roots,
+            show_hidden: config.show_hidden,
+        }
+    }
+
+    fn is_root(&self, path: &Path) -> bool {
+        self.roots.iter().any(|(root, _)| root == path)
+    }
+
+    fn root_for(&self, path: &Path) -> Option<&(PathBuf, Override)> {
+        self.roots.iter().find(|(root, _)| path.starts_with(root))
+    }
+
+    fn walker(&self, path: &Path) -> WalkBuilder {
+        let mut builder = WalkBuilder::new(path);
+        builder
+            .hidden(!self.show_hidden)
+            .require_git(false)
+            .follow_links(false);
+        if let Some((_, overrides)) = self.root_for(path) {
+            builder.overrides(overrides.clone());
+        }
+        builder
+    }
+
+    /// Whether a path that appeared inside an indexed directory belongs in
+    /// the index. The walker applies the same rules during a scan.
+    fn accepts(&self, path: &Path, is_dir: bool) -> bool {
+        let Some((root, overrides)) = self.root_for(path) else {
+            return false;
+        };
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."));
+
+        (self.show_hidden || !hidden)
+            && !overrides.matched(path, is_dir).is_ignore()
+            && !ignored_by_files(root, path, is_dir)
+    }
+}
+
+/// Turns `.gitignore`-style patterns into overrides that ignore what they
+/// match.
+fn exclusions(root: &Path, patterns: &[String]) -> Override {
+    let mut builder = OverrideBuilder::new(root);
+    for pattern in patterns {
+        if let Err(e) = builder.add(&format!("!{pattern}")) {
+            tracing::warn!("Invalid file search exclusion {pattern:?}: {e}");
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        tracing::warn!("Invalid file search exclusions: {e}");
+        Override::empty()
+    })
+}
+
+/// Checks `path` against the `.gitignore` and `.ignore` files of the
+/// directories between it and `root`.
+fn ignored_by_files(root: &Path, path: &Path, is_dir: bool) -> bool {
+    path.ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .flat_map(|dir| [dir.join(".gitignore"), dir.join(".ignore")])
+        .filter(|file| file.is_file())
+        .any(|file| {
+            let (ignore, _) = Gitignore::new(file);
+            ignore.matched_path_or_any_parents(path, is_dir).is_ignore()
+        })
+}
+
+/// Non-recursive watches on every indexed directory, so excluded trees
+/// don't use up inotify watches.
+struct Watches {
+    watcher: RecommendedWatcher,
+    /// Set once the inotify watch limit is hit.
+    exhausted: bool,
+}
+
+impl Watches {
+    fn add(&mut self, dir: &Path) {
+        if self.exhausted {
+            return;
+        }
+        match self.watcher.watch(dir, RecursiveMode::NonRecursive) {
+            Ok(()) => {}
+            Err(e) if matches!(e.kind, notify::ErrorKind::MaxFilesWatch) => {
+                tracing::warn!(
+                    "Out of inotify watches, some folders won't update live. \
+                     Raising fs.inotify.max_user_watches fixes this."
+                );
+                self.exhausted = true;
+            }
+            Err(e) => tracing::debug!("Failed to watch {}: {e}", dir.display()),
+        }
+    }
+}
+
+struct Indexer {
+    rules: IndexRules,
+    watches: Option<Watches>,
+}
+
+impl Indexer {
+    fn scan_roots(&mut self) -> Files {
+        let started = Instant::now();
+        let mut files = Files::new();
+        let roots: Vec<PathBuf> = self.rules.roots.iter().map(|(r, _)| r.clone()).collect();
+        for root in roots {
+            self.scan(&root, &mut files);
+        }
+        tracing::info!(count = files.len(), elapsed = ?started.elapsed(), "Indexed files");
+        files
+    }
+
+    /// Adds `path` and everything under it to `files`, watching the
+    /// directories found on the way.
+    fn scan(&mut self, path: &Path, files: &mut Files) {
+        for entry in self.rules.walker(path).build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    tracing::debug!("Skipping while indexing: {e}");
+                    continue;
+                }
+            };
+            if entry.file_type().is_some_and(|t| t.is_dir())
+                && let Some(watches) = &mut self.watches
+            {
+                watches.add(entry.path());
+            }
+            if entry.depth() == 0 && self.rules.is_root(entry.path()) {
+                continue;
+            }
+            if files.len() >= MAX_ENTRIES {
+                tracing::warn!("Stopped indexing at {MAX_ENTRIES} files");
+                return;
+            }
+            if let Some(file) = IndexedFile::new(&entry) {
+                files.insert(entry.into_path(), file);
+            }
+        }
+    }
+
+    fn handle(&mut self, files: &RwLock<Files>, event: Event) {
+        if event.need_rescan() {
+            let fresh = self.scan_roots();
+            *write(files) = fresh;
+            return;
+        }
+
+        let paths = event.paths;
+        match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for path in &paths {
+                    self.add(files, path);
+                }
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                for path in &paths {
+                    remove(&mut write(files), path);
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = paths.as_slice() {
+                    remove(&mut write(files), from);
+                    self.add(files, to);
+                }
+            }
+            // Renames that couldn't be paired up.
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in &paths {
+                    if path.exists() {
+                        self.add(files, path);
+                    } else {
+                        remove(&mut write(files), path);
+                    }
+                }
+            }
+            EventKind::Modify(_) => {
+                let mut files = write(files);
+                for path in &paths {
+                    if let (Some(file), Ok(metadata)) = (files.get_mut(path), fs::metadata(path)) {
+                        file.modified = modified(&metadata);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Indexes a path that appeared on disk, if its parent is indexed and
+    /// the rules accept it. Scanned without holding the lock.
+    fn add(&mut self, files: &RwLock<Files>, path: &Path) {
+        let Ok(metadata) = fs::symlink_metadata(path) else {
+            return;
+        };
+        let parent_indexed = path
+            .parent()
+            .is_some_and(|parent| self.rules.is_root(parent) || read(files).contains_key(parent));
+        if !parent_indexed || !self.rules.accepts(path, metadata.is_dir()) {
+            return;
+        }
+
+        let mut found = Files::new();
+        self.scan(path, &mut found);
+        write(files).extend(found);
+    }
+}
+
+/// Drops `path` and everything under it.
+fn remove(files: &mut Files, path: &Path) {
+    let doomed: Vec<PathBuf> = files
+        .range(path.to_path_buf()..)
+        .map(|(p, _)| p)
+        .take_while(|p| p.starts_with(path))
+        .cloned()
+        .collect();
+    for path in doomed {
+        files.remove(&path);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        path::{Path, PathBuf},
+        sync::RwLock,
+    };
+
+    use super::{Files, IndexRules, Indexer, exclusions, read, remove};
+    use crate::common::test_dir::TempDir;
+
+    fn indexer(root: &Path) -> Indexer {
+        let overrides = exclusions(root, &["node_modules/".to_string()]);
+        Indexer {
+            rules: IndexRules {
+                roots: vec![(root.to_path_buf(), overrides)],
+                show_hidden: false,
+            },
+            watches: None,
+        }
+    }
+
+    fn relative(files: &Files, root: &Path) -> Vec<String> {
+        files
+            .keys()
+            .map(|p| p.strip_prefix(root).unwrap().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn touch(path: PathBuf) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    #[test]
+    fn indexes_and_updates_respecting_exclusions() {
+        let root = TempDir::new("files");
+        fs::write(root.join(".gitignore"), "build/\n*.log\n").unwrap();
+        for path in [
+            "notes.txt",
+            ".hidden.txt",
+            "build/out.bin",
+            "node_modules/pkg/index.js",
+            "src/main.rs",
+        ] {
+            touch(root.join(path));
+        }
+
+        let mut indexer = indexer(&root);
+        let files = RwLock::new(indexer.scan_roots());
+        assert_eq!(
+            relative(&read(&files), &root),
+            ["notes.txt", "src", "src/main.rs"]
+        );
+
+        for path in ["src/lib.rs", "build/new.bin", "debug.log", ".cache"] {
+            touch(root.join(path));
+            indexer.add(&files, &root.join(path));
+        }
+        assert_eq!(
+            relative(&read(&files), &root),
+            ["notes.txt", "src", "src/lib.rs", "src/main.rs"]
+        );
+
+        remove(&mut files.write().unwrap(), &root.join("src"));
+        assert_eq!(relative(&read(&files), &root), ["notes.txt"]);
+    }
+}
@@ -0,0 +1,202 @@
+mod index;
+
+use std::{
+    cmp::Reverse,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Result, bail};
+
+pub(crate) use self::index::FileIndex;
+use self::index::IndexedFile;
+use super::{Action, CommandEntity, Outcome, Provider, SecondaryAction, display_path};
+use crate::{
+    Detail, Entity,
+    config::FilesConfig,
+    detail::{format_relative_time, format_size},
+};
+
+const PLUGIN: &str = "Files";
+/// Files listed by [`search_paths`].
+const PATH_RESULTS: usize = 50;
+
+/// Searches files and folders under the configured roots by name.
+pub(super) struct FilesProvider {
+    index: FileIndex,
+    min_query_length: usize,
+    max_results: usize,
+}
+
+impl FilesProvider {
+    pub fn new(config: &FilesConfig, index: FileIndex) -> Self {
+        Self {
+            index,
+            min_query_length: config.min_query_length,
+            max_results: config.max_results,
+        }
+    }
+}
+
+impl Provider for FilesProvider {
+    fn name(&self) -> &str {
+        PLUGIN
+    }
+
+    fn search(&self, query: &str) -> Vec<Entity> {
+        let query = query.trim();
+        if query.chars().count() < self.min_query_length.max(1) {
+            return Vec::new();
+        }
+        let needle = query.to_lowercase();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        let files = self.index.read();
+        let mut matches: Vec<(u32, &Path, &IndexedFile)> = files
+            .iter()
+            .filter_map(|(path, file)| Some((score(file, &needle, now)?, path.as_path(), file)))
+            .collect();
+        matches.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(b.2.modified.cmp(&a.2.modified)));
+
+        matches
+            .into_iter()
+            .take(self.max_results)
+            .map(|(_, path, file)| entity(path, file))
+            .collect()
+    }
+}
+
+/// Lists the indexed files whose path, rather than just name, contains
+/// `query`, more of them than the search results show.
+pub(super) fn search_paths(index: &FileIndex, query: &str) -> Result<Outcome> {
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        bail!("Nothing to search for");
+    }
+
+    let files = index.read();
+    let mut matches: Vec<(String, &Path, &IndexedFile)> = files
+        .iter()
+        .map(|(path, file)| (display_path(path), path.as_path(), file))
+        .filter(|(shown, _, _)| shown.to_lowercase().contains(&needle))
+        .collect();
+    if matches.is_empty() {
+        bail!("No files match “{}”", query.trim());
+    }
+    matches.sort_by_key(|(_, _, file)| Reverse(file.modified));
+
+    let actions = matches
+        .into_iter()
+        .take(PATH_RESULTS)
+        .map(|(shown, path, _)| {
+            let target = path.to_string_lossy().into_owned();
+            SecondaryAction::new(shown, Action::Open { target })
+        })
+        .collect();
+    Ok(Outcome::Choose {
+        title: format!("Files Matching “{}”", query.trim()),
+        actions,
+    })
+}
+
+/// Ranks how well `file`'s name matches `needle`, which must be lowercase,
+/// favouring recently modified files. `None` when it doesn't match.
+fn score(file: &IndexedFile, needle: &str, now: u64) -> Option<u32> {
+    let name = file.name.as_str();
+    let position = name.find(needle)?;
+    let stem = Path::new(name).file_stem().and_then(|s| s.to_str());
+
+    let quality = if name == needle {
+        400
+    } else if stem == Some(needle) {
+        350
+    } else if position == 0 {
+        300
+    } else if name[..position].ends_with(|c: char| !c.is_alphanumeric()) {
+        200
+    } else {
+        100
+    };
+
+    let recency = match now.saturating_sub(file.modified) {
+        0..86_400 => 50,
+        86_400..604_800 => 30,
+        604_800..2_592_000 => 15,
+        _ => 0,
+    };
+
+    Some(quality + recency)
+}
+
+fn entity(path: &Path, file: &IndexedFile) -> Entity {
+    let target = path.to_string_lossy().into_owned();
+    let name = path
+        .file_name()
+        .map_or_else(|| target.clone(), |n| n.to_string_lossy().into_owned());
+
+    let mut detail = Detail::default()
+        .with_row("Path", target.as_str())
+        .with_row("Kind", if file.is_dir { "Folder" } else { "File" })
+        .with_row("Modified", format_relative_time(file.modified));
+    if !file.is_dir
+        && let Ok(metadata) = fs::metadata(path)
+    {
+        detail = detail.with_row("Size", format_size(metadata.len()));
+    }
+
+    Entity::Command(CommandEntity {
+        name,
+        description: Some(display_path(path)),
+        plugin: Some(PLUGIN.to_string()),
+        detail: Some(detail),
+        action: Action::Open {
+            target: target.clone(),
+        },
+        secondary_actions: vec![
+            SecondaryAction::new(
+                "Open With…",
+                Action::ChooseApplication {
+                    target: target.clone(),
+                },
+            ),
+            SecondaryAction::new(
+                "Open Containing Folder",
+                Action::Reveal {
+                    path: path.to_path_buf(),
+                },
+            ),
+            SecondaryAction::new("Copy Path", Action::Copy { text: target }),
+        ],
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IndexedFile, score};
+
+    fn file(name: &str, modified: u64) -> IndexedFile {
+        IndexedFile {
+            name: name.to_string(),
+            is_dir: false,
+            modified,
+        }
+    }
+
+    #[test]
+    fn ranks_by_match_then_recency() {
+        let now = 100 * 86_400;
+        let old = 0;
+        let rank = |name: &str, modified| score(&file(name, modified), "report", now);
+
+        assert!(rank("report", old) > rank("report.pdf", old));
+        assert!(rank("report.pdf", old) > rank("report-2023.pdf", old));
+        assert!(rank("report-2023.pdf", old) > rank("q3 report.pdf", old));
+        assert!(rank("q3 report.pdf", old) > rank("myreport.pdf", old));
+        assert!(rank("myreport.pdf", now) > rank("myreport.pdf", old));
+        assert_eq!(rank("notes.txt", now), None);
+    }
+}
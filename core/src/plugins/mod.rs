@@ -1,6 +1,34 @@
+mod browsers;
+mod clipboard;
+mod emoji;
+mod fallbacks;
+mod files;
+mod placeholders;
+mod quicklinks;
+mod recent;
+mod shell;
+mod snippets;
+
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::{Context, Result, bail};
+use directories::BaseDirs;
+
 use crate::common::Image;
+use crate::{ActivationContext, Config, Detail, Entity, LaunchConfig};
 
-#[derive(Debug, Clone)]
+use self::browsers::BrowsersProvider;
+use self::clipboard::ClipboardProvider;
+use self::emoji::EmojiProvider;
+use self::files::{FileIndex, FilesProvider};
+use self::recent::RecentProvider;
+use self::shell::ShellProvider;
+use self::snippets::SnippetsProvider;
+
+#[derive(Debug, Clone, Default)]
 pub struct CommandEntity {
     pub id: u64,
     // plugin_ref: Plugin_Ref,
@@ -8,5 +36,379 @@ pub struct CommandEntity {
     pub alias: Option<String>,
     pub description: Option<String>,
     pub image: Option<Image>,
-    pub needs_argument: bool,
+    /// Icon theme names tried in order when there's no `image`, resolved
+    /// at the size the icon is shown at.
+    pub icon_names: Vec<String>,
+    /// Names of the arguments asked for before running it, in the order
+    /// `action` gets their values. Empty when it takes none.
+    pub arguments: Vec<String>,
+    /// The plugin that contributed this command; its results get their own
+    /// section.
+    pub plugin: Option<String>,
+    pub detail: Option<Detail>,
+    /// Kept out of the usage stats, which are stored as plain text, because
+    /// the name is private, like a clipboard entry.
+    pub private: bool,
+    /// Text shown in place of the icon, like an emoji.
+    pub symbol: Option<String>,
+    pub action: Action,
+    /// Alternatives to `action`, listed in the actions panel.
+    pub secondary_actions: Vec<SecondaryAction>,
+}
+
+/// What running a command does.
+#[derive(Debug, Clone, Default)]
+pub enum Action {
+    /// Placeholder for commands that don't do anything yet.
+    #[default]
+    None,
+    /// Runs a command line through the user's `$SHELL`.
+    Shell { command: String, mode: ShellMode },
+    /// Opens a path or URL with its default application.
+    Open { target: String },
+    /// Opens a path or URL with the application whose id is `app`.
+    OpenWith { target: String, app: String },
+    /// Lists the applications that can open a path or URL to pick from.
+    ChooseApplication { target: String },
+    /// Starts a program with the given arguments, without waiting for it.
+    Spawn { argv: Vec<String> },
+    /// Removes a URI from the recently used files.
+    ForgetRecent { uri: String },
+    /// Shows a file in the file manager.
+    Reveal { path: PathBuf },
+    /// Copies text to the clipboard.
+    Copy { text: String },
+    /// Opens a quicklink, filling in its placeholders with the argument,
+    /// the clipboard and the date, with the application `app` if given.
+    Quicklink { url: String, app: Option<String> },
+    /// Runs the argument as a command line through the user's `$SHELL`.
+    RunArgument { mode: ShellMode },
+    /// Lists the files of `index` whose path contains the argument.
+    SearchFiles { index: FileIndex },
+    /// Creates a note named after the argument in `folder` and opens it.
+    CreateNote { folder: String, extension: String },
+    /// Lists the results of `query`, as if it was typed.
+    Search { query: String },
+    /// Copies a clipboard history entry back to the clipboard.
+    CopyClip { id: u64 },
+    /// Copies a clipboard history entry and runs `paste` to paste it.
+    PasteClip { id: u64, paste: Vec<String> },
+    /// Pins or unpins a clipboard history entry.
+    PinClip { id: u64, pinned: bool },
+    /// Removes an entry from the clipboard history.
+    DeleteClip { id: u64 },
+    /// Copies a snippet with its placeholders filled in.
+    CopySnippet { text: String },
+    /// Types a snippet into the focused window with `type_command`, then
+    /// moves the cursor to its `{cursor}` with `cursor_left_command`.
+    TypeSnippet {
+        text: String,
+        type_command: Vec<String>,
+        cursor_left_command: Vec<String>,
+    },
+    /// Adds the snippets of the Raycast export at the argument's path.
+    ImportSnippets,
+    /// Saves the snippets in Raycast's format at the argument's path,
+    /// asking before replacing a file.
+    ExportSnippets,
+    /// Saves the snippets at `target`, replacing the file there.
+    ReplaceSnippetExport { target: PathBuf },
+}
+
+/// An entry of the actions panel.
+#[derive(Debug, Clone)]
+pub struct SecondaryAction {
+    pub title: String,
+    pub action: Action,
+}
+
+impl SecondaryAction {
+    pub fn new(title: impl Into<String>, action: Action) -> Self {
+        Self {
+            title: title.into(),
+            action,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShellMode {
+    /// Runs in the background and reports the captured output.
+    Silent,
+    /// Runs in a new terminal window, optionally kept open after it exits.
+    Terminal { hold: bool },
+}
+
+/// What running an entity produced besides its side effects.
+#[derive(Debug, Clone)]
+pub enum Outcome {
+    Done,
+    /// Text to show in the launcher, e.g. a command's output.
+    Output {
+        title: String,
+        text: String,
+    },
+    /// A list of further actions for the user to pick from.
+    Choose {
+        title: String,
+        actions: Vec<SecondaryAction>,
+    },
+    /// A query to search for, keeping the launcher open.
+    Search {
+        query: String,
+    },
+    /// The results changed; the query is searched again.
+    Refresh,
+    /// A command typing into the focused window, run once the launcher has
+    /// closed so that the window focused before it gets the input.
+    AfterClose {
+        argv: Vec<String>,
+    },
+}
+
+impl Action {
+    pub fn run(
+        &self,
+        arguments: Vec<String>,
+        launch: &LaunchConfig,
+        activation: &ActivationContext,
+    ) -> Result<Outcome> {
+        // Only snippets take more than one.
+        let argument = arguments.first().cloned();
+        match self {
+            Action::None => Ok(Outcome::Done),
+            Action::Shell { command, mode } => shell::run(command, *mode, launch),
+            Action::Open { target } => {
+                crate::open_with(target, None, launch, activation).map(|()| Outcome::Done)
+            }
+            Action::OpenWith { target, app } => {
+                crate::open_with(target, Some(app), launch, activation).map(|()| Outcome::Done)
+            }
+            Action::ChooseApplication { target } => choose_application(target),
+            Action::Spawn { argv } => {
+                crate::application::spawn(argv, launch, activation).map(|()| Outcome::Done)
+            }
+            Action::ForgetRecent { uri } => recent::forget(uri).map(|()| Outcome::Done),
+            Action::Reveal { path } => crate::reveal(path, activation).map(|()| Outcome::Done),
+            Action::Copy { text } => crate::copy(text).map(|()| Outcome::Done),
+            Action::Quicklink { url, app } => {
+                quicklinks::open(url, app.as_deref(), argument.as_deref(), launch, activation)
+                    .map(|()| Outcome::Done)
+            }
+            Action::RunArgument { mode } => {
+                let command = argument.context("No command to run")?;
+                shell::run(&command, *mode, launch)
+            }
+            Action::SearchFiles { index } => {
+                files::search_paths(index, argument.as_deref().unwrap_or_default())
+            }
+            Action::CreateNote { folder, extension } => {
+                let title = argument.context("A note needs a title")?;
+                fallbacks::create_note(folder, extension, &title, launch, activation)
+                    .map(|()| Outcome::Done)
+            }
+            Action::Search { query } => Ok(Outcome::Search {
+                query: query.clone(),
+            }),
+            Action::CopyClip { id } => clipboard::copy(*id).map(|()| Outcome::Done),
+            Action::PasteClip { id, paste } => clipboard::paste(*id, paste),
+            Action::PinClip { id, pinned } => {
+                clipboard::set_pinned(*id, *pinned).map(|()| Outcome::Refresh)
+            }
+            Action::DeleteClip { id } => clipboard::delete(*id).map(|()| Outcome::Refresh),
+            Action::CopySnippet { text } => {
+                snippets::copy(text, &arguments).map(|()| Outcome::Done)
+            }
+            Action::TypeSnippet {
+                text,
+                type_command,
+                cursor_left_command,
+            } => snippets::type_text(text, &arguments, type_command, cursor_left_command),
+            Action::ImportSnippets => {
+                snippets::import(&argument.context("No snippet file to import")?)
+            }
+            Action::ExportSnippets => {
+                snippets::export(&argument.context("No file to export the snippets to")?)
+            }
+            Action::ReplaceSnippetExport { target } => snippets::export_to(target),
+        }
+    }
+}
+
+/// `path` with a leading `~` standing for the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), BaseDirs::new()) {
+        (Some(rest), Some(dirs)) => dirs.home_dir().join(rest.trim_start_matches('/')),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// The path with the home directory shortened to `~`.
+fn display_path(path: &Path) -> String {
+    BaseDirs::new()
+        .and_then(|dirs| {
+            let relative = path.strip_prefix(dirs.home_dir()).ok()?;
+            Some(Path::new("~").join(relative).to_string_lossy().into_owned())
+        })
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+fn choose_application(target: &str) -> Result<Outcome> {
+    let applications = crate::open::applications_for(target)?;
+    if applications.is_empty() {
+        bail!("No application can open {target}");
+    }
+
+    let actions = applications
+        .into_iter()
+        .enumerate()
+        .map(|(i, (id, name, is_default))| {
+            // What opening without a choice uses, if the user or system
+            // configured it rather than it merely supporting the type.
+            let title = if i == 0 && is_default {
+                format!("{name} (Default)")
+            } else {
+                name
+            };
+            let action = Action::OpenWith {
+                target: target.to_string(),
+                app: id,
+            };
+            SecondaryAction::new(title, action)
+        })
+        .collect();
+    Ok(Outcome::Choose {
+        title: "Open With".to_string(),
+        actions,
+    })
+}
+
+/// A source of results computed from the query, as opposed to the fixed
+/// list loaded at startup.
+pub trait Provider: Send + Sync {
+    /// Names the provider in logs.
+    fn name(&self) -> &str;
+
+    /// A query prefix claiming the query for this provider, like `>` for
+    /// shell commands. Unprefixed providers only see unclaimed queries.
+    fn prefix(&self) -> Option<&str> {
+        None
+    }
+
+    /// Lays the results out in a grid of this many columns instead of a
+    /// list, for results shown as a [`CommandEntity::symbol`].
+    fn columns(&self) -> Option<usize> {
+        None
+    }
+
+    /// Results for `query`. Runs off the UI thread on every query change.
+    fn search(&self, query: &str) -> Vec<Entity>;
+}
+
+/// The providers enabled by the config, and the fixed commands plugins
+/// contribute.
+pub struct Plugins {
+    providers: Vec<Box<dyn Provider>>,
+    commands: Vec<Entity>,
+    fallbacks: Vec<Entity>,
+    fallback_min_results: usize,
+}
+
+impl Plugins {
+    pub fn new(config: &Config) -> Self {
+        let mut providers: Vec<Box<dyn Provider>> =
+            vec![Box::new(ShellProvider::new(&config.shell))];
+        // Shared by the files provider and the Search Files fallback.
+        let file_index = config
+            .files
+            .enabled
+            .then(|| FileIndex::spawn(&config.files));
+        if let Some(index) = &file_index {
+            providers.push(Box::new(FilesProvider::new(&config.files, index.clone())));
+        }
+        if config.recent.enabled {
+            providers.push(Box::new(RecentProvider::new(&config.recent)));
+        }
+        if config.browsers.enabled {
+            providers.push(Box::new(BrowsersProvider::new(&config.browsers)));
+        }
+        let mut commands = quicklinks::commands(&config.quicklinks);
+        if config.clipboard.enabled {
+            providers.push(Box::new(ClipboardProvider::new(&config.clipboard)));
+            commands.push(clipboard::command(&config.clipboard));
+        }
+        if config.snippets.enabled {
+            providers.push(Box::new(SnippetsProvider::new(&config.snippets)));
+            commands.extend(snippets::commands());
+        }
+        if config.emoji.enabled {
+            providers.push(Box::new(EmojiProvider::new(&config.emoji)));
+            commands.push(emoji::command(&config.emoji));
+        }
+        let fallbacks = fallbacks::commands(
+            &config.fallbacks.commands,
+            file_index.as_ref(),
+            config.shell.keep_terminal_open,
+        );
+        Self {
+            providers,
+            commands,
+            fallbacks,
+            fallback_min_results: config.fallbacks.min_results,
+        }
+    }
+
+    /// Commands listed alongside the applications, such as quicklinks.
+    pub fn commands(&self) -> Vec<Entity> {
+        self.commands.clone()
+    }
+
+    /// Commands run with the query as their argument, offered when little
+    /// else matches it.
+    pub fn fallbacks(&self) -> Vec<Entity> {
+        self.fallbacks.clone()
+    }
+
+    /// Whether to list the fallback commands for `query`, which `matches`
+    /// results were found for.
+    pub fn shows_fallbacks(&self, query: &str, matches: usize) -> bool {
+        !query.trim().is_empty() && matches < self.fallback_min_results
+    }
+
+    /// The grid columns of the provider claiming `query`, if it shows its
+    /// results in a grid.
+    pub fn grid_columns(&self, query: &str) -> Option<usize> {
+        let trimmed = query.trim_start();
+        self.providers
+            .iter()
+            .find(|p| p.prefix().is_some_and(|prefix| trimmed.starts_with(prefix)))?
+            .columns()
+    }
+
+    pub fn search(&self, query: &str) -> Vec<Entity> {
+        let trimmed = query.trim_start();
+        let claimed = self
+            .providers
+            .iter()
+            .any(|p| p.prefix().is_some_and(|prefix| trimmed.starts_with(prefix)));
+
+        self.providers
+            .iter()
+            .filter(|p| match p.prefix() {
+                Some(prefix) => trimmed.starts_with(prefix),
+                None => !claimed,
+            })
+            .flat_map(|provider| {
+                let started = Instant::now();
+                let results = provider.search(query);
+                tracing::debug!(
+                    provider = provider.name(),
+                    results = results.len(),
+                    elapsed = ?started.elapsed(),
+                    "Searched provider"
+                );
+                results
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,150 @@
+//! The `{name}` placeholders of quicklinks and snippets, like `{clipboard}`,
+//! `{date:%Y-%m-%d}` or `{argument name="who" default="you"}`.
+
+use anyhow::Result;
+
+pub(super) struct Placeholder<'a> {
+    pub name: &'a str,
+    /// What follows a `:`, like the format of `{date:%Y}`.
+    pub format: Option<&'a str>,
+    /// The `key="value"` pairs, like in `{argument name="x"}`.
+    attributes: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Placeholder<'a> {
+    pub fn attribute(&self, key: &str) -> Option<&'a str> {
+        self.attributes
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// A piece of a text with placeholders.
+pub(super) enum Segment<'a> {
+    Text(&'a str),
+    /// A placeholder, along with its source text, braces included.
+    Placeholder(Placeholder<'a>, &'a str),
+}
+
+/// Splits `text` into plain text and placeholders. Braces that don't
+/// enclose a placeholder, like those of code in a snippet, are text.
+pub(super) fn segments(text: &str) -> impl Iterator<Item = Segment<'_>> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut from = 0;
+        while let Some(start) = rest[from..].find('{').map(|start| from + start) {
+            let parsed = rest[start..]
+                .find('}')
+                .map(|end| start + end)
+                .and_then(|end| Some((end, parse(&rest[start + 1..end])?)));
+            let Some((end, placeholder)) = parsed else {
+                from = start + 1;
+                continue;
+            };
+
+            let segment = if start == 0 {
+                let source = &rest[..=end];
+                rest = &rest[end + 1..];
+                Segment::Placeholder(placeholder, source)
+            } else {
+                let text = &rest[..start];
+                rest = &rest[start..];
+                Segment::Text(text)
+            };
+            return Some(segment);
+        }
+        let text = rest;
+        rest = "";
+        Some(Segment::Text(text))
+    })
+}
+
+/// The placeholders of `text`.
+pub(super) fn placeholders(text: &str) -> impl Iterator<Item = Placeholder<'_>> {
+    segments(text).filter_map(|segment| match segment {
+        Segment::Placeholder(placeholder, _) => Some(placeholder),
+        Segment::Text(_) => None,
+    })
+}
+
+/// The text on the clipboard when `text` has a `{clipboard}` placeholder.
+/// Reading the clipboard runs a program, so it's only done when needed.
+pub(super) fn clipboard_for(text: &str) -> Result<Option<String>> {
+    if placeholders(text).any(|placeholder| placeholder.name == "clipboard") {
+        crate::open::paste().map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parses the inside of `{...}`, or `None` if it isn't a placeholder.
+fn parse(inner: &str) -> Option<Placeholder<'_>> {
+    let end = inner.find([':', ' ']).unwrap_or(inner.len());
+    let name = &inner[..end];
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut placeholder = Placeholder {
+        name,
+        format: None,
+        attributes: Vec::new(),
+    };
+    let mut rest = &inner[end..];
+    if let Some(format) = rest.strip_prefix(':') {
+        placeholder.format = Some(format);
+        return Some(placeholder);
+    }
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some(placeholder);
+        }
+        let (key, after) = rest.split_once("=\"")?;
+        let (value, after) = after.split_once('"')?;
+        placeholder.attributes.push((key.trim(), value));
+        rest = after;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Segment, placeholders, segments};
+
+    #[test]
+    fn splits_text_and_placeholders() {
+        let parts: Vec<String> = segments("fn x() {} {date:%Y} {{argument name=\"a b\"}")
+            .map(|segment| match segment {
+                Segment::Text(text) => format!("text {text:?}"),
+                Segment::Placeholder(placeholder, source) => {
+                    format!("{} {source}", placeholder.name)
+                }
+            })
+            .collect();
+        assert_eq!(
+            parts,
+            [
+                r#"text "fn x() {} ""#,
+                "date {date:%Y}",
+                r#"text " {""#,
+                r#"argument {argument name="a b"}"#,
+            ]
+        );
+
+        let found: Vec<_> = placeholders("https://x/{query}?d={date:%d}&{")
+            .map(|placeholder| (placeholder.name, placeholder.format))
+            .collect();
+        assert_eq!(found, [("query", None), ("date", Some("%d"))]);
+
+        let argument = placeholders("{argument name=\"who\" default=\"you\"}")
+            .next()
+            .unwrap();
+        assert_eq!(argument.attribute("name"), Some("who"));
+        assert_eq!(argument.attribute("default"), Some("you"));
+        assert!(placeholders("if (a) { b } {}").next().is_none());
+    }
+}
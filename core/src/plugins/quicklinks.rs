@@ -0,0 +1,186 @@
+use anyhow::{Context, Result, bail};
+use jiff::{Zoned, fmt::strtime};
+
+use super::placeholders::{Segment, clipboard_for, placeholders, segments};
+use super::{Action, CommandEntity, expand_home};
+use crate::{ActivationContext, Detail, Entity, LaunchConfig, common::percent_encode};
+
+const PLUGIN: &str = "Quicklinks";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// The configured quicklinks, as commands.
+pub(super) fn commands(quicklinks: &[crate::Quicklink]) -> Vec<Entity> {
+    quicklinks
+        .iter()
+        .map(|link| {
+            let mut detail = Detail::default().with_row("URL", link.url.as_str());
+            if let Some(app) = &link.app {
+                detail = detail.with_row("Opens With", app.as_str());
+            }
+            let icon = if is_url(&link.url) {
+                "web-browser"
+            } else {
+                "document-open"
+            };
+
+            let arguments = if placeholders(&link.url)
+                .any(|placeholder| matches!(placeholder.name, "query" | "argument"))
+            {
+                vec!["Query".to_string()]
+            } else {
+                Vec::new()
+            };
+
+            Entity::Command(CommandEntity {
+                name: link.name.clone(),
+                alias: link.alias.clone(),
+                description: Some(link.url.clone()),
+                icon_names: vec![icon.to_string()],
+                arguments,
+                plugin: Some(PLUGIN.to_string()),
+                detail: Some(detail),
+                action: Action::Quicklink {
+                    url: link.url.clone(),
+                    app: link.app.clone(),
+                },
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Opens the quicklink `url` with its placeholders filled in.
+pub(super) fn open(
+    url: &str,
+    app: Option<&str>,
+    argument: Option<&str>,
+    launch: &LaunchConfig,
+    activation: &ActivationContext,
+) -> Result<()> {
+    let clipboard = clipboard_for(url)?;
+    let values = Values {
+        argument,
+        clipboard: clipboard.as_deref(),
+        now: Zoned::now(),
+    };
+    let mut target = expand(url, &values)?;
+    if !is_url(&target) {
+        target = expand_home(&target).to_string_lossy().into_owned();
+    }
+    tracing::info!(%target, "Opening quicklink");
+    crate::open_with(&target, app, launch, activation)
+}
+
+struct Values<'a> {
+    argument: Option<&'a str>,
+    clipboard: Option<&'a str>,
+    now: Zoned,
+}
+
+/// `url` with its placeholders replaced, percent-encoded when `url` is a
+/// URL rather than a path. Unknown placeholders are left as they are.
+fn expand(url: &str, values: &Values) -> Result<String> {
+    let encode = is_url(url);
+    let mut expanded = String::with_capacity(url.len());
+
+    for segment in segments(url) {
+        let (placeholder, source) = match segment {
+            Segment::Text(text) => {
+                expanded.push_str(text);
+                continue;
+            }
+            Segment::Placeholder(placeholder, source) => (placeholder, source),
+        };
+
+        let value = match placeholder.name {
+            "query" | "argument" => values
+                .argument
+                .context("This quicklink needs an argument")?
+                .to_string(),
+            "clipboard" => values
+                .clipboard
+                .context("The clipboard is empty")?
+                .to_string(),
+            "date" => strtime::format(placeholder.format.unwrap_or(DATE_FORMAT), &values.now)
+                .with_context(|| format!("Invalid date format in {url}"))?,
+            _ => {
+                expanded.push_str(source);
+                continue;
+            }
+        };
+        if encode {
+            expanded.push_str(&percent_encode(&value));
+        } else {
+            expanded.push_str(&value);
+        }
+    }
+
+    if expanded.trim().is_empty() {
+        bail!("The quicklink {url} is empty");
+    }
+    Ok(expanded)
+}
+
+/// Whether `target` starts with a URL scheme, as opposed to being a path.
+fn is_url(target: &str) -> bool {
+    target.split_once(':').is_some_and(|(scheme, _)| {
+        // Longer than a drive letter, like in `C:\`.
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use jiff::{Zoned, civil::date, tz::TimeZone};
+
+    use super::{Values, expand, is_url};
+
+    #[test]
+    fn expands_and_encodes_placeholders() {
+        let values = Values {
+            argument: Some("tokio select!"),
+            clipboard: Some("PROJ-42"),
+            now: date(2024, 3, 1)
+                .at(9, 30, 0, 0)
+                .to_zoned(TimeZone::UTC)
+                .unwrap(),
+        };
+        let expanded = |url: &str| expand(url, &values).unwrap();
+
+        assert_eq!(
+            expanded("https://docs.rs/releases/search?query={argument}"),
+            "https://docs.rs/releases/search?query=tokio%20select%21"
+        );
+        assert_eq!(
+            expanded("https://jira.example.com/browse/{clipboard}?on={date}"),
+            "https://jira.example.com/browse/PROJ-42?on=2024-03-01"
+        );
+        assert_eq!(
+            expanded("~/Notes/{date:%Y/%m}/{query}.md"),
+            "~/Notes/2024/03/tokio select!.md"
+        );
+        assert_eq!(
+            expanded("https://example.com/{unknown}/{query"),
+            "https://example.com/{unknown}/{query"
+        );
+
+        let missing = Values {
+            argument: None,
+            clipboard: None,
+            now: Zoned::now(),
+        };
+        assert!(expand("https://example.com/{query}", &missing).is_err());
+    }
+
+    #[test]
+    fn tells_urls_from_paths() {
+        assert!(is_url("https://example.com"));
+        assert!(is_url("mailto:{clipboard}"));
+        assert!(!is_url("~/Notes/{query}.md"));
+        assert!(!is_url(r"C:\Notes"));
+    }
+}
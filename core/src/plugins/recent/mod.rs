@@ -0,0 +1,261 @@
+mod xbel;
+
+use std::{
+    cmp::Reverse,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, mpsc},
+    thread,
+};
+
+use anyhow::{Context, Result, bail};
+use directories::BaseDirs;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use self::xbel::RecentItem;
+use super::{Action, CommandEntity, Provider, SecondaryAction, display_path};
+use crate::{
+    Detail, Entity,
+    common::{local_path, percent_decode, write_atomically},
+    config::RecentConfig,
+    detail::format_relative_time,
+};
+
+const PLUGIN: &str = "Recent Files";
+const FILE_NAME: &str = "recently-used.xbel";
+
+/// Searches the files GTK applications recorded as recently used.
+pub(super) struct RecentProvider {
+    items: Arc<RwLock<Vec<RecentItem>>>,
+    max_results: usize,
+}
+
+impl RecentProvider {
+    pub fn new(config: &RecentConfig) -> Self {
+        let items = Arc::new(RwLock::new(Vec::new()));
+
+        if let Some(path) = xbel_path() {
+            let shared = Arc::clone(&items);
+            if let Err(e) = thread::Builder::new()
+                .name("recent-files".to_string())
+                .spawn(move || watch(&path, &shared))
+            {
+                tracing::error!("Failed to start the recent files watcher: {e}");
+            }
+        }
+
+        Self {
+            items,
+            max_results: config.max_results,
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Vec<RecentItem>> {
+        self.items.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Provider for RecentProvider {
+    fn name(&self) -> &str {
+        PLUGIN
+    }
+
+    fn search(&self, query: &str) -> Vec<Entity> {
+        let needle = query.trim().to_lowercase();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+
+        // Items are sorted by recency; names starting with the query go
+        // first.
+        let items = self.read();
+        let mut matches: Vec<(bool, &RecentItem, String)> = items
+            .iter()
+            .filter_map(|item| {
+                let name = item_name(&item.uri);
+                let position = name.to_lowercase().find(&needle)?;
+                Some((position != 0, item, name))
+            })
+            .collect();
+        matches.sort_by_key(|(not_prefix, _, _)| *not_prefix);
+
+        matches
+            .into_iter()
+            // Files that were moved or deleted since can't be opened.
+            .filter(|(_, item, _)| {
+                local_path(&item.uri).is_none_or(|path| Path::new(&path).exists())
+            })
+            .take(self.max_results)
+            .map(|(_, item, name)| entity(item, name))
+            .collect()
+    }
+}
+
+/// Removes `uri` from the recently used files.
+pub(super) fn forget(uri: &str) -> Result<()> {
+    let path = xbel_path().context("No home directory")?;
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let Some(updated) = xbel::remove(&content, uri)
+        .with_context(|| format!("Failed to parse {}", path.display()))?
+    else {
+        bail!("{uri} isn't in the recent files");
+    };
+
+    // Replaced at once, like GTK does.
+    write_atomically(&path, updated)
+}
+
+fn xbel_path() -> Option<PathBuf> {
+    BaseDirs::new().map(|dirs| dirs.data_dir().join(FILE_NAME))
+}
+
+/// Loads the recent files and reloads them whenever the file changes.
+fn watch(path: &Path, items: &RwLock<Vec<RecentItem>>) {
+    let store = |loaded| *items.write().unwrap_or_else(PoisonError::into_inner) = loaded;
+
+    let (tx, rx) = mpsc::channel();
+    let watcher = notify::recommended_watcher(tx).and_then(|mut watcher| {
+        // GTK replaces the file rather than writing to it, so the folder
+        // is watched instead.
+        let folder = path.parent().unwrap_or(path);
+        watcher.watch(folder, RecursiveMode::NonRecursive)?;
+        Ok(watcher)
+    });
+    store(load(path));
+
+    let _watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            tracing::warn!(
+                "Can't watch {}, recent files won't update: {e}",
+                path.display()
+            );
+            return;
+        }
+    };
+    for result in rx {
+        match result {
+            Ok(event)
+                if !matches!(event.kind, EventKind::Access(_))
+                    && event.paths.iter().any(|changed| changed == path) =>
+            {
+                store(load(path));
+            }
+            Ok(_) => {}
+            Err(e) => tracing::debug!("Recent files watcher error: {e}"),
+        }
+    }
+}
+
+/// The recorded items, most recently used first.
+fn load(path: &Path) -> Vec<RecentItem> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Vec::new(),
+        Err(e) => {
+            tracing::warn!("Failed to read {}: {e}", path.display());
+            return Vec::new();
+        }
+    };
+    match xbel::parse(&content) {
+        Ok(mut items) => {
+            items.sort_by_key(|item| Reverse(item.used));
+            items
+        }
+        Err(e) => {
+            tracing::warn!("Failed to parse {}: {e}", path.display());
+            Vec::new()
+        }
+    }
+}
+
+/// The file name, or the last segment of a remote URI.
+fn item_name(uri: &str) -> String {
+    let trimmed = uri.trim_end_matches('/');
+    let segment = trimmed.rsplit('/').next().unwrap_or(trimmed);
+    percent_decode(segment)
+}
+
+fn entity(item: &RecentItem, name: String) -> Entity {
+    let path = local_path(&item.uri).map(PathBuf::from);
+    let target = path.as_ref().map_or_else(
+        || item.uri.clone(),
+        |path| path.to_string_lossy().into_owned(),
+    );
+    let location = match &path {
+        Some(path) => display_path(path.parent().unwrap_or(path)),
+        None => item.uri.clone(),
+    };
+
+    let mut detail = Detail::default().with_row("Location", location.as_str());
+    if let Some(mime_type) = &item.mime_type {
+        detail = detail.with_row("Type", mime_type.as_str());
+    }
+    detail = detail.with_row("Last Used", format_relative_time(item.used));
+
+    let mut description = location;
+    let mut secondary_actions = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+    for app in &item.applications {
+        if seen.contains(&app.name.as_str()) {
+            continue;
+        }
+        seen.push(&app.name);
+        let path = path.as_ref().map(|path| path.to_string_lossy());
+        if let Some(argv) = app.argv(&item.uri, path.as_deref()) {
+            secondary_actions.push(SecondaryAction::new(
+                format!("Open with {}", app.name),
+                Action::Spawn { argv },
+            ));
+        }
+    }
+    if let Some(last) = seen.first() {
+        description = format!("{description} · {last}");
+        detail = detail.with_row("Opened With", seen.join(", "));
+    }
+    secondary_actions.push(SecondaryAction::new(
+        "Remove from Recent Files",
+        Action::ForgetRecent {
+            uri: item.uri.clone(),
+        },
+    ));
+
+    Entity::Command(CommandEntity {
+        name,
+        description: Some(description),
+        icon_names: icon_names(item.mime_type.as_deref(), &target),
+        plugin: Some(PLUGIN.to_string()),
+        detail: Some(detail),
+        action: Action::Open { target },
+        secondary_actions,
+        ..Default::default()
+    })
+}
+
+/// Themed icons for the item's MIME type, guessed when GTK didn't record
+/// one.
+#[cfg(target_os = "linux")]
+fn icon_names(mime_type: Option<&str>, target: &str) -> Vec<String> {
+    let mime_type = mime_type.map_or_else(|| crate::mime::mime_type(target), String::from);
+    crate::mime::icon_names(&mime_type)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn icon_names(_mime_type: Option<&str>, _target: &str) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod test {
+    use super::item_name;
+
+    #[test]
+    fn names_local_and_remote_items() {
+        assert_eq!(
+            item_name("file:///home/me/Report%20Q3.pdf"),
+            "Report Q3.pdf"
+        );
+        assert_eq!(item_name("sftp://host/srv/share/"), "share");
+    }
+}
@@ -0,0 +1,317 @@
+//! The XBEL bookmark file GTK applications record recently used files in.
+
+use std::cmp::Reverse;
+
+use jiff::{Timestamp, civil::DateTime, tz::TimeZone};
+use roxmltree::{Document, Node};
+
+const BOOKMARK_NS: &str = "http://www.freedesktop.org/standards/desktop-bookmarks";
+const MIME_NS: &str = "http://www.freedesktop.org/standards/shared-mime-info";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct RecentItem {
+    pub uri: String,
+    pub mime_type: Option<String>,
+    /// When the item was last used, as a unix timestamp.
+    pub used: u64,
+    /// The applications that used it, most recent first.
+    pub applications: Vec<RecordedApp>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct RecordedApp {
+    pub name: String,
+    /// The command line as GLib stores it: shell-quoted, with `%u` or `%f`
+    /// standing for the file.
+    pub exec: String,
+    pub used: u64,
+    pub count: u32,
+}
+
+impl RecordedApp {
+    /// The argv that reopens the item at `uri`, whose local path, if any,
+    /// is `path`.
+    pub fn argv(&self, uri: &str, path: Option<&str>) -> Option<Vec<String>> {
+        let line = shell_split(&self.exec)?.join(" ");
+        let argv: Vec<String> = shell_split(&line)?
+            .iter()
+            .map(|arg| expand(arg, uri, path))
+            .filter(|arg| !arg.is_empty())
+            .collect();
+        (!argv.is_empty()).then_some(argv)
+    }
+}
+
+pub(super) fn parse(content: &str) -> Result<Vec<RecentItem>, roxmltree::Error> {
+    let doc = Document::parse(content)?;
+    Ok(bookmarks(&doc).filter_map(item).collect())
+}
+
+/// `content` without the bookmark for `uri`, or `None` when it has none.
+/// The rest of the file is kept as it was.
+pub(super) fn remove(content: &str, uri: &str) -> Result<Option<String>, roxmltree::Error> {
+    let doc = Document::parse(content)?;
+    let Some(bookmark) = bookmarks(&doc).find(|node| node.attribute("href") == Some(uri)) else {
+        return Ok(None);
+    };
+
+    // Take the bookmark's line along: its indentation and line break.
+    let range = bookmark.range();
+    let start = content[..range.start].trim_end_matches([' ', '\t']).len();
+    let end = range.end + usize::from(content[range.end..].starts_with('\n'));
+    Ok(Some(format!("{}{}", &content[..start], &content[end..])))
+}
+
+fn bookmarks<'a, 'input>(doc: &'a Document<'input>) -> impl Iterator<Item = Node<'a, 'input>> {
+    doc.root_element()
+        .children()
+        .filter(|node| node.has_tag_name("bookmark"))
+}
+
+fn item(bookmark: Node) -> Option<RecentItem> {
+    let uri = bookmark.attribute("href")?.to_string();
+    let used = ["modified", "visited", "added"]
+        .into_iter()
+        .filter_map(|name| parse_timestamp(bookmark.attribute(name)?))
+        .max()
+        .unwrap_or(0);
+
+    let metadata: Vec<Node> = bookmark
+        .descendants()
+        .filter(|node| node.has_tag_name("metadata"))
+        .flat_map(|metadata| metadata.descendants())
+        .collect();
+
+    let mime_type = metadata
+        .iter()
+        .find(|node| node.has_tag_name((MIME_NS, "mime-type")))
+        .and_then(|node| node.attribute("type"))
+        .map(String::from);
+
+    let mut applications: Vec<RecordedApp> = metadata
+        .iter()
+        .filter(|node| node.has_tag_name((BOOKMARK_NS, "application")))
+        .filter_map(|node| {
+            // Files written by old GLib versions carry unix timestamps.
+            let used = node
+                .attribute("modified")
+                .and_then(parse_timestamp)
+                .or_else(|| node.attribute("timestamp")?.parse().ok())
+                .unwrap_or(0);
+            Some(RecordedApp {
+                name: node.attribute("name")?.to_string(),
+                exec: node.attribute("exec")?.to_string(),
+                used,
+                count: node
+                    .attribute("count")
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or(1),
+            })
+        })
+        .collect();
+    applications.sort_by_key(|app| Reverse(app.used));
+
+    Some(RecentItem {
+        uri,
+        mime_type,
+        used,
+        applications,
+    })
+}
+
+/// Parses the ISO 8601 timestamps GLib writes, like
+/// `2024-03-01T12:34:56.123456Z`, into unix seconds. Those without an
+/// offset are in UTC.
+fn parse_timestamp(value: &str) -> Option<u64> {
+    let timestamp = value.parse::<Timestamp>().ok().or_else(|| {
+        let civil: DateTime = value.parse().ok()?;
+        civil
+            .to_zoned(TimeZone::UTC)
+            .ok()
+            .map(|zoned| zoned.timestamp())
+    })?;
+    u64::try_from(timestamp.as_second()).ok()
+}
+
+/// Splits a command line into words following POSIX shell quoting.
+/// `None` when a quote isn't closed.
+fn shell_split(line: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            escaped @ ('"' | '\\' | '$' | '`') => word.push(escaped),
+                            '\n' => {}
+                            other => {
+                                word.push('\\');
+                                word.push(other);
+                            }
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(escaped) = chars.next() {
+                    word.push(escaped);
+                }
+            }
+            ' ' | '\t' | '\n' => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Some(words)
+}
+
+/// Substitutes `%u` with the URI and `%f` with the local path, if any.
+fn expand(arg: &str, uri: &str, path: Option<&str>) -> String {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('u') => expanded.push_str(uri),
+            Some('f') => expanded.push_str(path.unwrap_or(uri)),
+            Some('%') => expanded.push('%'),
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RecordedApp, parse, parse_timestamp, remove};
+
+    const XBEL: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0"
+      xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks"
+      xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info"
+>
+  <bookmark href="file:///home/me/Report%20Q3.pdf" added="2024-03-01T09:00:00.000000Z" modified="2024-03-01T09:00:00.000000Z" visited="2024-03-02T10:30:00.5Z">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="application/pdf"/>
+        <bookmark:applications>
+          <bookmark:application name="Document Viewer" exec="&apos;evince %u&apos;" modified="2024-03-01T09:00:00Z" count="1"/>
+          <bookmark:application name="Drawing" exec="&apos;draw --open %f&apos;" modified="2024-03-02T10:30:00Z" count="3"/>
+        </bookmark:applications>
+      </metadata>
+    </info>
+  </bookmark>
+  <bookmark href="https://example.com/notes" added="2024-01-01T00:00:00+01:00">
+  </bookmark>
+</xbel>
+"#;
+
+    #[test]
+    fn parses_bookmarks() {
+        let items = parse(XBEL).unwrap();
+        assert_eq!(items.len(), 2);
+
+        let report = &items[0];
+        assert_eq!(report.uri, "file:///home/me/Report%20Q3.pdf");
+        assert_eq!(report.mime_type.as_deref(), Some("application/pdf"));
+        assert_eq!(report.used, 1_709_375_400);
+        let names: Vec<&str> = report
+            .applications
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(names, ["Drawing", "Document Viewer"]);
+        assert_eq!(report.applications[0].count, 3);
+
+        let notes = &items[1];
+        assert_eq!(notes.used, 1_704_063_600);
+        assert!(notes.applications.is_empty() && notes.mime_type.is_none());
+
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2000-02-29T12:00:00-02:30"),
+            Some(951_834_600)
+        );
+        assert_eq!(
+            parse_timestamp("2024-03-01T12:34:56.123456"),
+            Some(1_709_296_496)
+        );
+        assert_eq!(
+            parse_timestamp("2000-02-29T12:00:00-0230"),
+            Some(951_834_600)
+        );
+        assert_eq!(parse_timestamp("99999999999-01-01T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("yesterday"), None);
+    }
+
+    #[test]
+    fn builds_the_recorded_command() {
+        let app = |exec: &str| RecordedApp {
+            name: String::new(),
+            exec: exec.to_string(),
+            used: 0,
+            count: 1,
+        };
+        let uri = "file:///home/me/Report%20Q3.pdf";
+        let path = Some("/home/me/Report Q3.pdf");
+
+        assert_eq!(app("'evince %u'").argv(uri, path).unwrap(), ["evince", uri]);
+        assert_eq!(
+            app("'draw --open %f'").argv(uri, path).unwrap(),
+            ["draw", "--open", "/home/me/Report Q3.pdf"]
+        );
+        assert_eq!(
+            app(r#"'sh -c "open \"$0\"" %f'"#).argv(uri, None).unwrap(),
+            ["sh", "-c", r#"open "$0""#, uri]
+        );
+        assert_eq!(app("'unterminated").argv(uri, path), None);
+    }
+
+    #[test]
+    fn removes_a_bookmark_keeping_the_rest() {
+        let updated = remove(XBEL, "file:///home/me/Report%20Q3.pdf")
+            .unwrap()
+            .unwrap();
+        let items = parse(&updated).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].uri, "https://example.com/notes");
+        assert!(updated.starts_with(&XBEL[..XBEL.find("  <bookmark").unwrap()]));
+
+        assert_eq!(remove(XBEL, "file:///missing").unwrap(), None);
+    }
+}
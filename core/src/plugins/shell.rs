@@ -0,0 +1,423 @@
+use std::{
+    env, fs,
+    io::{self, Read},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::{
+        Mutex, PoisonError,
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{Action, CommandEntity, Outcome, Provider, ShellMode};
+use crate::{Detail, Entity, LaunchConfig, application, common::data_file, config::ShellConfig};
+
+const PREFIX: &str = ">";
+const PLUGIN: &str = "Shell";
+/// Past commands kept in the history file.
+const HISTORY_LIMIT: usize = 200;
+/// Past commands listed for a query.
+const HISTORY_RESULTS: usize = 10;
+/// How long a silent command may run before it is killed.
+const SILENT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Bytes of a silent command's output that are kept.
+const OUTPUT_LIMIT: usize = 64 * 1024;
+/// How long output is still read after a silent command exited, for what
+/// it wrote just before.
+const EXIT_GRACE: Duration = Duration::from_millis(100);
+/// How often a silent command is checked for having exited while waiting
+/// for its output.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Offers to run `> command` queries, along with matching past commands.
+pub(super) struct ShellProvider {
+    hold: bool,
+    history: Mutex<HistoryCache>,
+}
+
+/// The history as of its last change, read again once a run saved it.
+#[derive(Default)]
+struct HistoryCache {
+    modified: Option<SystemTime>,
+    history: History,
+}
+
+impl HistoryCache {
+    fn refresh(&mut self) {
+        let modified = fs::metadata(History::path())
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        if modified != self.modified {
+            self.history = History::load();
+            self.modified = modified;
+        }
+    }
+}
+
+impl ShellProvider {
+    pub fn new(config: &ShellConfig) -> Self {
+        Self {
+            hold: config.keep_terminal_open,
+            history: Mutex::default(),
+        }
+    }
+
+    fn entity(&self, command: &str, mode: ShellMode, description: &str) -> Entity {
+        Entity::Command(CommandEntity {
+            name: command.to_string(),
+            description: Some(description.to_string()),
+            plugin: Some(PLUGIN.to_string()),
+            detail: Some(Detail::default().with_row("Shell", shell())),
+            action: Action::Shell {
+                command: command.to_string(),
+                mode,
+            },
+            ..Default::default()
+        })
+    }
+}
+
+impl Provider for ShellProvider {
+    fn name(&self) -> &str {
+        PLUGIN
+    }
+
+    fn prefix(&self) -> Option<&str> {
+        Some(PREFIX)
+    }
+
+    fn search(&self, query: &str) -> Vec<Entity> {
+        let Some(command) = parse_query(query) else {
+            return Vec::new();
+        };
+        let terminal = ShellMode::Terminal { hold: self.hold };
+
+        let mut results = Vec::new();
+        if !command.is_empty() {
+            results.push(self.entity(command, ShellMode::Silent, "Run in shell"));
+            results.push(self.entity(command, terminal, "Run in terminal"));
+        }
+
+        let needle = command.to_lowercase();
+        let mut cache = self.history.lock().unwrap_or_else(PoisonError::into_inner);
+        cache.refresh();
+        results.extend(
+            cache
+                .history
+                .matching(&needle)
+                .filter(|entry| entry.command != command)
+                .take(HISTORY_RESULTS)
+                .map(|entry| {
+                    let mode = if entry.terminal {
+                        terminal
+                    } else {
+                        ShellMode::Silent
+                    };
+                    self.entity(&entry.command, mode, "Run again")
+                }),
+        );
+        results
+    }
+}
+
+/// The command of a `> command` query, trimmed.
+fn parse_query(query: &str) -> Option<&str> {
+    query.trim_start().strip_prefix(PREFIX).map(str::trim)
+}
+
+pub(super) fn shell() -> String {
+    env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+}
+
+/// Runs `command` with the user's shell and records it in the history.
+pub(super) fn run(command: &str, mode: ShellMode, launch: &LaunchConfig) -> Result<Outcome> {
+    let argv = shell_argv(&shell(), command);
+    tracing::info!(command, ?mode, "Running shell command");
+
+    let outcome = match mode {
+        ShellMode::Terminal { hold } => {
+            application::run_in_terminal(&argv, command, hold, launch)?;
+            Outcome::Done
+        }
+        ShellMode::Silent => {
+            let output = run_silent(&argv, SILENT_TIMEOUT, OUTPUT_LIMIT)?;
+            let title = match output.status {
+                Exit::Success => command.to_string(),
+                Exit::Failed(status) => format!("{command} ({status})"),
+                Exit::TimedOut => format!("{command} (killed after {}s)", SILENT_TIMEOUT.as_secs()),
+            };
+            let mut text = String::from_utf8_lossy(&output.text).into_owned();
+            if output.truncated {
+                text.push_str("\n…");
+            }
+            Outcome::Output { title, text }
+        }
+    };
+
+    let mut history = History::load();
+    history.record(command, matches!(mode, ShellMode::Terminal { .. }));
+    if let Err(e) = history.save() {
+        tracing::warn!("Failed to save shell history: {e:#}");
+    }
+    Ok(outcome)
+}
+
+fn shell_argv(shell: &str, command: &str) -> [String; 3] {
+    [shell.to_string(), "-c".to_string(), command.to_string()]
+}
+
+/// What a silent command printed, stdout and stderr interleaved.
+#[derive(Debug)]
+struct SilentOutput {
+    status: Exit,
+    text: Vec<u8>,
+    /// Whether the output went past the limit and was cut off.
+    truncated: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Exit {
+    Success,
+    Failed(std::process::ExitStatus),
+    TimedOut,
+}
+
+/// Runs `argv` without a terminal, keeping at most `limit` bytes of its
+/// output and killing it once `timeout` passes.
+fn run_silent(argv: &[String], timeout: Duration, limit: usize) -> Result<SilentOutput> {
+    let (mut reader, writer) = io::pipe()?;
+    let mut child = {
+        // The command keeps copies of the write end; it is dropped right
+        // after spawning so the reader sees the end of the output.
+        let mut cmd = Command::new(&argv[0]);
+        cmd.args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(writer.try_clone()?)
+            .stderr(writer);
+        cmd.spawn()
+            .with_context(|| format!("Failed to run {}", argv[0]))?
+    };
+    let deadline = Instant::now() + timeout;
+
+    // Reads on a thread of its own, so the deadline holds even if the
+    // command stays silent. Background processes it started may keep the
+    // pipe open past its exit, so reading stops shortly after it exits;
+    // the thread then ends with them.
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0; 8192];
+        while let Ok(read @ 1..) = reader.read(&mut buffer) {
+            if sender.send(buffer[..read].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut text = Vec::new();
+    let mut truncated = false;
+    let mut read_until = deadline;
+    let mut exited = false;
+    while let Some(left) = read_until.checked_duration_since(Instant::now()) {
+        match receiver.recv_timeout(left.min(POLL_INTERVAL)) {
+            Ok(chunk) if text.len() + chunk.len() > limit => {
+                text.extend_from_slice(&chunk[..limit - text.len()]);
+                truncated = true;
+                break;
+            }
+            Ok(chunk) => text.extend_from_slice(&chunk),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+        if !exited && child.try_wait()?.is_some() {
+            exited = true;
+            read_until = read_until.min(Instant::now() + EXIT_GRACE);
+        }
+    }
+    // Dropping the receiver stops the reader, so a command still writing
+    // past the limit gets a broken pipe.
+    drop(receiver);
+
+    let status = wait_until(&mut child, deadline)?;
+    Ok(SilentOutput {
+        status,
+        text,
+        truncated,
+    })
+}
+
+/// Waits for `child` to exit, killing it once `deadline` passes.
+fn wait_until(child: &mut Child, deadline: Instant) -> Result<Exit> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(if status.success() {
+                Exit::Success
+            } else {
+                Exit::Failed(status)
+            });
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            return Ok(Exit::TimedOut);
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Commands run from the launcher, most recent first.
+#[derive(Serialize, Deserialize, Default, Debug)]
+struct History {
+    commands: Vec<HistoryEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct HistoryEntry {
+    command: String,
+    /// Whether it was last run in a terminal.
+    terminal: bool,
+    last_used: u64,
+}
+
+impl History {
+    fn path() -> PathBuf {
+        data_file("shell_history.toml")
+    }
+
+    fn load() -> Self {
+        fs::read_to_string(Self::path())
+            .ok()
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<()> {
+        fs::write(Self::path(), toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    fn record(&mut self, command: &str, terminal: bool) {
+        let last_used = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.commands.retain(|entry| entry.command != command);
+        self.commands.insert(
+            0,
+            HistoryEntry {
+                command: command.to_string(),
+                terminal,
+                last_used,
+            },
+        );
+        self.commands.truncate(HISTORY_LIMIT);
+    }
+
+    /// Entries containing `needle`, which must be lowercase.
+    fn matching(&self, needle: &str) -> impl Iterator<Item = &HistoryEntry> {
+        self.commands
+            .iter()
+            .filter(move |entry| entry.command.to_lowercase().contains(needle))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{Exit, History, ShellProvider, parse_query, run_silent, shell_argv};
+    use crate::plugins::{Action, Provider, ShellMode};
+    use crate::{Entity, config::ShellConfig};
+
+    fn sh(command: &str) -> [String; 3] {
+        shell_argv("/bin/sh", command)
+    }
+
+    #[test]
+    fn parses_prefixed_commands() {
+        assert_eq!(parse_query(">ls -la "), Some("ls -la"));
+        assert_eq!(parse_query("  >  htop"), Some("htop"));
+        assert_eq!(parse_query(">"), Some(""));
+        assert_eq!(parse_query("ls >"), None);
+        assert_eq!(sh("echo hi"), ["/bin/sh", "-c", "echo hi"]);
+    }
+
+    #[test]
+    fn offers_silent_and_terminal_runs() {
+        let provider = ShellProvider::new(&ShellConfig {
+            keep_terminal_open: true,
+        });
+        let actions: Vec<_> = provider
+            .search("> ls")
+            .into_iter()
+            .take(2)
+            .map(|entity| match entity {
+                Entity::Command(command) => command.action,
+                Entity::Application(_) => unreachable!(),
+            })
+            .collect();
+        assert!(matches!(
+            &actions[..],
+            [
+                Action::Shell { command: silent, mode: ShellMode::Silent },
+                Action::Shell { command: terminal, mode: ShellMode::Terminal { hold: true } },
+            ] if silent == "ls" && terminal == "ls"
+        ));
+        assert!(provider.search("ls").is_empty());
+    }
+
+    #[test]
+    fn silent_commands_report_output_and_status() {
+        let output =
+            run_silent(&sh("echo out; echo err >&2"), Duration::from_secs(5), 1024).unwrap();
+        assert_eq!(output.status, Exit::Success);
+        assert_eq!(output.text, b"out\nerr\n");
+        assert!(!output.truncated);
+
+        let output = run_silent(&sh("exit 3"), Duration::from_secs(5), 1024).unwrap();
+        assert!(matches!(output.status, Exit::Failed(status) if status.code() == Some(3)));
+    }
+
+    #[test]
+    fn silent_commands_are_limited() {
+        let output = run_silent(&sh("yes"), Duration::from_secs(5), 100).unwrap();
+        assert_eq!(output.text.len(), 100);
+        assert!(output.truncated);
+        assert_ne!(output.status, Exit::Success);
+
+        let output = run_silent(
+            &sh("echo started; exec sleep 5"),
+            Duration::from_millis(200),
+            1024,
+        )
+        .unwrap();
+        assert_eq!(output.status, Exit::TimedOut);
+        assert_eq!(output.text, b"started\n");
+
+        // A background process holding the output open doesn't hold up
+        // the command that started it.
+        let started = Instant::now();
+        let output = run_silent(&sh("echo done; sleep 5 &"), Duration::from_secs(5), 1024).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(output.status, Exit::Success);
+        assert_eq!(output.text, b"done\n");
+    }
+
+    #[test]
+    fn history_keeps_latest_run_first_without_duplicates() {
+        let mut history = History::default();
+        history.record("htop", true);
+        history.record("ls -la", false);
+        history.record("htop", false);
+
+        let commands: Vec<_> = history.matching("").map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["htop", "ls -la"]);
+        assert!(!history.commands[0].terminal);
+
+        let commands: Vec<_> = history.matching("ls").map(|e| e.command.as_str()).collect();
+        assert_eq!(commands, ["ls -la"]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Snippet placeholders: `{clipboard}`, `{cursor}`, `{argument}`, and the
+//! date ones, written `{date:%Y-%m-%d}` or with the Unicode date patterns
+//! of Raycast snippets, like `{date format="yyyy-MM-dd"}`.
+
+use anyhow::{Context, Result};
+use jiff::{Zoned, fmt::strtime};
+
+use crate::plugins::placeholders::{Placeholder, Segment, placeholders, segments};
+
+pub(super) struct Values<'a> {
+    /// The value of each argument, by name.
+    pub arguments: Vec<(&'a str, &'a str)>,
+    pub clipboard: Option<&'a str>,
+    pub now: Zoned,
+}
+
+/// A snippet's text with its placeholders filled in.
+#[derive(Debug, PartialEq)]
+pub(super) struct Expanded {
+    pub text: String,
+    /// Where `{cursor}` was, as a byte offset into `text`.
+    pub cursor: Option<usize>,
+}
+
+impl Expanded {
+    /// Characters between the cursor and the end of the text.
+    pub fn chars_after_cursor(&self) -> usize {
+        self.cursor
+            .map_or(0, |cursor| self.text[cursor..].chars().count())
+    }
+}
+
+/// `text` with its placeholders replaced. `{argument}` placeholders of the
+/// same name get the same value, or their default when it's empty.
+/// Unknown placeholders are left as they are.
+pub(super) fn expand(text: &str, values: &Values) -> Result<Expanded> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut cursor = None;
+
+    for segment in segments(text) {
+        let (placeholder, source) = match segment {
+            Segment::Text(text) => {
+                expanded.push_str(text);
+                continue;
+            }
+            Segment::Placeholder(placeholder, source) => (placeholder, source),
+        };
+
+        let value = match placeholder.name {
+            "cursor" => {
+                cursor.get_or_insert(expanded.len());
+                String::new()
+            }
+            "clipboard" => values
+                .clipboard
+                .context("The clipboard is empty")?
+                .to_string(),
+            "argument" => {
+                let name = argument_name(&placeholder);
+                values
+                    .arguments
+                    .iter()
+                    .find(|(known, value)| *known == name && !value.is_empty())
+                    .map(|(_, value)| *value)
+                    .or(placeholder.attribute("default"))
+                    .with_context(|| format!("This snippet needs the argument “{name}”"))?
+                    .to_string()
+            }
+            "date" | "time" | "datetime" | "day" => date(&placeholder, &values.now)?,
+            _ => source.to_string(),
+        };
+        expanded.push_str(&value);
+    }
+
+    Ok(Expanded {
+        text: expanded,
+        cursor,
+    })
+}
+
+/// The names of the arguments to ask for to fill in `text`, in order of
+/// appearance. A name isn't asked for when all its placeholders have a
+/// default.
+pub(super) fn argument_names(text: &str) -> Vec<String> {
+    let mut names: Vec<(&str, bool)> = Vec::new();
+    for placeholder in placeholders(text).filter(|placeholder| placeholder.name == "argument") {
+        let name = argument_name(&placeholder);
+        let required = placeholder.attribute("default").is_none();
+        match names.iter_mut().find(|(known, _)| *known == name) {
+            Some((_, needed)) => *needed |= required,
+            None => names.push((name, required)),
+        }
+    }
+    names
+        .into_iter()
+        .filter(|(_, required)| *required)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Placeholders without a name are all the same argument.
+fn argument_name<'a>(placeholder: &Placeholder<'a>) -> &'a str {
+    placeholder.attribute("name").unwrap_or("argument")
+}
+
+fn date(placeholder: &Placeholder, now: &Zoned) -> Result<String> {
+    let format = match (placeholder.format, placeholder.attribute("format")) {
+        (Some(format), _) => format.to_string(),
+        (None, Some(pattern)) => strftime_from_pattern(pattern),
+        (None, None) => match placeholder.name {
+            "time" => "%H:%M",
+            "datetime" => "%Y-%m-%d %H:%M",
+            "day" => "%A",
+            _ => "%Y-%m-%d",
+        }
+        .to_string(),
+    };
+    strtime::format(&format, now).with_context(|| format!("Invalid date format {format}"))
+}
+
+/// Converts the common fields of a Unicode date pattern, as used by Raycast,
+/// like `EEEE, d MMM yyyy`, to a strftime format.
+fn strftime_from_pattern(pattern: &str) -> String {
+    const FIELDS: [(&str, &str); 17] = [
+        ("yyyy", "%Y"),
+        ("yy", "%y"),
+        ("MMMM", "%B"),
+        ("MMM", "%b"),
+        ("MM", "%m"),
+        ("M", "%-m"),
+        ("dd", "%d"),
+        ("d", "%-d"),
+        ("EEEE", "%A"),
+        ("EEE", "%a"),
+        ("HH", "%H"),
+        ("H", "%-H"),
+        ("hh", "%I"),
+        ("h", "%-I"),
+        ("mm", "%M"),
+        ("ss", "%S"),
+        ("a", "%p"),
+    ];
+
+    let mut format = String::with_capacity(pattern.len() * 2);
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' {
+            // Quoted literal text; `''` is a quote.
+            let (literal, after) = rest[1..].split_once('\'').unwrap_or((&rest[1..], ""));
+            let literal = if literal.is_empty() { "'" } else { literal };
+            format.push_str(&literal.replace('%', "%%"));
+            rest = after;
+        } else if let Some((field, directive)) =
+            FIELDS.iter().find(|(field, _)| rest.starts_with(field))
+        {
+            format.push_str(directive);
+            rest = &rest[field.len()..];
+        } else {
+            if c == '%' {
+                format.push('%');
+            }
+            format.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    format
+}
+
+#[cfg(test)]
+mod test {
+    use jiff::{civil::date, tz::TimeZone};
+
+    use super::{Expanded, Values, argument_names, expand, strftime_from_pattern};
+
+    #[test]
+    fn fills_in_placeholders() {
+        let values = Values {
+            arguments: vec![("who", "Ada")],
+            clipboard: Some("https://example.com/pr/7"),
+            now: date(2024, 3, 1)
+                .at(9, 5, 0, 0)
+                .to_zoned(TimeZone::UTC)
+                .unwrap(),
+        };
+        let expanded = expand(
+            "Hi {argument name=\"who\"}, see {clipboard} ({date:%Y-%m-%d}, {time}).\n{cursor}\nfn x() {}",
+            &values,
+        )
+        .unwrap();
+        assert_eq!(
+            expanded.text,
+            "Hi Ada, see https://example.com/pr/7 (2024-03-01, 09:05).\n\nfn x() {}"
+        );
+        assert_eq!(expanded.chars_after_cursor(), 10);
+
+        assert_eq!(
+            expand("{date format=\"EEEE, d MMM yyyy\"} {unknown}", &values).unwrap(),
+            Expanded {
+                text: "Friday, 1 Mar 2024 {unknown}".to_string(),
+                cursor: None,
+            }
+        );
+
+        let missing = Values {
+            arguments: vec![("x", "")],
+            ..values
+        };
+        assert_eq!(
+            expand("{argument name=\"x\" default=\"there\"}", &missing)
+                .unwrap()
+                .text,
+            "there"
+        );
+        assert!(expand("{argument name=\"x\"}", &missing).is_err());
+    }
+
+    #[test]
+    fn asks_for_each_named_argument_once() {
+        let text = "{argument name=\"to\"}: {argument name=\"greeting\" default=\"Hi\"} \
+                    {argument name=\"to\"}, {argument} {argument name=\"sign\" default=\"me\"} \
+                    {argument name=\"sign\"}";
+        assert_eq!(argument_names(text), ["to", "argument", "sign"]);
+        assert!(argument_names("{argument default=\"x\"} if (a) { b }").is_empty());
+
+        let values = Values {
+            arguments: vec![("to", "Ada"), ("argument", "thanks"), ("sign", "")],
+            clipboard: None,
+            now: jiff::Zoned::now(),
+        };
+        assert_eq!(
+            expand(
+                "{argument name=\"greeting\" default=\"Hi\"} {argument name=\"to\"}, \
+                 {argument} {argument name=\"to\"}",
+                &values
+            )
+            .unwrap()
+            .text,
+            "Hi Ada, thanks Ada"
+        );
+        assert!(expand("{argument name=\"sign\"}", &values).is_err());
+    }
+
+    #[test]
+    fn converts_unicode_date_patterns() {
+        assert_eq!(strftime_from_pattern("yyyy-MM-dd HH:mm"), "%Y-%m-%d %H:%M");
+        assert_eq!(
+            strftime_from_pattern("h:mm a 'at' d/M"),
+            "%-I:%M %p at %-d/%-m"
+        );
+        assert_eq!(strftime_from_pattern("100% ''yy"), "100%% '%y");
+        assert_eq!(strftime_from_pattern("d 'at 100%'"), "%-d at 100%%");
+    }
+}
@@ -0,0 +1,375 @@
+mod expand;
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result, bail};
+use jiff::Zoned;
+use serde::{Deserialize, Serialize};
+
+use self::expand::{Expanded, Values, expand};
+use super::placeholders::clipboard_for;
+use super::{Action, CommandEntity, Outcome, Provider, SecondaryAction, display_path, expand_home};
+use crate::{
+    Detail, Entity, SnippetsConfig,
+    common::{data_file, write_atomically},
+    detail::code_block,
+    search::SearchEngine,
+};
+
+const PLUGIN: &str = "Snippets";
+const FILE_NAME: &str = "snippets.json";
+
+/// A snippet, in the format of Raycast's snippet exports.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub(super) struct Snippet {
+    name: String,
+    text: String,
+    /// Typed in the launcher to find the snippet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    keyword: Option<String>,
+}
+
+/// Raycast exports a list, or an object holding one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SnippetFile {
+    List(Vec<Snippet>),
+    Object { snippets: Vec<Snippet> },
+}
+
+/// Searches the saved snippets, read again whenever the file changes so
+/// imported ones are found right away.
+pub(super) struct SnippetsProvider {
+    config: SnippetsConfig,
+    cache: Mutex<Cache>,
+}
+
+/// The snippets as of the file's last change, as commands.
+#[derive(Default)]
+struct Cache {
+    modified: Option<SystemTime>,
+    commands: Vec<Entity>,
+}
+
+impl SnippetsProvider {
+    pub fn new(config: &SnippetsConfig) -> Self {
+        Self {
+            config: config.clone(),
+            cache: Mutex::default(),
+        }
+    }
+}
+
+impl Provider for SnippetsProvider {
+    fn name(&self) -> &str {
+        PLUGIN
+    }
+
+    fn search(&self, query: &str) -> Vec<Entity> {
+        let needle = query.trim();
+        if needle.is_empty() {
+            return Vec::new();
+        }
+
+        let mut cache = self.cache.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = cache.refresh(&self.config) {
+            tracing::error!("Failed to read the snippets: {e:#}");
+        }
+        cache
+            .commands
+            .iter()
+            .filter(|command| SearchEngine::matches(command, needle))
+            .cloned()
+            .collect()
+    }
+}
+
+impl Cache {
+    /// Reads the snippets again if they changed since they were last read.
+    fn refresh(&mut self, config: &SnippetsConfig) -> Result<()> {
+        let path = path();
+        let modified = match fs::metadata(&path) {
+            Ok(metadata) => Some(metadata.modified()?),
+            // None were saved yet.
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        if modified == self.modified {
+            return Ok(());
+        }
+
+        self.commands = read(&path)?
+            .iter()
+            .map(|snippet| entity(snippet, config))
+            .collect();
+        self.modified = modified;
+        Ok(())
+    }
+}
+
+/// The commands importing and exporting the snippets.
+pub(super) fn commands() -> Vec<Entity> {
+    vec![
+        Entity::Command(CommandEntity {
+            name: "Import Snippets".to_string(),
+            description: Some("Add the snippets of a Raycast export".to_string()),
+            icon_names: vec!["document-import".to_string()],
+            arguments: vec!["File".to_string()],
+            plugin: Some(PLUGIN.to_string()),
+            action: Action::ImportSnippets,
+            ..Default::default()
+        }),
+        Entity::Command(CommandEntity {
+            name: "Export Snippets".to_string(),
+            description: Some("Save the snippets in Raycast's format".to_string()),
+            icon_names: vec!["document-export".to_string()],
+            arguments: vec!["File or Folder".to_string()],
+            plugin: Some(PLUGIN.to_string()),
+            action: Action::ExportSnippets,
+            ..Default::default()
+        }),
+    ]
+}
+
+fn entity(snippet: &Snippet, config: &SnippetsConfig) -> Entity {
+    let mut detail = Detail::default().with_markdown(code_block(&snippet.text));
+    if let Some(keyword) = &snippet.keyword {
+        detail = detail.with_row("Keyword", keyword.as_str());
+    }
+
+    let copy = Action::CopySnippet {
+        text: snippet.text.clone(),
+    };
+    let (action, secondary_actions) = if config.type_command.is_empty() {
+        (copy, Vec::new())
+    } else {
+        let typing = Action::TypeSnippet {
+            text: snippet.text.clone(),
+            type_command: config.type_command.clone(),
+            cursor_left_command: config.cursor_left_command.clone(),
+        };
+        (
+            typing,
+            vec![SecondaryAction::new("Copy to Clipboard", copy)],
+        )
+    };
+
+    Entity::Command(CommandEntity {
+        name: snippet.name.clone(),
+        alias: snippet.keyword.clone(),
+        description: snippet
+            .text
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_string),
+        icon_names: vec!["text-x-generic".to_string()],
+        arguments: expand::argument_names(&snippet.text),
+        plugin: Some(PLUGIN.to_string()),
+        detail: Some(detail),
+        action,
+        secondary_actions,
+        ..Default::default()
+    })
+}
+
+/// `text` with its placeholders filled in, `arguments` being the values of
+/// its [`argument_names`](expand::argument_names).
+fn fill(text: &str, arguments: &[String]) -> Result<Expanded> {
+    let clipboard = clipboard_for(text)?;
+    let names = expand::argument_names(text);
+    expand(
+        text,
+        &Values {
+            arguments: names
+                .iter()
+                .map(String::as_str)
+                .zip(arguments.iter().map(String::as_str))
+                .collect(),
+            clipboard: clipboard.as_deref(),
+            now: Zoned::now(),
+        },
+    )
+}
+
+pub(super) fn copy(text: &str, arguments: &[String]) -> Result<()> {
+    crate::copy(&fill(text, arguments)?.text)
+}
+
+/// Types the snippet into the focused window once the launcher closed,
+/// then moves the cursor back to `{cursor}`.
+pub(super) fn type_text(
+    text: &str,
+    arguments: &[String],
+    type_command: &[String],
+    cursor_left_command: &[String],
+) -> Result<Outcome> {
+    let expanded = fill(text, arguments)?;
+    let mut typing = type_command.to_vec();
+    typing.push(expanded.text.clone());
+
+    let mut script = quote_all(&typing);
+    let left = expanded.chars_after_cursor();
+    if left > 0 && !cursor_left_command.is_empty() {
+        script.push_str(&format!(
+            " && i=0 && while [ $i -lt {left} ]; do {}; i=$((i + 1)); done",
+            quote_all(cursor_left_command)
+        ));
+    }
+    Ok(Outcome::AfterClose {
+        argv: vec!["sh".to_string(), "-c".to_string(), script],
+    })
+}
+
+/// Adds the snippets of the Raycast export at `source`, replacing saved
+/// ones of the same name.
+pub(super) fn import(source: &str) -> Result<Outcome> {
+    let source = expand_home(source.trim());
+    let imported = read(&source)?;
+    if imported.is_empty() {
+        bail!("{} has no snippets", display_path(&source));
+    }
+
+    let target = path();
+    let mut snippets = read(&target)?;
+    let names: HashSet<&str> = imported.iter().map(|s| s.name.as_str()).collect();
+    let before = snippets.len();
+    snippets.retain(|snippet| !names.contains(snippet.name.as_str()));
+    let replaced = before - snippets.len();
+    snippets.extend(imported.iter().cloned());
+    write(&target, &snippets)?;
+
+    let mut text = format!(
+        "Imported {} snippets from {}",
+        imported.len(),
+        display_path(&source)
+    );
+    if replaced > 0 {
+        text.push_str(&format!(", replacing {replaced} with the same name"));
+    }
+    text.push('.');
+    Ok(Outcome::Output {
+        title: "Imported Snippets".to_string(),
+        text,
+    })
+}
+
+/// Writes the snippets to `target` in Raycast's format; into
+/// `snippets.json` when `target` is a directory. Asks first when that
+/// replaces a file.
+pub(super) fn export(target: &str) -> Result<Outcome> {
+    let mut target = expand_home(target.trim());
+    if target.is_dir() {
+        target.push(FILE_NAME);
+    }
+    if !target.exists() {
+        return export_to(&target);
+    }
+    Ok(Outcome::Choose {
+        title: format!("Replace {}?", display_path(&target)),
+        actions: vec![SecondaryAction::new(
+            "Replace",
+            Action::ReplaceSnippetExport { target },
+        )],
+    })
+}
+
+pub(super) fn export_to(target: &Path) -> Result<Outcome> {
+    let snippets = read(&path())?;
+    write(target, &snippets)?;
+    Ok(Outcome::Output {
+        title: "Exported Snippets".to_string(),
+        text: format!(
+            "Exported {} snippets to {}.",
+            snippets.len(),
+            display_path(target)
+        ),
+    })
+}
+
+fn path() -> PathBuf {
+    data_file(FILE_NAME)
+}
+
+/// The snippets in `path`, none if it doesn't exist.
+fn read(path: &Path) -> Result<Vec<Snippet>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    let file: SnippetFile = serde_json::from_str(&content)
+        .with_context(|| format!("{} isn't a snippet file", path.display()))?;
+    Ok(match file {
+        SnippetFile::List(snippets) | SnippetFile::Object { snippets } => snippets,
+    })
+}
+
+fn write(path: &Path, snippets: &[Snippet]) -> Result<()> {
+    write_atomically(path, serde_json::to_string_pretty(snippets)?)
+}
+
+/// `argv` as a shell command line.
+fn quote_all(argv: &[String]) -> String {
+    argv.iter()
+        .map(|arg| format!("'{}'", arg.replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{Snippet, export, quote_all, read, write};
+    use crate::{Action, Outcome, common::test_dir::TempDir};
+
+    #[test]
+    fn reads_raycast_exports() {
+        let root = TempDir::new("snippets");
+        let path = root.join("snippets.json");
+
+        fs::write(
+            &path,
+            r#"{"snippets": [{"name": "Sign-off", "text": "Thanks,\n{cursor}", "keyword": "!thx"}]}"#,
+        )
+        .unwrap();
+        let snippets = read(&path).unwrap();
+        assert_eq!(snippets[0].keyword.as_deref(), Some("!thx"));
+
+        let plain = Snippet {
+            name: "Email".to_string(),
+            text: "me@example.com".to_string(),
+            keyword: None,
+        };
+        write(&path, std::slice::from_ref(&plain)).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "[\n  {\n    \"name\": \"Email\",\n    \"text\": \"me@example.com\"\n  }\n]"
+        );
+        assert_eq!(read(&path).unwrap(), [plain]);
+        assert!(read(&root.join("missing.json")).unwrap().is_empty());
+
+        // Exporting over a file asks first, here for the directory's
+        // snippets.json.
+        let Outcome::Choose { actions, .. } = export(&root.to_string_lossy()).unwrap() else {
+            panic!("Replaced {} without asking", path.display());
+        };
+        assert!(matches!(
+            &actions[0].action,
+            Action::ReplaceSnippetExport { target } if *target == path
+        ));
+
+        assert_eq!(
+            quote_all(&["wtype".to_string(), "it's".to_string()]),
+            r"'wtype' 'it'\''s'"
+        );
+    }
+}
@@ -1,6 +1,6 @@
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::BTreeMap};
 
-use crate::{AppState, Entity};
+use crate::{AppState, Entity, Section, SectionsConfig};
 
 pub struct SearchEngine;
 
@@ -39,4 +39,97 @@ impl SearchEngine {
 
         score_ordering
     }
+
+    /// Splits `items`, already sorted with [`SearchEngine::compare`], into
+    /// sections in display order, applying the configured per-section caps.
+    /// With `suggestions` set, the most used entries are pulled into
+    /// [`Section::Suggestions`] first.
+    pub fn group<T>(
+        items: Vec<T>,
+        entity: impl Fn(&T) -> &Entity,
+        app_state: &AppState,
+        config: &SectionsConfig,
+        suggestions: bool,
+    ) -> Vec<(Section, Vec<T>)> {
+        let mut sections: BTreeMap<Section, Vec<T>> = BTreeMap::new();
+        let mut suggested = 0;
+
+        for item in items {
+            let section = if suggestions
+                && suggested < config.suggestions
+                && app_state.get_score(entity(&item)) > 0
+            {
+                suggested += 1;
+                Section::Suggestions
+            } else {
+                entity(&item).section()
+            };
+            sections.entry(section).or_default().push(item);
+        }
+
+        sections
+            .into_iter()
+            .map(|(section, mut items)| {
+                if let Some(limit) = config.limits.get(&section.key()) {
+                    items.truncate(*limit);
+                }
+                (section, items)
+            })
+            .filter(|(_, items)| !items.is_empty())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SearchEngine;
+    use crate::{AppState, Entity, Section, SectionsConfig, plugins::CommandEntity};
+
+    fn command(name: &str, plugin: Option<&str>) -> Entity {
+        Entity::Command(CommandEntity {
+            id: 0,
+            name: name.to_string(),
+            alias: None,
+            description: None,
+            image: None,
+            plugin: plugin.map(str::to_string),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn groups_into_sections() {
+        let mut app_state = AppState::default();
+        let used = command("Used", None);
+        app_state.record_usage(&used);
+
+        let items = vec![
+            used,
+            command("Plain", None),
+            command("Link A", Some("Quicklinks")),
+            command("Link B", Some("Quicklinks")),
+        ];
+
+        let mut config = SectionsConfig::default();
+        config.limits.insert("quicklinks".to_string(), 1);
+
+        let grouped = SearchEngine::group(items.clone(), |e| e, &app_state, &config, true);
+        let names: Vec<(Section, Vec<&str>)> = grouped
+            .iter()
+            .map(|(section, items)| (section.clone(), items.iter().map(|e| e.name()).collect()))
+            .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                (Section::Suggestions, vec!["Used"]),
+                (Section::Commands, vec!["Plain"]),
+                (Section::Plugin("Quicklinks".to_string()), vec!["Link A"]),
+            ]
+        );
+
+        let grouped = SearchEngine::group(items, |e| e, &app_state, &config, false);
+        assert_eq!(grouped[0].0, Section::Commands);
+        assert_eq!(grouped[0].1.len(), 2);
+    }
 }
@@ -0,0 +1,39 @@
+use std::fmt;
+
+/// A group of results in the list, in display order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Section {
+    /// The user's most used entries, shown while the query is empty.
+    Suggestions,
+    Applications,
+    Commands,
+    /// Results contributed by a named plugin.
+    Plugin(String),
+    /// Commands offered for the query when little else matches it.
+    Fallback,
+}
+
+impl Section {
+    /// The key used for this section in the config file.
+    pub fn key(&self) -> String {
+        match self {
+            Section::Suggestions => "suggestions".to_string(),
+            Section::Applications => "applications".to_string(),
+            Section::Commands => "commands".to_string(),
+            Section::Plugin(name) => name.to_lowercase(),
+            Section::Fallback => "fallback".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Section::Suggestions => write!(f, "Suggestions"),
+            Section::Applications => write!(f, "Applications"),
+            Section::Commands => write!(f, "Commands"),
+            Section::Plugin(name) => write!(f, "{name}"),
+            Section::Fallback => write!(f, "Fallback Commands"),
+        }
+    }
+}
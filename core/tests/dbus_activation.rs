@@ -0,0 +1,164 @@
+#![cfg(target_os = "linux")]
+
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use core::{ActivationContext, Entity, LaunchConfig, LaunchOverride, get_entities};
+use zbus::{
+    blocking::{Connection, MessageIterator, connection},
+    message::Type,
+    zvariant::OwnedValue,
+};
+
+/// A throwaway session bus, killed when dropped.
+struct PrivateBus {
+    daemon: Child,
+    address: String,
+}
+
+impl PrivateBus {
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .ok()?;
+
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take()?)
+            .read_line(&mut address)
+            .ok()?;
+
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// Method names received, each with the activation token it carried.
+type Calls = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+/// Answers `org.freedesktop.Application` calls on `connection` in the
+/// background, recording each method with its activation token.
+fn record_calls(connection: Connection) -> Calls {
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let recorded = calls.clone();
+
+    // The `zbus::interface` macro can't be used here: this crate's name
+    // shadows `::core` in its expansion.
+    thread::spawn(move || {
+        for message in MessageIterator::from(&connection) {
+            let Ok(message) = message else { break };
+            let header = message.header();
+            if header.message_type() != Type::MethodCall {
+                continue;
+            }
+            let Some(method) = header.member().map(|m| m.to_string()) else {
+                continue;
+            };
+
+            let body = message.body();
+            let platform_data = match method.as_str() {
+                "Activate" => body
+                    .deserialize::<(HashMap<String, OwnedValue>,)>()
+                    .map(|(data,)| data),
+                "Open" => body
+                    .deserialize::<(Vec<String>, HashMap<String, OwnedValue>)>()
+                    .map(|(_, data)| data),
+                _ => continue,
+            };
+            let token = platform_data.ok().and_then(|data| {
+                data.get("activation-token")
+                    .and_then(|value| String::try_from(value.clone()).ok())
+            });
+
+            recorded.lock().unwrap().push((method, token));
+            let _ = connection.reply(&header, &());
+        }
+    });
+
+    calls
+}
+
+fn find<'a>(entities: &'a [Entity], name: &str) -> &'a Entity {
+    entities
+        .iter()
+        .find(|entity| entity.name() == name)
+        .unwrap_or_else(|| panic!("{name} was not found"))
+}
+
+#[test]
+fn activates_dbus_activatable_entries() {
+    let Some(bus) = PrivateBus::start() else {
+        eprintln!("dbus-daemon is not available, skipping");
+        return;
+    };
+
+    let service = connection::Builder::address(bus.address.as_str())
+        .unwrap()
+        .name("org.example.Activatable")
+        .unwrap()
+        .build()
+        .unwrap();
+    let calls = record_calls(service);
+
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/applications");
+    // SAFETY: this is the only test in this binary, so nothing else reads
+    // the environment concurrently.
+    unsafe {
+        std::env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address);
+        std::env::set_var("XDG_DATA_HOME", &fixtures);
+        std::env::set_var("XDG_DATA_DIRS", &fixtures);
+    }
+
+    let entities = get_entities();
+    let launch = LaunchConfig::default();
+    let activation = ActivationContext::from_token("test-token".to_string());
+
+    find(&entities, "Activatable Example")
+        .execute(Vec::new(), &launch, &activation)
+        .unwrap();
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [("Activate".to_string(), Some("test-token".to_string()))]
+    );
+
+    // Nobody owns these names: one falls back to Exec, the other has none.
+    find(&entities, "Fallback Example")
+        .execute(Vec::new(), &launch, &activation)
+        .unwrap();
+    assert!(
+        find(&entities, "Unreachable Example")
+            .execute(Vec::new(), &launch, &activation)
+            .is_err()
+    );
+    assert_eq!(calls.lock().unwrap().len(), 1);
+
+    // D-Bus activation can't pass launch overrides, so they run Exec.
+    let mut overridden = LaunchConfig::default();
+    overridden.overrides.insert(
+        "org.example.Activatable".to_string(),
+        LaunchOverride {
+            args: vec!["--verbose".to_string()],
+            ..Default::default()
+        },
+    );
+    find(&entities, "Activatable Example")
+        .execute(Vec::new(), &overridden, &activation)
+        .unwrap();
+    assert_eq!(calls.lock().unwrap().len(), 1);
+}
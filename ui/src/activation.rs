@@ -0,0 +1,115 @@
+use core::ActivationContext;
+
+/// How long to wait for the compositor before launching without a token.
+#[cfg(target_os = "linux")]
+const TOKEN_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Gets what the next launched application, known to the compositor as
+/// `app_id` when given, needs to be allowed to take focus. Returns an
+/// empty context when the platform offers nothing. Blocks for up to
+/// 200 ms.
+pub fn request_activation(app_id: Option<String>) -> ActivationContext {
+    #[cfg(target_os = "linux")]
+    {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(wayland::request_token(app_id.as_deref()));
+        });
+        match receiver.recv_timeout(TOKEN_TIMEOUT) {
+            Ok(Ok(token)) => return ActivationContext::from_token(token),
+            Ok(Err(e)) => tracing::debug!("No activation token: {e:#}"),
+            Err(_) => tracing::debug!("Timed out waiting for an activation token"),
+        }
+    }
+
+    ActivationContext::default()
+}
+
+#[cfg(target_os = "linux")]
+mod wayland {
+    use anyhow::{Context, Result};
+    use wayland_client::{
+        Connection, Dispatch, QueueHandle,
+        globals::{GlobalListContents, registry_queue_init},
+        protocol::wl_registry::WlRegistry,
+    };
+    use wayland_protocols::xdg::activation::v1::client::{
+        xdg_activation_token_v1::{self, XdgActivationTokenV1},
+        xdg_activation_v1::XdgActivationV1,
+    };
+
+    #[derive(Default)]
+    struct State {
+        token: Option<String>,
+    }
+
+    /// Requests an `xdg_activation_v1` token for `app_id` on a connection
+    /// of our own.
+    ///
+    /// iced_layershell doesn't expose the launcher's surface or the last
+    /// input serial, so the request carries neither and takes whatever the
+    /// compositor grants; compositors that insist on them hand out a token
+    /// that won't grant focus.
+    pub fn request_token(app_id: Option<&str>) -> Result<String> {
+        let connection = Connection::connect_to_env().context("Not running under Wayland")?;
+        let (globals, mut queue) = registry_queue_init::<State>(&connection)?;
+        let qh = queue.handle();
+
+        let activation: XdgActivationV1 = globals
+            .bind(&qh, 1..=1, ())
+            .context("Compositor doesn't support xdg_activation_v1")?;
+        let token = activation.get_activation_token(&qh, ());
+        if let Some(app_id) = app_id {
+            token.set_app_id(app_id.to_string());
+        }
+        token.commit();
+
+        let mut state = State::default();
+        while state.token.is_none() {
+            queue.blocking_dispatch(&mut state)?;
+        }
+
+        token.destroy();
+        activation.destroy();
+        Ok(state.token.unwrap_or_default())
+    }
+
+    impl Dispatch<WlRegistry, GlobalListContents> for State {
+        fn event(
+            _: &mut Self,
+            _: &WlRegistry,
+            _: <WlRegistry as wayland_client::Proxy>::Event,
+            _: &GlobalListContents,
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<XdgActivationV1, ()> for State {
+        fn event(
+            _: &mut Self,
+            _: &XdgActivationV1,
+            _: <XdgActivationV1 as wayland_client::Proxy>::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+        }
+    }
+
+    impl Dispatch<XdgActivationTokenV1, ()> for State {
+        fn event(
+            state: &mut Self,
+            _: &XdgActivationTokenV1,
+            event: xdg_activation_token_v1::Event,
+            _: &(),
+            _: &Connection,
+            _: &QueueHandle<Self>,
+        ) {
+            if let xdg_activation_token_v1::Event::Done { token } = event {
+                state.token = Some(token);
+            }
+        }
+    }
+}
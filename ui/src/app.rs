@@ -1,23 +1,87 @@
-use core::AppState;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use core::{
+    Action, ActivationContext, AppState, Application, Config, Entity, LaunchConfig, Outcome,
+    Plugins, init_logging,
+};
 
 use iced::{Color, Element, Event, Task, event, widget::container};
 #[cfg(target_os = "linux")]
 use iced_layershell::to_layer_message;
 
+use crate::activation::request_activation;
+use crate::blocking::unblock;
 use crate::prism;
 use crate::prism::PrismEvent;
+use crate::prism::items::ListEntry;
+use crate::prism::keybindings::Keymap;
+use crate::prism::state::Toast;
 
 pub struct Raycast {
     prism: prism::Prism,
     app_state: AppState,
+    launch: LaunchConfig,
+    after_close: AfterClose,
+}
+
+/// A command left to run once the launcher has closed. `main` runs it
+/// after the event loop returns, when the launcher's surface is gone and
+/// focus went back to the window that had it before.
+#[derive(Clone, Default)]
+pub struct AfterClose(Arc<Mutex<Option<Pending>>>);
+
+struct Pending {
+    argv: Vec<String>,
+    launch: LaunchConfig,
+}
+
+impl AfterClose {
+    fn set(&self, argv: Vec<String>, launch: LaunchConfig) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = Some(Pending { argv, launch });
+    }
+
+    pub fn run(&self) {
+        let pending = self.0.lock().unwrap_or_else(PoisonError::into_inner).take();
+        if let Some(Pending { argv, launch }) = pending
+            && let Err(e) = core::spawn(&argv, &launch, &ActivationContext::default())
+        {
+            tracing::error!("{e:#}");
+        }
+    }
 }
 
 impl Raycast {
-    pub fn new() -> (Raycast, Task<Message>) {
+    pub fn new(after_close: AfterClose) -> (Raycast, Task<Message>) {
+        let (config, config_error) = match Config::load() {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(e)),
+        };
+        if let Err(e) = init_logging(&config.logging) {
+            eprintln!("{e:#}");
+        }
+        if let Some(e) = &config_error {
+            tracing::error!("{e:#}");
+        }
+
         let app_state = AppState::load();
-        let (prism, prism_task) = prism::Prism::new();
 
-        let state = Raycast { prism, app_state };
+        let (keymap, problems) = Keymap::from_config(&config.keybindings);
+        for problem in problems {
+            tracing::warn!("Keybindings: {problem}");
+        }
+
+        let plugins = Arc::new(Plugins::new(&config));
+        let (mut prism, prism_task) = prism::Prism::new(keymap, config.sections.clone(), plugins);
+        if let Some(e) = config_error {
+            prism.show_toast(Toast::error("Using the default config", &e));
+        }
+
+        let state = Raycast {
+            prism,
+            app_state,
+            launch: config.launch,
+            after_close,
+        };
 
         (state, prism_task.map(Message::PrismEvent))
     }
@@ -31,24 +95,112 @@ impl Raycast {
             Message::PrismEvent(prism_event) => {
                 let task = self.prism.update(prism_event, &mut self.app_state);
                 task.map(|event| match event {
-                    PrismEvent::Run => Message::Run,
+                    PrismEvent::Run {
+                        keep_open,
+                        action,
+                        entry,
+                        arguments,
+                    } => Message::Run {
+                        keep_open,
+                        action,
+                        entry,
+                        arguments,
+                    },
                     PrismEvent::ExitApp => Message::ExitApp,
                     e => Message::PrismEvent(e),
                 })
             }
-            Message::Run => {
-                if let Some(entry) = self.prism.get_selected_entry().cloned() {
-                    self.app_state.record_usage(&entry.entry.entity);
+            Message::Run {
+                keep_open,
+                action,
+                entry,
+                arguments,
+            } => {
+                let launch = self.launch.clone();
+                let app_id = match entry.entity.as_ref() {
+                    Entity::Application(app) => Some(app.id().to_string()),
+                    Entity::Command(_) => None,
+                };
+
+                // Runs on a thread of its own: the token request and silent
+                // shell commands both block.
+                Task::perform(
+                    {
+                        let entry = entry.clone();
+                        unblock(move || {
+                            // Fetched first so the launched app may take focus.
+                            let activation = request_activation(app_id);
+                            match action {
+                                Some(action) => action.run(arguments, &launch, &activation),
+                                None => entry.execute(arguments, &launch, &activation),
+                            }
+                            .map_err(Arc::new)
+                        })
+                    },
+                    move |result| Message::Finished {
+                        keep_open,
+                        entry: entry.clone(),
+                        result,
+                    },
+                )
+            }
+            Message::Finished {
+                keep_open,
+                entry,
+                result,
+            } => {
+                let entity = &entry.entity;
+
+                // Failures keep the window open so the error can be read.
+                let outcome = match result {
+                    Ok(outcome) => outcome,
+                    Err(e) => {
+                        let title = format!("Failed to launch {}", entity.name());
+                        tracing::error!(entry = %entity.name(), "{e:#}");
+                        self.prism.show_toast(Toast::error(title, &e));
+                        return Task::none();
+                    }
+                };
+
+                // Choosing only lists further actions; the pick records usage.
+                if !matches!(outcome, Outcome::Choose { .. }) {
+                    self.app_state.record_usage(entity);
                     if let Err(e) = self.app_state.save() {
-                        eprintln!("Failed to save state: {}", e);
+                        tracing::error!("Failed to save state: {e:#}");
                     }
+                }
 
-                    let argument = self.prism.get_argument();
-                    if let Err(e) = entry.entry.execute(argument) {
-                        eprintln!("Failed to launch: {}", e);
+                match outcome {
+                    Outcome::Choose { title, actions } => {
+                        self.prism.show_actions(title, actions);
+                        Task::none()
+                    }
+                    Outcome::Output { title, text } => {
+                        self.prism.show_output(title, text);
+                        Task::none()
+                    }
+                    Outcome::Search { query } => {
+                        Task::done(Message::PrismEvent(PrismEvent::SearchInput(query)))
+                    }
+                    Outcome::Refresh => {
+                        let query = self.prism.query().to_string();
+                        Task::done(Message::PrismEvent(PrismEvent::SearchInput(query)))
+                    }
+                    Outcome::AfterClose { argv } => {
+                        self.after_close.set(argv, self.launch.clone());
+                        iced::exit()
+                    }
+                    Outcome::Done if keep_open => {
+                        let verb = match entity.as_ref() {
+                            Entity::Application(_) => "Launched",
+                            Entity::Command(_) => "Ran",
+                        };
+                        self.prism
+                            .show_toast(Toast::success(format!("{verb} {}", entity.name())));
+                        Task::none()
                     }
+                    Outcome::Done => iced::exit(),
                 }
-                iced::exit()
             }
             Message::ExitApp => iced::exit(),
             _ => Task::none(),
@@ -85,6 +237,17 @@ pub enum Message {
     #[allow(dead_code)]
     IcedEvent(Event),
     PrismEvent(PrismEvent),
-    Run,
+    /// Runs `entry`, or `action` on its behalf.
+    Run {
+        keep_open: bool,
+        action: Option<Action>,
+        entry: ListEntry,
+        arguments: Vec<String>,
+    },
+    Finished {
+        keep_open: bool,
+        entry: ListEntry,
+        result: Result<Outcome, Arc<anyhow::Error>>,
+    },
     ExitApp,
 }
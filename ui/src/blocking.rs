@@ -0,0 +1,15 @@
+//! Runs blocking work, like disk I/O, without stalling the async executor.
+
+use iced::futures::channel::oneshot;
+
+/// Runs `f` on a thread of its own and waits for its result.
+pub async fn unblock<T>(f: impl FnOnce() -> T + Send + 'static) -> T
+where
+    T: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(f());
+    });
+    receiver.await.expect("Blocking task panicked")
+}
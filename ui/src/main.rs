@@ -1,15 +1,43 @@
 #![allow(clippy::too_many_arguments)]
-use crate::app::Raycast;
+use crate::app::{AfterClose, Raycast};
 
+mod activation;
 mod app;
+mod blocking;
 mod design_system;
 mod prism;
 
+/// Records the clipboard history instead of showing the launcher.
+const CLIPBOARD_DAEMON: &str = "--clipboard-daemon";
+
+fn clipboard_daemon() -> ! {
+    let config = core::Config::load().unwrap_or_else(|e| {
+        eprintln!("Using the default config: {e:#}");
+        core::Config::default()
+    });
+    if let Err(e) = core::init_logging(&config.logging) {
+        eprintln!("{e:#}");
+    }
+    if let Err(e) = core::run_clipboard_daemon(&config.clipboard) {
+        tracing::error!("{e:#}");
+        std::process::exit(1);
+    }
+    std::process::exit(0);
+}
+
 #[cfg(not(target_os = "linux"))]
 pub fn main() -> iced::Result {
+    if std::env::args().any(|arg| arg == CLIPBOARD_DAEMON) {
+        clipboard_daemon();
+    }
     use iced::{Size, advanced::graphics::core::window};
 
-    iced::application(Raycast::new, Raycast::update, Raycast::view)
+    let after_close = AfterClose::default();
+    let boot = {
+        let after_close = after_close.clone();
+        move || Raycast::new(after_close.clone())
+    };
+    iced::application(boot, Raycast::update, Raycast::view)
         .style(Raycast::style)
         .font(include_bytes!("../fonts/Roboto-Regular.ttf").as_slice())
         .font(include_bytes!("../fonts/Roboto-Medium.ttf").as_slice())
@@ -30,35 +58,42 @@ pub fn main() -> iced::Result {
             level: window::Level::AlwaysOnTop,
             ..window::Settings::default()
         })
-        .run()
+        .run()?;
+    after_close.run();
+    Ok(())
 }
 
 #[cfg(target_os = "linux")]
 pub fn main() -> Result<(), iced_layershell::Error> {
+    if std::env::args().any(|arg| arg == CLIPBOARD_DAEMON) {
+        clipboard_daemon();
+    }
     use iced_layershell::application;
     use iced_layershell::reexport::{Anchor, KeyboardInteractivity};
     use iced_layershell::settings::{LayerShellSettings, Settings};
 
-    application(
-        Raycast::new,
-        Raycast::namespace,
-        Raycast::update,
-        Raycast::view,
-    )
-    .style(Raycast::style)
-    .font(include_bytes!("../fonts/Roboto-Regular.ttf").as_slice())
-    .font(include_bytes!("../fonts/Roboto-Medium.ttf").as_slice())
-    .font(include_bytes!("../fonts/RobotoMono-Regular.ttf").as_slice())
-    .subscription(Raycast::subscription)
-    .settings(Settings {
-        layer_settings: LayerShellSettings {
-            size: Some((700, 500)),
-            exclusive_zone: -1,
-            anchor: Anchor::empty(),
-            keyboard_interactivity: KeyboardInteractivity::OnDemand,
+    let after_close = AfterClose::default();
+    let boot = {
+        let after_close = after_close.clone();
+        move || Raycast::new(after_close.clone())
+    };
+    application(boot, Raycast::namespace, Raycast::update, Raycast::view)
+        .style(Raycast::style)
+        .font(include_bytes!("../fonts/Roboto-Regular.ttf").as_slice())
+        .font(include_bytes!("../fonts/Roboto-Medium.ttf").as_slice())
+        .font(include_bytes!("../fonts/RobotoMono-Regular.ttf").as_slice())
+        .subscription(Raycast::subscription)
+        .settings(Settings {
+            layer_settings: LayerShellSettings {
+                size: Some((700, 500)),
+                exclusive_zone: -1,
+                anchor: Anchor::empty(),
+                keyboard_interactivity: KeyboardInteractivity::OnDemand,
+                ..Default::default()
+            },
             ..Default::default()
-        },
-        ..Default::default()
-    })
-    .run()
+        })
+        .run()?;
+    after_close.run();
+    Ok(())
 }
@@ -1,13 +1,19 @@
-use core::Entity;
-use std::{path::Path, sync::Arc};
+use core::{ActivationContext, Entity, IconSize, LaunchConfig, Outcome};
+use std::{
+    path::Path,
+    sync::{Arc, LazyLock},
+};
 
 use anyhow::Result;
 use iced::widget::{image, svg};
 
+static PLACEHOLDER_ICON: LazyLock<image::Handle> = LazyLock::new(|| {
+    image::Handle::from_bytes(include_bytes!("../../../assets/icon_placeholder.png").as_slice())
+});
+
 #[derive(Clone, Debug)]
 pub struct ListEntry {
     pub entity: Arc<Entity>,
-    image_handler: IconHandle,
 }
 
 impl ListEntry {
@@ -26,45 +32,29 @@ impl ListEntry {
         }
     }
 
-    pub fn icon(&self) -> IconHandle {
-        self.image_handler.clone()
+    /// Resolves and reads the icon. Blocks on disk I/O, so it is only
+    /// called from background tasks.
+    pub fn load_icon(&self, icon_size: IconSize) -> IconHandle {
+        self.entity
+            .icon(icon_size)
+            .map(IconHandle::from)
+            .unwrap_or_else(IconHandle::placeholder)
     }
 
-    pub fn execute(&self, arg: Option<String>) -> Result<()> {
-        self.entity.execute(arg)
+    pub fn execute(
+        &self,
+        arguments: Vec<String>,
+        launch: &LaunchConfig,
+        activation: &ActivationContext,
+    ) -> Result<Outcome> {
+        self.entity.execute(arguments, launch, activation)
     }
 }
 
 impl From<Entity> for ListEntry {
     fn from(value: Entity) -> Self {
-        let image_handler = match value
-            .icon()
-            .unwrap_or(core::Image::Path("assets/icon_placeholder.png".to_string()))
-        {
-            core::Image::Bytes(bytes) => {
-                IconHandle::Other(image::Handle::from_bytes(bytes.clone()))
-            }
-            core::Image::Rgba(width, height, pixels) => {
-                IconHandle::Other(image::Handle::from_rgba(width, height, pixels))
-            }
-            core::Image::Path(path) => {
-                let path_obj = Path::new(&path);
-                match path_obj.extension().and_then(|s| s.to_str()) {
-                    Some("svg") => IconHandle::Svg(svg::Handle::from_path(path_obj)),
-                    _ => IconHandle::Other(image::Handle::from_path(path_obj)),
-                }
-            }
-        };
-
-        match &value {
-            Entity::Application(_) => ListEntry {
-                entity: Arc::new(value),
-                image_handler,
-            },
-            Entity::Command(_) => ListEntry {
-                entity: Arc::new(value),
-                image_handler,
-            },
+        ListEntry {
+            entity: Arc::new(value),
         }
     }
 }
@@ -75,6 +65,13 @@ pub enum IconHandle {
     Other(image::Handle),
 }
 
+impl IconHandle {
+    /// The compiled-in icon shown until an entry's own icon has loaded.
+    pub fn placeholder() -> Self {
+        IconHandle::Other(PLACEHOLDER_ICON.clone())
+    }
+}
+
 impl From<core::Image> for IconHandle {
     fn from(value: core::Image) -> Self {
         match value {
@@ -86,9 +83,14 @@ impl From<core::Image> for IconHandle {
             }
             core::Image::Path(path) => {
                 let path_obj = Path::new(&path);
-                match path_obj.extension().and_then(|s| s.to_str()) {
-                    Some("svg") => IconHandle::Svg(svg::Handle::from_path(path_obj)),
-                    _ => IconHandle::Other(image::Handle::from_path(path_obj)),
+                // Read the file now so rendering never has to.
+                match (
+                    path_obj.extension().and_then(|s| s.to_str()),
+                    std::fs::read(path_obj),
+                ) {
+                    (Some("svg"), Ok(bytes)) => IconHandle::Svg(svg::Handle::from_memory(bytes)),
+                    (_, Ok(bytes)) => IconHandle::Other(image::Handle::from_bytes(bytes)),
+                    (_, Err(_)) => IconHandle::placeholder(),
                 }
             }
         }
@@ -1,40 +1,425 @@
-use iced::keyboard;
+use std::collections::HashMap;
+use std::fmt;
+
+use core::{KeybindingPreset, KeybindingsConfig};
+use iced::keyboard::{self, Modifiers, key::Named};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Key {
-    ArrowUp,
-    ArrowDown,
-    Enter,
-    Escape,
-    Other,
+    Named(Named),
+    Character(String),
 }
 
-impl From<keyboard::Key> for Key {
-    fn from(key: keyboard::Key) -> Self {
+impl Key {
+    fn parse(name: &str) -> Option<Self> {
+        let named = match name {
+            "up" => Named::ArrowUp,
+            "down" => Named::ArrowDown,
+            "left" => Named::ArrowLeft,
+            "right" => Named::ArrowRight,
+            "enter" | "return" => Named::Enter,
+            "escape" | "esc" => Named::Escape,
+            "tab" => Named::Tab,
+            "space" => Named::Space,
+            "backspace" => Named::Backspace,
+            "delete" | "del" => Named::Delete,
+            "pageup" | "page_up" => Named::PageUp,
+            "pagedown" | "page_down" => Named::PageDown,
+            "home" => Named::Home,
+            "end" => Named::End,
+            _ => {
+                let mut chars = name.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some(c), None) => Some(Key::Character(c.to_lowercase().collect())),
+                    _ => None,
+                };
+            }
+        };
+        Some(Key::Named(named))
+    }
+
+    fn from_iced(key: &keyboard::Key) -> Option<Self> {
         match key {
-            keyboard::Key::Named(keyboard::key::Named::ArrowUp) => Key::ArrowUp,
-            keyboard::Key::Named(keyboard::key::Named::ArrowDown) => Key::ArrowDown,
-            keyboard::Key::Named(keyboard::key::Named::Enter) => Key::Enter,
-            keyboard::Key::Named(keyboard::key::Named::Escape) => Key::Escape,
-            _ => Key::Other,
+            keyboard::Key::Named(named) => Some(Key::Named(*named)),
+            keyboard::Key::Character(c) => Some(Key::Character(c.to_lowercase())),
+            keyboard::Key::Unidentified => None,
+        }
+    }
+}
+
+/// A key together with the modifiers that must be held, e.g. `ctrl+n`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    key: Key,
+    modifiers: Modifiers,
+}
+
+impl KeyChord {
+    pub fn parse(chord: &str) -> Option<Self> {
+        let chord = chord.trim().to_lowercase();
+        // Split on '+' but keep a literal trailing "+" key, as in "ctrl++".
+        let (prefix, key) = match chord.strip_suffix("++") {
+            Some(prefix) => (prefix, "+"),
+            None => chord.rsplit_once('+').unwrap_or(("", chord.as_str())),
+        };
+
+        let mut modifiers = Modifiers::empty();
+        for modifier in prefix.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match modifier {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "alt" | "option" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "super" | "logo" | "cmd" | "meta" => Modifiers::LOGO,
+                _ => return None,
+            };
+        }
+
+        Some(KeyChord {
+            key: Key::parse(key)?,
+            modifiers,
+        })
+    }
+
+    /// A compact label for hints in the list, e.g. `⌥3` for `alt+3`.
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        for (modifier, symbol) in [
+            (Modifiers::CTRL, "⌃"),
+            (Modifiers::ALT, "⌥"),
+            (Modifiers::SHIFT, "⇧"),
+            (Modifiers::LOGO, "❖"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                label.push_str(symbol);
+            }
+        }
+
+        match &self.key {
+            Key::Character(c) => label.push_str(&c.to_uppercase()),
+            Key::Named(Named::Enter) => label.push('↵'),
+            Key::Named(Named::ArrowUp) => label.push('↑'),
+            Key::Named(Named::ArrowDown) => label.push('↓'),
+            Key::Named(Named::ArrowLeft) => label.push('←'),
+            Key::Named(Named::ArrowRight) => label.push('→'),
+            Key::Named(named) => label.push_str(&format!("{named:?}")),
         }
+        label
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KeyAction {
     SelectPrevious,
     SelectNext,
+    /// Moves left in a grid of results.
+    SelectLeft,
+    /// Moves right in a grid of results.
+    SelectRight,
+    PageUp,
+    PageDown,
+    SelectFirst,
+    SelectLast,
     Submit,
+    AlternateSubmit,
+    /// Launch the Nth result, counting from 1.
+    Launch(usize),
+    ToggleDetail,
+    /// Open the actions panel for the selected entry.
+    ShowActions,
     EscapePressed,
 }
 
-pub fn map_key_to_action(key: keyboard::Key) -> Option<KeyAction> {
-    match Key::from(key) {
-        Key::ArrowUp => Some(KeyAction::SelectPrevious),
-        Key::ArrowDown => Some(KeyAction::SelectNext),
-        Key::Enter => Some(KeyAction::Submit),
-        Key::Escape => Some(KeyAction::EscapePressed),
-        _ => None,
+impl KeyAction {
+    /// Whether the search input's own handling of the key wins, so that
+    /// the arrows, Home and End still move the cursor while typing. Tab
+    /// moves through a grid of results instead.
+    pub fn yields_to_input(self) -> bool {
+        matches!(
+            self,
+            KeyAction::SelectLeft
+                | KeyAction::SelectRight
+                | KeyAction::SelectFirst
+                | KeyAction::SelectLast
+        )
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "select_previous" => KeyAction::SelectPrevious,
+            "select_next" => KeyAction::SelectNext,
+            "select_left" => KeyAction::SelectLeft,
+            "select_right" => KeyAction::SelectRight,
+            "page_up" => KeyAction::PageUp,
+            "page_down" => KeyAction::PageDown,
+            "select_first" => KeyAction::SelectFirst,
+            "select_last" => KeyAction::SelectLast,
+            "submit" => KeyAction::Submit,
+            "alternate_submit" => KeyAction::AlternateSubmit,
+            "toggle_detail" => KeyAction::ToggleDetail,
+            "show_actions" => KeyAction::ShowActions,
+            "escape" => KeyAction::EscapePressed,
+            _ => {
+                let n = name.strip_prefix("launch_")?.parse().ok()?;
+                if !(1..=9).contains(&n) {
+                    return None;
+                }
+                KeyAction::Launch(n)
+            }
+        })
+    }
+}
+
+impl fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyAction::SelectPrevious => write!(f, "select_previous"),
+            KeyAction::SelectNext => write!(f, "select_next"),
+            KeyAction::SelectLeft => write!(f, "select_left"),
+            KeyAction::SelectRight => write!(f, "select_right"),
+            KeyAction::PageUp => write!(f, "page_up"),
+            KeyAction::PageDown => write!(f, "page_down"),
+            KeyAction::SelectFirst => write!(f, "select_first"),
+            KeyAction::SelectLast => write!(f, "select_last"),
+            KeyAction::Submit => write!(f, "submit"),
+            KeyAction::AlternateSubmit => write!(f, "alternate_submit"),
+            KeyAction::Launch(n) => write!(f, "launch_{n}"),
+            KeyAction::ToggleDetail => write!(f, "toggle_detail"),
+            KeyAction::ShowActions => write!(f, "show_actions"),
+            KeyAction::EscapePressed => write!(f, "escape"),
+        }
+    }
+}
+
+fn preset_bindings(preset: KeybindingPreset) -> Vec<(&'static str, KeyAction)> {
+    let mut bindings = vec![
+        ("up", KeyAction::SelectPrevious),
+        ("down", KeyAction::SelectNext),
+        ("left", KeyAction::SelectLeft),
+        ("right", KeyAction::SelectRight),
+        ("shift+tab", KeyAction::SelectLeft),
+        ("tab", KeyAction::SelectRight),
+        ("pageup", KeyAction::PageUp),
+        ("pagedown", KeyAction::PageDown),
+        ("home", KeyAction::SelectFirst),
+        ("end", KeyAction::SelectLast),
+        ("enter", KeyAction::Submit),
+        ("ctrl+enter", KeyAction::AlternateSubmit),
+        ("ctrl+i", KeyAction::ToggleDetail),
+        ("escape", KeyAction::EscapePressed),
+        ("alt+1", KeyAction::Launch(1)),
+        ("alt+2", KeyAction::Launch(2)),
+        ("alt+3", KeyAction::Launch(3)),
+        ("alt+4", KeyAction::Launch(4)),
+        ("alt+5", KeyAction::Launch(5)),
+        ("alt+6", KeyAction::Launch(6)),
+        ("alt+7", KeyAction::Launch(7)),
+        ("alt+8", KeyAction::Launch(8)),
+        ("alt+9", KeyAction::Launch(9)),
+    ];
+
+    // Vim takes ctrl+k for moving up.
+    match preset {
+        KeybindingPreset::Default => bindings.push(("ctrl+k", KeyAction::ShowActions)),
+        KeybindingPreset::Emacs => bindings.extend([
+            ("ctrl+p", KeyAction::SelectPrevious),
+            ("ctrl+n", KeyAction::SelectNext),
+            ("ctrl+g", KeyAction::EscapePressed),
+            ("ctrl+k", KeyAction::ShowActions),
+        ]),
+        KeybindingPreset::Vim => bindings.extend([
+            ("ctrl+l", KeyAction::ShowActions),
+            ("ctrl+k", KeyAction::SelectPrevious),
+            ("ctrl+j", KeyAction::SelectNext),
+            ("ctrl+u", KeyAction::PageUp),
+            ("ctrl+d", KeyAction::PageDown),
+            ("ctrl+[", KeyAction::EscapePressed),
+        ]),
+    }
+
+    bindings
+}
+
+/// Maps key chords to actions, built from a preset plus the user's overrides.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: HashMap<KeyChord, KeyAction>,
+    /// The label of the first chord bound to each action.
+    labels: HashMap<KeyAction, String>,
+}
+
+impl Keymap {
+    /// Builds the keymap and returns every problem found on the way:
+    /// unknown actions, unparsable chords and chords bound twice. The
+    /// first binding of a conflicting chord wins, user bindings first.
+    pub fn from_config(config: &KeybindingsConfig) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let mut requested: Vec<(String, KeyAction)> = Vec::new();
+
+        for (action_name, chords) in &config.bindings {
+            let Some(action) = KeyAction::parse(action_name) else {
+                problems.push(format!("Unknown keybinding action \"{action_name}\""));
+                continue;
+            };
+            requested.extend(chords.iter().map(|chord| (chord.clone(), action)));
+        }
+
+        requested.extend(
+            preset_bindings(config.preset)
+                .into_iter()
+                .filter(|(_, action)| !config.bindings.contains_key(&action.to_string()))
+                .map(|(chord, action)| (chord.to_string(), action)),
+        );
+
+        let mut bindings = HashMap::new();
+        let mut labels = HashMap::new();
+        for (chord_name, action) in requested {
+            let Some(chord) = KeyChord::parse(&chord_name) else {
+                problems.push(format!("Invalid key chord \"{chord_name}\" for {action}"));
+                continue;
+            };
+
+            match bindings.get(&chord) {
+                Some(existing) if *existing != action => problems.push(format!(
+                    "\"{chord_name}\" is bound to both {existing} and {action}; keeping {existing}"
+                )),
+                Some(_) => {}
+                None => {
+                    labels.entry(action).or_insert_with(|| chord.label());
+                    bindings.insert(chord, action);
+                }
+            }
+        }
+
+        (Keymap { bindings, labels }, problems)
+    }
+
+    pub fn label_for(&self, action: KeyAction) -> Option<&str> {
+        self.labels.get(&action).map(String::as_str)
+    }
+
+    pub fn action_for(&self, key: &keyboard::Key, modifiers: Modifiers) -> Option<KeyAction> {
+        let mut chord = KeyChord {
+            key: Key::from_iced(key)?,
+            modifiers,
+        };
+
+        self.bindings.get(&chord).copied().or_else(|| {
+            // Shifted characters already arrive transformed ("<" rather than
+            // shift+","), so also try without shift.
+            chord.modifiers.remove(Modifiers::SHIFT);
+            matches!(chord.key, Key::Character(_))
+                .then(|| self.bindings.get(&chord).copied())
+                .flatten()
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use core::{KeybindingPreset, KeybindingsConfig};
+    use iced::keyboard::{self, Modifiers, key::Named};
+
+    use super::{KeyAction, KeyChord, Keymap};
+
+    fn config(preset: KeybindingPreset, bindings: &[(&str, &[&str])]) -> KeybindingsConfig {
+        KeybindingsConfig {
+            preset,
+            bindings: bindings
+                .iter()
+                .map(|(action, chords)| {
+                    (
+                        action.to_string(),
+                        chords.iter().map(|c| c.to_string()).collect(),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord = KeyChord::parse("Ctrl+Shift+K").unwrap();
+        assert_eq!(chord.modifiers, Modifiers::CTRL | Modifiers::SHIFT);
+        assert_eq!(chord, KeyChord::parse("shift+ctrl+k").unwrap());
+        assert!(KeyChord::parse("hyper+k").is_none());
+        assert!(KeyChord::parse("ctrl+nope").is_none());
+        assert!(KeyChord::parse("ctrl++").is_some());
+        assert_eq!(KeyChord::parse("alt+3").unwrap().label(), "⌥3");
+        assert_eq!(KeyChord::parse("ctrl+shift+k").unwrap().label(), "⌃⇧K");
+    }
+
+    #[test]
+    fn applies_presets() {
+        let (keymap, problems) = Keymap::from_config(&config(KeybindingPreset::Vim, &[]));
+        assert!(problems.is_empty());
+
+        let j = keyboard::Key::Character("j".into());
+        assert_eq!(
+            keymap.action_for(&j, Modifiers::CTRL),
+            Some(KeyAction::SelectNext)
+        );
+        assert_eq!(keymap.action_for(&j, Modifiers::empty()), None);
+        assert_eq!(
+            keymap.action_for(&keyboard::Key::Character("3".into()), Modifiers::ALT),
+            Some(KeyAction::Launch(3))
+        );
+        assert_eq!(
+            keymap.action_for(&keyboard::Key::Named(Named::Enter), Modifiers::CTRL),
+            Some(KeyAction::AlternateSubmit)
+        );
+
+        let home = keymap
+            .action_for(&keyboard::Key::Named(Named::Home), Modifiers::empty())
+            .unwrap();
+        assert!(home.yields_to_input());
+        assert!(!KeyAction::Submit.yields_to_input());
+
+        // The input moves its cursor with the arrows; Tab moves in grids.
+        let left = keymap
+            .action_for(&keyboard::Key::Named(Named::ArrowLeft), Modifiers::empty())
+            .unwrap();
+        assert!(left.yields_to_input());
+        let tab = keyboard::Key::Named(Named::Tab);
+        assert_eq!(
+            keymap.action_for(&tab, Modifiers::SHIFT),
+            Some(KeyAction::SelectLeft)
+        );
+        assert_eq!(
+            keymap.action_for(&tab, Modifiers::empty()),
+            Some(KeyAction::SelectRight)
+        );
+        assert!(!KeyAction::EscapePressed.yields_to_input());
+    }
+
+    #[test]
+    fn user_bindings_replace_preset_and_report_conflicts() {
+        let (keymap, problems) = Keymap::from_config(&config(
+            KeybindingPreset::Emacs,
+            &[
+                ("select_next", &["tab"]),
+                ("submit", &["ctrl+p", "enter"]),
+                ("jump", &["ctrl+x"]),
+            ],
+        ));
+
+        let n = keyboard::Key::Character("n".into());
+        assert_eq!(keymap.action_for(&n, Modifiers::CTRL), None);
+        assert_eq!(
+            keymap.action_for(&keyboard::Key::Named(Named::Tab), Modifiers::empty()),
+            Some(KeyAction::SelectNext)
+        );
+
+        let p = keyboard::Key::Character("p".into());
+        assert_eq!(
+            keymap.action_for(&p, Modifiers::CTRL),
+            Some(KeyAction::Submit)
+        );
+
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems.iter().any(|p| p.contains("\"jump\"")));
+        assert!(problems.iter().any(|p| p.contains("\"ctrl+p\"")));
+        assert!(problems.iter().any(|p| p.contains("\"tab\"")));
     }
 }
@@ -0,0 +1,83 @@
+use std::ops::Range;
+
+/// Vertical positions of the rows in the result list, so only the rows
+/// intersecting the viewport need to be built.
+#[derive(Debug, Clone, Default)]
+pub struct RowLayout {
+    /// `offsets[i]` is the top of row `i`; the last element is the total height.
+    offsets: Vec<f32>,
+}
+
+impl RowLayout {
+    pub fn new(heights: impl IntoIterator<Item = f32>) -> Self {
+        let mut offsets = vec![0.0];
+        let mut y = 0.0;
+        for height in heights {
+            y += height;
+            offsets.push(y);
+        }
+        Self { offsets }
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn top(&self, index: usize) -> f32 {
+        self.offsets[index.min(self.len())]
+    }
+
+    pub fn bottom(&self, index: usize) -> f32 {
+        self.top(index + 1)
+    }
+
+    pub fn total_height(&self) -> f32 {
+        self.top(self.len())
+    }
+
+    /// Rows overlapping `scroll_offset..scroll_offset + viewport_height`,
+    /// extended by `buffer` rows on each side.
+    pub fn visible_range(
+        &self,
+        scroll_offset: f32,
+        viewport_height: f32,
+        buffer: usize,
+    ) -> Range<usize> {
+        let len = self.len();
+        let rows = &self.offsets[1..];
+
+        // First row whose bottom is below the top of the viewport.
+        let start = rows.partition_point(|bottom| *bottom <= scroll_offset);
+        // First row whose top is at or past the bottom of the viewport.
+        let end = self.offsets[..len].partition_point(|top| *top < scroll_offset + viewport_height);
+
+        start.saturating_sub(buffer).min(len)..(end + buffer).min(len)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RowLayout;
+
+    #[test]
+    fn computes_visible_range() {
+        let layout = RowLayout::new([10.0; 100]);
+
+        assert_eq!(layout.total_height(), 1000.0);
+        assert_eq!(layout.visible_range(0.0, 35.0, 0), 0..4);
+        assert_eq!(layout.visible_range(25.0, 30.0, 0), 2..6);
+        assert_eq!(layout.visible_range(25.0, 30.0, 3), 0..9);
+        assert_eq!(layout.visible_range(990.0, 50.0, 2), 97..100);
+    }
+
+    #[test]
+    fn handles_mixed_heights_and_empty_lists() {
+        let layout = RowLayout::new([10.0, 40.0, 10.0]);
+        assert_eq!(layout.top(2), 50.0);
+        assert_eq!(layout.bottom(2), 60.0);
+        assert_eq!(layout.visible_range(15.0, 5.0, 0), 1..2);
+
+        let empty = RowLayout::new([]);
+        assert_eq!(empty.visible_range(0.0, 100.0, 5), 0..0);
+    }
+}
@@ -1,67 +1,156 @@
-mod items;
-mod keybindings;
+pub mod items;
+pub mod keybindings;
+mod layout;
 pub mod state;
 mod widgets;
 
-use self::state::{PrismEntry, PrismState};
-use crate::design_system::{colors, spacing};
-use crate::prism::items::ListEntry;
-use core::{AppState, get_entities, search::SearchEngine};
+use self::state::{ActionsMenu, DetailView, ListRow, OutputView, PrismEntry, PrismState, Toast};
+use crate::blocking::unblock;
+use crate::design_system::{colors, icons, spacing};
+use crate::prism::items::{IconHandle, ListEntry};
+use crate::prism::keybindings::{KeyAction, Keymap};
+use crate::prism::layout::RowLayout;
+use core::{
+    Action, AppState, Entity, IconSize, Plugins, SecondaryAction, Section, SectionsConfig,
+    get_entities, search::SearchEngine,
+};
 use iced::{
     Element, Length, Rectangle, Size, Subscription, Task,
     advanced::widget::{operate, operation},
     event, keyboard,
     widget::{
-        Id, column, container,
+        Id, column, container, markdown,
         operation::{focus, scroll_to},
-        scrollable,
+        row, scrollable,
         selector::{self, Selector},
+        space::Space,
+        stack,
     },
+    window,
 };
+use std::{ops::Range, sync::Arc, time::Instant};
 
 pub struct Prism {
     state: PrismState,
+    keymap: Keymap,
+    sections: SectionsConfig,
+    plugins: Arc<Plugins>,
 }
 
 impl Prism {
-    pub fn new() -> (Self, Task<PrismEvent>) {
+    pub fn new(
+        keymap: Keymap,
+        sections: SectionsConfig,
+        plugins: Arc<Plugins>,
+    ) -> (Self, Task<PrismEvent>) {
         let search_id = Id::unique();
-        let argument_id = Id::unique();
         let scroll_id = Id::unique();
 
         let state = PrismState {
             query: "".to_string(),
-            argument: None,
+            arguments: Vec::new(),
             all_entries: Vec::new(),
+            provider_entries: Vec::new(),
+            fallback_entries: plugins
+                .fallbacks()
+                .into_iter()
+                .map(|entity| PrismEntry::from(ListEntry::from(entity)))
+                .collect(),
             entries: Vec::new(),
+            rows: Vec::new(),
+            entry_rows: Vec::new(),
+            columns: None,
             selected_index: 0,
             search_id: search_id.clone(),
-            argument_id,
+            argument_ids: Vec::new(),
             scroll_id,
             viewport_height: 0.0,
             current_scroll_offset: 0.0,
             height_cache: std::collections::HashMap::new(),
             default_row_height: 54.0,
+            layout: RowLayout::default(),
             show_argument_input: false,
             is_argument_input_active: false,
+            icon_size: IconSize::from_scale_factor(icons::LG, 1.0),
+            icons: std::collections::HashMap::new(),
+            pending_icons: std::collections::HashSet::new(),
+            show_detail: false,
+            detail: None,
+            toast: None,
+            output: None,
+            actions: None,
         };
 
+        let commands = plugins.commands();
         let load_task = Task::perform(
-            async { get_entities().into_iter().map(From::from).collect() },
+            async move {
+                get_entities()
+                    .into_iter()
+                    .chain(commands)
+                    .map(From::from)
+                    .collect()
+            },
             PrismEvent::EntriesLoaded,
         );
+        // Icons are resolved for the size they are drawn at on this output.
+        let scale_task = window::latest()
+            .then(|id| match id {
+                Some(id) => window::scale_factor(id),
+                None => Task::done(1.0),
+            })
+            .map(PrismEvent::ScaleFactorChanged);
         let init_task = Task::perform(async {}, |_| PrismEvent::Initialized);
 
-        (Self { state }, Task::batch(vec![load_task, init_task]))
+        (
+            Self {
+                state,
+                keymap,
+                sections,
+                plugins,
+            },
+            Task::batch(vec![load_task, scale_task, init_task]),
+        )
     }
 
     pub fn update(&mut self, message: PrismEvent, app_state: &mut AppState) -> Task<PrismEvent> {
+        let task = self.handle(message, app_state);
+        self.sync_detail(app_state);
+        task
+    }
+
+    fn handle(&mut self, message: PrismEvent, app_state: &mut AppState) -> Task<PrismEvent> {
         match message {
             PrismEvent::Initialized => focus(self.state.search_id.clone()),
 
             PrismEvent::Scrolled(viewport) => {
                 self.state.current_scroll_offset = viewport.absolute_offset().y;
                 self.state.viewport_height = viewport.bounds().height;
+                Task::batch(vec![
+                    measure_visible_items(&self.state),
+                    load_visible_icons(&mut self.state),
+                ])
+            }
+
+            PrismEvent::ScaleFactorChanged(scale_factor) => {
+                let icon_size = IconSize::from_scale_factor(icons::LG, scale_factor);
+                if icon_size == self.state.icon_size {
+                    return Task::none();
+                }
+                self.state.icon_size = icon_size;
+                self.state.icons.clear();
+                self.state.pending_icons.clear();
+                load_visible_icons(&mut self.state)
+            }
+
+            PrismEvent::IconsLoaded(icon_size, loaded) => {
+                // Drop results requested before a scale change.
+                if icon_size != self.state.icon_size {
+                    return Task::none();
+                }
+                for (id, handle) in loaded {
+                    self.state.pending_icons.remove(&id);
+                    self.state.icons.insert(id, handle);
+                }
                 Task::none()
             }
 
@@ -73,82 +162,202 @@ impl Prism {
                     SearchEngine::compare(&a.entry.entity, &b.entry.entity, app_state)
                 });
 
-                self.state.all_entries = wrapped_entries.clone();
-                self.state.entries = wrapped_entries;
+                self.state.all_entries = wrapped_entries;
+                self.refresh_results(app_state);
 
-                measure_all_visible_items(&self.state)
+                Task::batch(vec![
+                    measure_visible_items(&self.state),
+                    load_visible_icons(&mut self.state),
+                ])
             }
 
             PrismEvent::SearchInput(query) => {
                 self.state.query = query;
+                self.state.toast = None;
+                self.state.output = None;
+                self.state.actions = None;
+                self.state.provider_entries.clear();
                 self.state.selected_index = 0;
-                self.state.argument = None;
+                self.state.arguments.clear();
                 self.state.show_argument_input = false;
                 self.state.is_argument_input_active = false;
-                self.state.entries = self
-                    .state
-                    .all_entries
-                    .iter()
-                    .filter(|e| SearchEngine::matches(&e.entry.entity, &self.state.query))
-                    .cloned()
-                    .collect();
+                self.refresh_results(app_state);
+                self.state.current_scroll_offset = 0.0;
 
                 Task::batch(vec![
                     scroll_to(
                         self.state.scroll_id.clone(),
                         scrollable::AbsoluteOffset { x: 0.0, y: 0.0 },
                     ),
-                    measure_all_visible_items(&self.state),
+                    measure_visible_items(&self.state),
+                    load_visible_icons(&mut self.state),
+                    search_providers(self.plugins.clone(), self.state.query.clone()),
+                ])
+            }
+
+            PrismEvent::ProviderResults { query, entries } => {
+                // Drop results for a query that has since been edited.
+                if query != self.state.query {
+                    return Task::none();
+                }
+                self.state.provider_entries = entries.into_iter().map(PrismEntry::from).collect();
+                self.refresh_results(app_state);
+                self.state.selected_index = self
+                    .state
+                    .selected_index
+                    .min(self.state.entries.len().saturating_sub(1));
+
+                Task::batch(vec![
+                    measure_visible_items(&self.state),
+                    load_visible_icons(&mut self.state),
                 ])
             }
 
-            PrismEvent::ArgumentInput(arg) => {
-                self.state.argument = Some(arg);
+            PrismEvent::ArgumentInput(index, value) => {
+                if let Some(argument) = self.state.arguments.get_mut(index) {
+                    *argument = value;
+                }
                 Task::none()
             }
 
             PrismEvent::SelectNext => {
                 if !self.state.entries.is_empty() {
-                    self.state.selected_index =
-                        (self.state.selected_index + 1).min(self.state.entries.len() - 1);
-                    return smart_scroll(&self.state);
+                    move_vertically(&mut self.state, true, 1);
+                    return smart_scroll(&mut self.state);
                 }
                 Task::none()
             }
 
             PrismEvent::SelectPrevious => {
+                move_vertically(&mut self.state, false, 1);
+                smart_scroll(&mut self.state)
+            }
+
+            PrismEvent::SelectLeft => {
+                if !in_grid(&self.state) {
+                    return Task::none();
+                }
                 self.state.selected_index = self.state.selected_index.saturating_sub(1);
-                smart_scroll(&self.state)
+                smart_scroll(&mut self.state)
+            }
+
+            PrismEvent::SelectRight => {
+                if !in_grid(&self.state) {
+                    return Task::none();
+                }
+                self.state.selected_index =
+                    (self.state.selected_index + 1).min(self.state.entries.len() - 1);
+                smart_scroll(&mut self.state)
             }
 
-            PrismEvent::ItemMeasured { id, rect } => {
-                if rect.height > 0.0 {
-                    self.state.height_cache.insert(id, rect.height);
-                    self.state.default_row_height = rect.height;
+            PrismEvent::PageDown => {
+                if !self.state.entries.is_empty() {
+                    let rows = page_size(&self.state);
+                    move_vertically(&mut self.state, true, rows);
+                    return smart_scroll(&mut self.state);
                 }
                 Task::none()
             }
 
-            PrismEvent::EntrySelected(index) => {
-                self.state.selected_index = index;
-                if let Some(entry) = self.get_selected_entry() {
-                    if entry.entry.entity.needs_argument() && self.get_argument().is_none() {
-                        self.state.show_argument_input = true;
-                        self.state.is_argument_input_active = true;
-                        return focus(self.state.argument_id.clone());
+            PrismEvent::PageUp => {
+                let rows = page_size(&self.state);
+                move_vertically(&mut self.state, false, rows);
+                smart_scroll(&mut self.state)
+            }
+
+            PrismEvent::SelectFirst => {
+                self.state.selected_index = 0;
+                smart_scroll(&mut self.state)
+            }
+
+            PrismEvent::SelectLast => {
+                self.state.selected_index = self.state.entries.len().saturating_sub(1);
+                smart_scroll(&mut self.state)
+            }
+
+            PrismEvent::KeyPressed {
+                key,
+                modifiers,
+                captured,
+            } => {
+                let Some(action) = self
+                    .keymap
+                    .action_for(&key, modifiers)
+                    .filter(|action| !(captured && action.yields_to_input()))
+                else {
+                    return Task::none();
+                };
+                if let Some(menu) = &mut self.state.actions {
+                    return match action {
+                        KeyAction::SelectNext => {
+                            menu.selected = (menu.selected + 1).min(menu.items.len() - 1);
+                            Task::none()
+                        }
+                        KeyAction::SelectPrevious => {
+                            menu.selected = menu.selected.saturating_sub(1);
+                            Task::none()
+                        }
+                        KeyAction::Submit => {
+                            let index = menu.selected;
+                            self.handle(PrismEvent::ActionChosen(index), app_state)
+                        }
+                        KeyAction::ShowActions | KeyAction::EscapePressed => {
+                            self.state.actions = None;
+                            Task::none()
+                        }
+                        _ => Task::none(),
+                    };
+                }
+                let event = match action {
+                    KeyAction::SelectPrevious => PrismEvent::SelectPrevious,
+                    KeyAction::SelectNext => PrismEvent::SelectNext,
+                    KeyAction::SelectLeft => PrismEvent::SelectLeft,
+                    KeyAction::SelectRight => PrismEvent::SelectRight,
+                    KeyAction::PageUp => PrismEvent::PageUp,
+                    KeyAction::PageDown => PrismEvent::PageDown,
+                    KeyAction::SelectFirst => PrismEvent::SelectFirst,
+                    KeyAction::SelectLast => PrismEvent::SelectLast,
+                    KeyAction::Submit => PrismEvent::Submit,
+                    KeyAction::AlternateSubmit => PrismEvent::AlternateSubmit,
+                    // Numbered shortcuts count from the top of the viewport.
+                    KeyAction::Launch(n) => {
+                        let index = first_visible_entry(&self.state) + n - 1;
+                        if index >= self.state.entries.len() {
+                            return Task::none();
+                        }
+                        PrismEvent::EntrySelected(index)
                     }
-                    self.state.is_argument_input_active = false;
-                    return Task::batch(vec![
-                        focus(self.state.search_id.clone()),
-                        Task::done(PrismEvent::Run),
-                    ]);
+                    KeyAction::ToggleDetail => PrismEvent::ToggleDetail,
+                    KeyAction::ShowActions => PrismEvent::ToggleActions,
+                    KeyAction::EscapePressed => PrismEvent::EscapePressed,
+                };
+                self.handle(event, app_state)
+            }
+
+            PrismEvent::ItemsMeasured(measured) => {
+                // The layout is rebuilt once for the whole batch.
+                let mut changed = false;
+                for (id, rect) in measured {
+                    if rect.height > 0.0 && self.state.height_cache.get(&id) != Some(&rect.height) {
+                        self.state.height_cache.insert(id, rect.height);
+                        self.state.default_row_height = rect.height;
+                        changed = true;
+                    }
+                }
+                if changed {
+                    rebuild_layout(&mut self.state);
                 }
                 Task::none()
             }
 
+            PrismEvent::EntrySelected(index) => {
+                self.state.actions = None;
+                self.select_entry(index, false)
+            }
+
             PrismEvent::Submit => {
                 if !self.state.entries.is_empty() {
-                    return self.update(
+                    return self.handle(
                         PrismEvent::EntrySelected(self.state.selected_index),
                         app_state,
                     );
@@ -156,9 +365,63 @@ impl Prism {
                 Task::none()
             }
 
+            PrismEvent::AlternateSubmit => {
+                if !self.state.entries.is_empty() {
+                    return self.select_entry(self.state.selected_index, true);
+                }
+                Task::none()
+            }
+
+            PrismEvent::ToggleDetail => {
+                self.state.show_detail = !self.state.show_detail;
+                Task::none()
+            }
+
+            PrismEvent::ToggleActions => {
+                self.state.actions = match (&self.state.actions, self.get_selected_entry()) {
+                    (None, Some(entry)) => Some(ActionsMenu {
+                        title: None,
+                        items: entity_actions(&entry.entry.entity),
+                        selected: 0,
+                    }),
+                    _ => None,
+                };
+                Task::none()
+            }
+
+            PrismEvent::ActionChosen(index) => {
+                let Some(mut menu) = self.state.actions.take() else {
+                    return Task::none();
+                };
+                if index >= menu.items.len() || self.get_selected_entry().is_none() {
+                    return Task::none();
+                }
+                match menu.items.swap_remove(index).1 {
+                    None => self.select_entry(self.state.selected_index, false),
+                    Some(action) => self
+                        .run(false, Some(action))
+                        .map_or_else(Task::none, Task::done),
+                }
+            }
+
+            PrismEvent::LinkClicked(url) => {
+                if let Err(e) = core::open(&url) {
+                    tracing::error!(%url, "{e:#}");
+                    self.state.toast = Some(Toast::error(format!("Failed to open {url}"), &e));
+                }
+                Task::none()
+            }
+
+            PrismEvent::DismissToast => {
+                self.state.toast = None;
+                Task::none()
+            }
+
             PrismEvent::EscapePressed => {
-                if self.state.is_argument_input_active {
-                    self.state.argument = Option::None;
+                if self.state.toast.take().is_some() || self.state.output.take().is_some() {
+                    Task::none()
+                } else if self.state.is_argument_input_active {
+                    self.state.arguments.clear();
                     self.state.show_argument_input = false;
                     self.state.is_argument_input_active = false;
                     focus(self.state.search_id.clone())
@@ -171,8 +434,203 @@ impl Prism {
         }
     }
 
-    pub fn get_argument(&self) -> Option<String> {
-        self.state.argument.clone()
+    /// Filters the loaded entries by the query and appends the provider
+    /// results.
+    fn refresh_results(&mut self, app_state: &AppState) {
+        let started = Instant::now();
+        let mut matches: Vec<PrismEntry> = self
+            .state
+            .all_entries
+            .iter()
+            .filter(|e| SearchEngine::matches(&e.entry.entity, &self.state.query))
+            .cloned()
+            .collect();
+        tracing::debug!(
+            query = %self.state.query,
+            matches = matches.len(),
+            elapsed = ?started.elapsed(),
+            "Searched entries"
+        );
+
+        matches.extend(self.state.provider_entries.iter().cloned());
+        self.show_results(matches, app_state);
+    }
+
+    /// Groups `matches` into sections and lays them out as the result list,
+    /// followed by the fallback commands when there are few matches.
+    /// `entries` follows display order, so navigation steps over headers.
+    /// Sections of symbols, like emoji, are laid out as a grid when the
+    /// provider of the query asks for one.
+    fn show_results(&mut self, matches: Vec<PrismEntry>, app_state: &AppState) {
+        let shows_fallbacks = self
+            .plugins
+            .shows_fallbacks(&self.state.query, matches.len());
+        let mut groups = SearchEngine::group(
+            matches,
+            |e| &e.entry.entity,
+            app_state,
+            &self.sections,
+            self.state.query.is_empty(),
+        );
+        if shows_fallbacks && !self.state.fallback_entries.is_empty() {
+            groups.push((Section::Fallback, self.state.fallback_entries.clone()));
+        }
+
+        let columns = self.plugins.grid_columns(&self.state.query);
+        let state = &mut self.state;
+        state.entries.clear();
+        state.rows.clear();
+        state.entry_rows.clear();
+        state.columns = columns;
+        for (section, entries) in groups {
+            state.rows.push(ListRow::Header(section));
+            let grid =
+                columns.filter(|_| entries.iter().all(|e| e.entry.entity.symbol().is_some()));
+            let start = state.entries.len();
+            state.entries.extend(entries);
+            let end = state.entries.len();
+            match grid {
+                Some(columns) => {
+                    for first in (start..end).step_by(columns) {
+                        let cells = first..(first + columns).min(end);
+                        let row = state.rows.len();
+                        state.entry_rows.extend(cells.clone().map(|_| row));
+                        state.rows.push(ListRow::Cells(cells));
+                    }
+                }
+                None => {
+                    for i in start..end {
+                        state.entry_rows.push(state.rows.len());
+                        state.rows.push(ListRow::Entry(i));
+                    }
+                }
+            }
+        }
+        rebuild_layout(state);
+    }
+
+    /// Rebuilds the detail pane when it is shown and the selection moved.
+    fn sync_detail(&mut self, app_state: &AppState) {
+        if !self.state.show_detail {
+            self.state.detail = None;
+            return;
+        }
+
+        let Some(entry) = self.get_selected_entry() else {
+            self.state.detail = None;
+            return;
+        };
+        if self
+            .state
+            .detail
+            .as_ref()
+            .is_some_and(|d| d.entry_id == entry.id)
+        {
+            return;
+        }
+
+        let detail = entry.entry.entity.detail(app_state);
+        self.state.detail = Some(DetailView {
+            entry_id: entry.id.clone(),
+            markdown: detail.markdown.as_deref().map(markdown::Content::parse),
+            metadata: detail.metadata,
+        });
+    }
+
+    /// Runs the entry at `index`, first asking for each of its arguments in
+    /// turn. With `keep_open` the launcher stays up after running it.
+    fn select_entry(&mut self, index: usize, keep_open: bool) -> Task<PrismEvent> {
+        self.state.selected_index = index;
+        let Some(entry) = self.get_selected_entry() else {
+            return Task::none();
+        };
+        let count = entry.entry.entity.arguments().len();
+        if let Some(missing) = self.missing_argument() {
+            let state = &mut self.state;
+            state.arguments.resize(count, String::new());
+            while state.argument_ids.len() < count {
+                state.argument_ids.push(Id::unique());
+            }
+            state.show_argument_input = true;
+            state.is_argument_input_active = true;
+            return focus(state.argument_ids[missing].clone());
+        }
+        self.state.is_argument_input_active = false;
+        Task::batch(vec![
+            focus(self.state.search_id.clone()),
+            self.run(keep_open, None)
+                .map_or_else(Task::none, Task::done),
+        ])
+    }
+
+    /// Runs the selected entry with the current argument. Both are taken
+    /// now, so keys pressed while it starts don't change what runs.
+    fn run(&self, keep_open: bool, action: Option<Action>) -> Option<PrismEvent> {
+        let selected = self.get_selected_entry()?;
+        Some(PrismEvent::Run {
+            keep_open,
+            action,
+            entry: selected.entry.clone(),
+            arguments: self.get_arguments(),
+        })
+    }
+
+    /// Replaces the result list with the output of a run, until the query
+    /// changes or Escape is pressed.
+    pub fn show_output(&mut self, title: String, text: String) {
+        self.state.output = Some(OutputView { title, text });
+    }
+
+    /// Opens the actions panel with `actions` to pick from, e.g. the
+    /// applications an action offers to open a file with.
+    pub fn show_actions(&mut self, title: String, actions: Vec<SecondaryAction>) {
+        self.state.actions = Some(ActionsMenu {
+            title: Some(title),
+            items: actions
+                .into_iter()
+                .map(|action| (action.title, Some(action.action)))
+                .collect(),
+            selected: 0,
+        });
+    }
+
+    /// Shows `toast` under the search bar, replacing any previous one.
+    pub fn show_toast(&mut self, toast: Toast) {
+        self.state.toast = Some(toast);
+    }
+
+    /// The arguments typed for the selected entry. Fallback commands take
+    /// the query instead.
+    pub fn get_arguments(&self) -> Vec<String> {
+        let Some(selected) = self.get_selected_entry() else {
+            return Vec::new();
+        };
+        if self.is_fallback(selected) {
+            return vec![self.state.query.trim().to_string()];
+        }
+        let count = selected.entry.entity.arguments().len();
+        self.state.arguments.iter().take(count).cloned().collect()
+    }
+
+    /// The first argument of the selected entry that wasn't typed yet.
+    fn missing_argument(&self) -> Option<usize> {
+        let selected = self.get_selected_entry()?;
+        if self.is_fallback(selected) {
+            return None;
+        }
+        (0..selected.entry.entity.arguments().len())
+            .find(|&i| self.state.arguments.get(i).is_none_or(String::is_empty))
+    }
+
+    fn is_fallback(&self, entry: &PrismEntry) -> bool {
+        self.state
+            .fallback_entries
+            .iter()
+            .any(|fallback| fallback.id == entry.id)
+    }
+
+    pub fn query(&self) -> &str {
+        &self.state.query
     }
 
     pub fn get_selected_entry(&self) -> Option<&PrismEntry> {
@@ -180,51 +638,159 @@ impl Prism {
     }
 
     pub fn subscription(&self) -> Subscription<PrismEvent> {
-        event::listen_with(|event, _status, _window| {
-            if let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) = event {
-                keybindings::map_key_to_action(key).map(|action| match action {
-                    keybindings::KeyAction::SelectPrevious => PrismEvent::SelectPrevious,
-                    keybindings::KeyAction::SelectNext => PrismEvent::SelectNext,
-                    keybindings::KeyAction::Submit => PrismEvent::Submit,
-                    keybindings::KeyAction::EscapePressed => PrismEvent::EscapePressed,
+        event::listen_with(|event, status, _window| match event {
+            iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                Some(PrismEvent::KeyPressed {
+                    key,
+                    modifiers,
+                    captured: status == event::Status::Captured,
                 })
-            } else {
-                None
             }
+            iced::Event::Window(window::Event::Rescaled(scale_factor)) => {
+                Some(PrismEvent::ScaleFactorChanged(scale_factor))
+            }
+            _ => None,
         })
     }
 
+    fn icon_for(&self, entry: &PrismEntry) -> IconHandle {
+        self.state
+            .icons
+            .get(&entry.id)
+            .cloned()
+            .unwrap_or_else(IconHandle::placeholder)
+    }
+
     pub fn view<'a>(&'a self) -> Element<'a, PrismEvent> {
         let selected_entry = self.get_selected_entry();
+        let arguments = match selected_entry {
+            Some(entry) if self.state.show_argument_input => entry
+                .entry
+                .entity
+                .arguments()
+                .iter()
+                .zip(&self.state.argument_ids)
+                .zip(&self.state.arguments)
+                .map(|((name, id), value)| (id.clone(), name.as_str(), value.as_str()))
+                .collect(),
+            _ => Vec::new(),
+        };
         let search_section = widgets::search_bar(
             self.state.search_id.clone(),
             &self.state.query,
             PrismEvent::SearchInput,
-            self.state.argument_id.clone(),
-            self.state.argument.as_deref(),
+            arguments,
             PrismEvent::ArgumentInput,
-            selected_entry.and_then(|e| e.entry.entity.icon()),
-            self.state.show_argument_input,
+            selected_entry.map(|e| self.icon_for(e)),
         );
 
-        let list_section = self.state.entries.iter().enumerate().map(|(i, entry)| {
-            container(widgets::list_item(
-                &entry.entry,
-                i == self.state.selected_index,
-                PrismEvent::EntrySelected(i),
-            ))
-            .id(entry.id.clone())
-            .into()
-        });
+        // Only rows near the viewport are built; spacers stand in for the
+        // rest so the scrollable keeps its full height.
+        let range = visible_range(&self.state, RENDER_BUFFER_ROWS);
+        let layout = &self.state.layout;
+        let first_visible = first_visible_entry(&self.state);
 
-        container(column![
-            search_section,
-            widgets::divider(),
-            scrollable(column(list_section))
-                .id(self.state.scroll_id.clone())
-                .on_scroll(PrismEvent::Scrolled)
-                .height(Length::Fill)
-        ])
+        let rows = self.state.rows[range.clone()]
+            .iter()
+            .map(|list_row| match list_row {
+                ListRow::Header(section) => {
+                    widgets::section_header(section.to_string(), SECTION_HEADER_HEIGHT)
+                }
+                ListRow::Entry(i) => {
+                    let i = *i;
+                    let entry = &self.state.entries[i];
+                    let shortcut = i
+                        .checked_sub(first_visible)
+                        .filter(|offset| *offset < QUICK_SELECT_ROWS)
+                        .and_then(|offset| self.keymap.label_for(KeyAction::Launch(offset + 1)));
+
+                    container(widgets::list_item(
+                        &entry.entry,
+                        self.icon_for(entry),
+                        shortcut,
+                        i == self.state.selected_index,
+                        PrismEvent::EntrySelected(i),
+                    ))
+                    .id(entry.id.clone())
+                    .into()
+                }
+                ListRow::Cells(cells) => {
+                    let columns = self.state.columns.unwrap_or(cells.len());
+                    let items = cells.clone().map(|i| {
+                        let entry = &self.state.entries[i];
+                        widgets::grid_cell(
+                            &entry.entry,
+                            self.icon_for(entry),
+                            GRID_ROW_HEIGHT,
+                            i == self.state.selected_index,
+                            PrismEvent::EntrySelected(i),
+                        )
+                    });
+                    // Pad a short last row so its cells line up with the others.
+                    let padding =
+                        (cells.len()..columns).map(|_| Space::new().width(Length::Fill).into());
+                    row(items.chain(padding)).into()
+                }
+            });
+
+        let list_section = column![Space::new().height(layout.top(range.start))]
+            .extend(rows)
+            .push(Space::new().height(layout.total_height() - layout.top(range.end)));
+
+        let list = scrollable(list_section)
+            .id(self.state.scroll_id.clone())
+            .on_scroll(PrismEvent::Scrolled)
+            .height(Length::Fill)
+            .width(Length::FillPortion(3));
+
+        let body: Element<'a, PrismEvent> = match (&self.state.detail, selected_entry) {
+            _ if let Some(output) = &self.state.output => {
+                widgets::output_view(&output.title, &output.text)
+            }
+            (Some(detail), Some(entry)) => row![
+                list,
+                widgets::vertical_divider(),
+                widgets::detail_pane(
+                    &entry.entry,
+                    self.icon_for(entry),
+                    detail.markdown.as_ref().map(markdown::Content::items),
+                    &detail.metadata,
+                    PrismEvent::LinkClicked,
+                )
+                .width(Length::FillPortion(2)),
+            ]
+            .into(),
+            _ => list.into(),
+        };
+
+        let body = match &self.state.actions {
+            Some(menu) => stack![
+                body,
+                container(widgets::actions_panel(
+                    menu.title.as_deref(),
+                    menu.items.iter().map(|(title, _)| title.as_str()),
+                    menu.selected,
+                    PrismEvent::ActionChosen,
+                ))
+                .align_right(Length::Fill)
+                .align_bottom(Length::Fill)
+                .padding(spacing::SPACE_S),
+            ]
+            .into(),
+            None => body,
+        };
+
+        let toast = self
+            .state
+            .toast
+            .as_ref()
+            .map(|toast| widgets::toast(toast, PrismEvent::DismissToast));
+
+        container(
+            column![search_section, widgets::divider()]
+                .push(toast)
+                .push(body),
+        )
         .width(Length::Fill)
         .height(Length::Fill)
         .padding(spacing::SPACE_S)
@@ -245,89 +811,307 @@ impl Prism {
 pub enum PrismEvent {
     Initialized,
     SearchInput(String),
-    ArgumentInput(String),
+    /// The value typed for the argument at an index.
+    ArgumentInput(usize, String),
+    KeyPressed {
+        key: keyboard::Key,
+        modifiers: keyboard::Modifiers,
+        /// Whether a widget, like the focused search input, handled the key.
+        captured: bool,
+    },
     SelectNext,
     SelectPrevious,
+    SelectLeft,
+    SelectRight,
+    PageUp,
+    PageDown,
+    SelectFirst,
+    SelectLast,
     EntrySelected(usize),
     Submit,
+    AlternateSubmit,
     EntriesLoaded(Vec<ListEntry>),
+    ProviderResults {
+        query: String,
+        entries: Vec<ListEntry>,
+    },
 
     Scrolled(scrollable::Viewport),
-    ItemMeasured { id: Id, rect: Rectangle },
-    Run,
+    /// The bounds of the rows measured together, by entry id.
+    ItemsMeasured(Vec<(Id, Rectangle)>),
+    ToggleDetail,
+    LinkClicked(markdown::Uri),
+    DismissToast,
+    ScaleFactorChanged(f32),
+    IconsLoaded(IconSize, Vec<(Id, IconHandle)>),
+    ToggleActions,
+    ActionChosen(usize),
+    /// Runs `entry`, or `action` on its behalf.
+    Run {
+        keep_open: bool,
+        action: Option<Action>,
+        entry: ListEntry,
+        arguments: Vec<String>,
+    },
     EscapePressed,
     ExitApp,
 }
 
-fn measure_all_visible_items(state: &PrismState) -> Task<PrismEvent> {
-    let tasks: Vec<Task<PrismEvent>> = state
-        .entries
+/// The actions panel items for `entity`: running it, then its secondary
+/// actions.
+fn entity_actions(entity: &Entity) -> Vec<(String, Option<Action>)> {
+    let primary = match entity {
+        Entity::Application(_) => "Open",
+        Entity::Command(cmd) => match cmd.action {
+            Action::Open { .. } => "Open",
+            _ => "Run",
+        },
+    };
+    std::iter::once((primary.to_string(), None))
+        .chain(
+            entity
+                .secondary_actions()
+                .iter()
+                .map(|a| (a.title.clone(), Some(a.action.clone()))),
+        )
+        .collect()
+}
+
+/// Asks the plugin providers for results on a thread of their own, since
+/// they read files and decode images.
+fn search_providers(plugins: Arc<Plugins>, query: String) -> Task<PrismEvent> {
+    Task::perform(
+        unblock(move || {
+            let entries = plugins
+                .search(&query)
+                .into_iter()
+                .map(ListEntry::from)
+                .collect();
+            (query, entries)
+        }),
+        |(query, entries)| PrismEvent::ProviderResults { query, entries },
+    )
+}
+
+/// Measures the visible list rows in one batch; grid rows have a fixed
+/// height.
+fn measure_visible_items(state: &PrismState) -> Task<PrismEvent> {
+    let tasks: Vec<Task<(Id, Rectangle)>> = state.rows[visible_range(state, RENDER_BUFFER_ROWS)]
         .iter()
-        .map(|entry| measure_item(entry.id.clone()))
+        .filter_map(|row| match row {
+            ListRow::Entry(i) => Some(measure_item(state.entries[*i].id.clone())),
+            _ => None,
+        })
         .collect();
 
-    Task::batch(tasks)
+    if tasks.is_empty() {
+        return Task::none();
+    }
+    Task::batch(tasks).collect().map(PrismEvent::ItemsMeasured)
 }
 
-fn measure_item(id: Id) -> Task<PrismEvent> {
+fn measure_item(id: Id) -> Task<(Id, Rectangle)> {
     let selector = selector::id(id.clone()).find();
     let operation = operation::map(selector, move |v| {
-        v.map(|widget| PrismEvent::ItemMeasured {
-            id: id.clone(),
-            rect: widget.bounds(),
-        })
-        .unwrap_or(PrismEvent::ItemMeasured {
-            id: id.clone(),
-            rect: Rectangle::with_size(Size::new(0.0, 0.0)),
-        })
+        let rect = v.map_or(Rectangle::with_size(Size::new(0.0, 0.0)), |widget| {
+            widget.bounds()
+        });
+        (id.clone(), rect)
     });
     operate(operation)
 }
 
-fn smart_scroll(state: &PrismState) -> Task<PrismEvent> {
-    let mut y_position = 0.0;
-    let mut target_height = state.default_row_height;
+/// Rows built on either side of the viewport so fast scrolling never
+/// reveals empty space.
+const RENDER_BUFFER_ROWS: usize = 8;
+/// Rows loaded ahead of the viewport so icons are ready before they scroll in.
+const ICON_PRELOAD_ROWS: usize = 5;
+/// Rows that get a numbered quick-select shortcut.
+const QUICK_SELECT_ROWS: usize = 9;
+/// Section headers are drawn at a fixed height instead of being measured.
+const SECTION_HEADER_HEIGHT: f32 = 28.0;
+/// Grid rows are drawn at a fixed height too.
+const GRID_ROW_HEIGHT: f32 = 84.0;
+/// Used before the scrollable has reported its real size.
+const FALLBACK_VIEWPORT_HEIGHT: f32 = 500.0;
 
-    for i in 0..state.selected_index {
-        if let Some(entry) = state.entries.get(i) {
-            let h = *state
+fn rebuild_layout(state: &mut PrismState) {
+    state.layout = RowLayout::new(state.rows.iter().map(|row| {
+        match row {
+            ListRow::Header(_) => SECTION_HEADER_HEIGHT,
+            ListRow::Cells(_) => GRID_ROW_HEIGHT,
+            ListRow::Entry(i) => *state
                 .height_cache
-                .get(&entry.id)
-                .unwrap_or(&state.default_row_height);
-            y_position += h;
+                .get(&state.entries[*i].id)
+                .unwrap_or(&state.default_row_height),
         }
+    }));
+}
+
+fn visible_range(state: &PrismState, buffer: usize) -> Range<usize> {
+    let viewport_height = if state.viewport_height > 0.0 {
+        state.viewport_height
+    } else {
+        FALLBACK_VIEWPORT_HEIGHT
+    };
+
+    state
+        .layout
+        .visible_range(state.current_scroll_offset, viewport_height, buffer)
+}
+
+/// Entries among the rows returned by [`visible_range`], skipping headers.
+fn visible_entries(state: &PrismState, buffer: usize) -> impl Iterator<Item = &PrismEntry> {
+    state.rows[visible_range(state, buffer)]
+        .iter()
+        .flat_map(ListRow::entries)
+        .map(|i| &state.entries[i])
+}
+
+fn load_visible_icons(state: &mut PrismState) -> Task<PrismEvent> {
+    let wanted: Vec<(Id, ListEntry)> = visible_entries(state, ICON_PRELOAD_ROWS)
+        .filter(|e| !state.icons.contains_key(&e.id) && !state.pending_icons.contains(&e.id))
+        .map(|e| (e.id.clone(), e.entry.clone()))
+        .collect();
+
+    if wanted.is_empty() {
+        return Task::none();
     }
 
-    if let Some(entry) = state.entries.get(state.selected_index) {
-        target_height = *state
-            .height_cache
-            .get(&entry.id)
-            .unwrap_or(&state.default_row_height);
+    state
+        .pending_icons
+        .extend(wanted.iter().map(|(id, _)| id.clone()));
+
+    let icon_size = state.icon_size;
+    Task::perform(
+        unblock(move || {
+            wanted
+                .into_iter()
+                .map(|(id, entry)| (id, entry.load_icon(icon_size)))
+                .collect()
+        }),
+        move |loaded| PrismEvent::IconsLoaded(icon_size, loaded),
+    )
+}
+
+/// The first entry whose row is fully inside the viewport.
+fn first_visible_entry(state: &PrismState) -> usize {
+    state.rows[visible_range(state, 0)]
+        .iter()
+        .zip(visible_range(state, 0))
+        .find_map(|(row, index)| {
+            let entries = row.entries();
+            (!entries.is_empty() && state.layout.top(index) >= state.current_scroll_offset)
+                .then_some(entries.start)
+        })
+        .unwrap_or(state.entries.len().saturating_sub(1))
+}
+
+/// Rows moved by PageUp/PageDown: one viewport's worth.
+fn page_size(state: &PrismState) -> usize {
+    visible_range(state, 0).len().saturating_sub(1).max(1)
+}
+
+/// Whether the selected entry is a grid cell.
+fn in_grid(state: &PrismState) -> bool {
+    state
+        .entry_rows
+        .get(state.selected_index)
+        .is_some_and(|row| matches!(state.rows[*row], ListRow::Cells(_)))
+}
+
+/// Moves the selection `rows` rows down or up, keeping its column in a
+/// grid, and stops at the first or last row.
+fn move_vertically(state: &mut PrismState, down: bool, rows: usize) {
+    for _ in 0..rows {
+        let Some(&row) = state.entry_rows.get(state.selected_index) else {
+            return;
+        };
+        let column = state.selected_index - state.rows[row].entries().start;
+        let next = if down {
+            state.rows[row + 1..]
+                .iter()
+                .find(|row| !row.entries().is_empty())
+        } else {
+            state.rows[..row]
+                .iter()
+                .rev()
+                .find(|row| !row.entries().is_empty())
+        };
+        let Some(entries) = next.map(ListRow::entries) else {
+            return;
+        };
+        state.selected_index = (entries.start + column).min(entries.end - 1);
     }
+}
 
-    let item_top = y_position;
-    let item_bottom = item_top + target_height;
+fn smart_scroll(state: &mut PrismState) -> Task<PrismEvent> {
+    let Some(&row) = state.entry_rows.get(state.selected_index) else {
+        return Task::none();
+    };
+    // Bring the section header into view along with its first entry.
+    let first_in_section = row > 0 && matches!(state.rows[row - 1], ListRow::Header(_));
+    let item_top = state
+        .layout
+        .top(if first_in_section { row - 1 } else { row });
+    let item_bottom = state.layout.bottom(row);
 
     let view_top = state.current_scroll_offset;
     let view_bottom = view_top + state.viewport_height;
 
-    if item_top < view_top {
-        return scroll_to(
-            state.scroll_id.clone(),
-            scrollable::AbsoluteOffset {
-                x: 0.0,
-                y: item_top,
-            },
-        );
+    let target = if item_top < view_top {
+        item_top
     } else if item_bottom > view_bottom && state.viewport_height > 0.0 {
-        return scroll_to(
+        item_bottom - state.viewport_height
+    } else {
+        return Task::none();
+    };
+
+    // Track the new offset right away so the next view renders the rows
+    // being scrolled to.
+    state.current_scroll_offset = target;
+    Task::batch(vec![
+        scroll_to(
             state.scroll_id.clone(),
-            scrollable::AbsoluteOffset {
-                x: 0.0,
-                y: item_bottom - state.viewport_height,
-            },
-        );
-    }
+            scrollable::AbsoluteOffset { x: 0.0, y: target },
+        ),
+        load_visible_icons(state),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use core::{Config, Plugins, SectionsConfig};
 
-    Task::none()
+    use super::{Prism, PrismEvent};
+    use crate::prism::keybindings::Keymap;
+
+    #[test]
+    fn run_takes_the_entry_and_argument_when_requested() {
+        let plugins = Arc::new(Plugins::new(&Config::default()));
+        let (mut prism, _) = Prism::new(Keymap::default(), SectionsConfig::default(), plugins);
+        // The default fallbacks: web search, file search and shell.
+        prism.state.entries = prism.state.fallback_entries.clone();
+        let second = prism.state.entries[1].entry.name().to_string();
+        prism.state.selected_index = 1;
+        prism.state.query = " argument ".to_string();
+
+        let Some(PrismEvent::Run {
+            entry, arguments, ..
+        }) = prism.run(false, None)
+        else {
+            panic!("Nothing to run");
+        };
+
+        // Typing on while it starts doesn't change what runs.
+        prism.state.selected_index = 0;
+        prism.state.query.clear();
+        assert_eq!(entry.name(), second);
+        assert_eq!(arguments, ["argument"]);
+
+        prism.state.entries.clear();
+        assert!(prism.run(false, None).is_none());
+    }
 }
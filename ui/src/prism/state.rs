@@ -1,7 +1,10 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 
-use crate::prism::items::ListEntry;
-use iced::widget::Id;
+use crate::prism::items::{IconHandle, ListEntry};
+use crate::prism::layout::RowLayout;
+use core::{Action, IconSize, Section};
+use iced::widget::{Id, markdown};
 
 #[derive(Clone, Debug)]
 pub struct PrismEntry {
@@ -18,19 +21,114 @@ impl From<ListEntry> for PrismEntry {
     }
 }
 
+/// A row of the result list: a section header, an index into
+/// [`PrismState::entries`], or a range of them shown as grid cells.
+#[derive(Clone, Debug)]
+pub enum ListRow {
+    Header(Section),
+    Entry(usize),
+    Cells(Range<usize>),
+}
+
+impl ListRow {
+    /// The indices of the entries in this row.
+    pub fn entries(&self) -> Range<usize> {
+        match self {
+            ListRow::Header(_) => 0..0,
+            ListRow::Entry(i) => *i..*i + 1,
+            ListRow::Cells(cells) => cells.clone(),
+        }
+    }
+}
+
+/// The detail pane content for the entry it was built for.
+pub struct DetailView {
+    pub entry_id: Id,
+    pub markdown: Option<markdown::Content>,
+    pub metadata: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToastKind {
+    Success,
+    Error,
+}
+
+/// A message shown under the search bar until the user dismisses it or
+/// starts a new search.
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub title: String,
+    /// One line per error in the chain, outermost first.
+    pub details: Vec<String>,
+}
+
+impl Toast {
+    pub fn success(title: impl Into<String>) -> Self {
+        Self {
+            kind: ToastKind::Success,
+            title: title.into(),
+            details: Vec::new(),
+        }
+    }
+
+    pub fn error(title: impl Into<String>, error: &anyhow::Error) -> Self {
+        Self {
+            kind: ToastKind::Error,
+            title: title.into(),
+            details: error.chain().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+/// The actions panel of the selected entry, or a list of choices an
+/// action asked for. Items without an action run the entry itself.
+pub struct ActionsMenu {
+    pub title: Option<String>,
+    pub items: Vec<(String, Option<Action>)>,
+    pub selected: usize,
+}
+
+/// Text produced by the last run, shown in place of the results.
+pub struct OutputView {
+    pub title: String,
+    pub text: String,
+}
+
 pub struct PrismState {
     pub query: String,
-    pub argument: Option<String>,
+    /// The values typed for the arguments of the selected entry.
+    pub arguments: Vec<String>,
     pub all_entries: Vec<PrismEntry>,
+    /// Results from the plugin providers for the current query.
+    pub provider_entries: Vec<PrismEntry>,
+    /// Commands offered for the query when little else matches it.
+    pub fallback_entries: Vec<PrismEntry>,
     pub entries: Vec<PrismEntry>,
+    pub rows: Vec<ListRow>,
+    /// Row index of each entry in `entries`.
+    pub entry_rows: Vec<usize>,
+    /// Cells per grid row, when the results are shown as a grid.
+    pub columns: Option<usize>,
     pub selected_index: usize,
     pub search_id: Id,
-    pub argument_id: Id,
+    /// The argument inputs, grown to the most arguments asked for so far.
+    pub argument_ids: Vec<Id>,
     pub scroll_id: Id,
     pub viewport_height: f32,
     pub current_scroll_offset: f32,
     pub height_cache: HashMap<Id, f32>,
     pub default_row_height: f32,
+    pub layout: RowLayout,
     pub show_argument_input: bool,
     pub is_argument_input_active: bool,
+    pub icon_size: IconSize,
+    pub icons: HashMap<Id, IconHandle>,
+    pub pending_icons: HashSet<Id>,
+    pub show_detail: bool,
+    pub detail: Option<DetailView>,
+    pub toast: Option<Toast>,
+    pub output: Option<OutputView>,
+    pub actions: Option<ActionsMenu>,
 }
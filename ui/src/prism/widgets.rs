@@ -1,15 +1,20 @@
 use crate::design_system::icons;
-use core::Image;
 use iced::{
     Alignment, Background, Color, Element, Length, gradient,
-    widget::{Id, Row, container, image, svg, text, text_input},
+    theme::Palette,
+    widget::{
+        Column, Container, Id, Row, container, image, markdown, scrollable, svg, text, text_input,
+    },
     widget::{button, column, row, space::horizontal},
 };
 
 use crate::design_system::typo::Typography;
 use crate::{
     design_system::{colors, spacing, typo},
-    prism::items::{IconHandle, ListEntry},
+    prism::{
+        items::{IconHandle, ListEntry},
+        state::{Toast, ToastKind},
+    },
 };
 
 /// A specialized search input with transparent styling
@@ -17,11 +22,9 @@ pub fn search_bar<'a, Message>(
     id: Id,
     query: &'a str,
     on_input: impl Fn(String) -> Message + 'a,
-    argument_id: Id,
-    argument: Option<&'a str>,
-    on_argument_input: impl Fn(String) -> Message + 'a,
-    icon: Option<Image>,
-    show_argument_input: bool,
+    arguments: Vec<(Id, &'a str, &'a str)>,
+    on_argument_input: impl Fn(usize, String) -> Message + Copy + 'a,
+    icon: Option<IconHandle>,
 ) -> Element<'a, Message>
 where
     Message: Clone + 'a,
@@ -47,15 +50,16 @@ where
 
     let mut row = Row::new().push(search_input);
 
-    if show_argument_input {
-        if let Some(icon) = icon {
-            let icon_handle: IconHandle = icon.into();
-            row = row.push(render_icon(icon_handle, icons::MD));
-        }
-
-        let argument_input = text_input("Argument...", argument.unwrap_or_default())
-            .on_input(on_argument_input)
-            .id(argument_id)
+    // The arguments asked for, as their input's id, name and value.
+    if !arguments.is_empty()
+        && let Some(icon) = icon
+    {
+        row = row.push(render_icon(icon, icons::MD));
+    }
+    for (index, (id, name, value)) in arguments.into_iter().enumerate() {
+        let argument_input = text_input(name, value)
+            .on_input(move |value| on_argument_input(index, value))
+            .id(id)
             .size(typo::TITLE_L.0)
             .font(typo::TITLE_L.2)
             .padding(15)
@@ -99,9 +103,252 @@ pub fn divider<'a, Message: 'a>() -> Element<'a, Message> {
         .into()
 }
 
-/// A clickable list entry with selection state styling
+/// A vertical gradient divider line
+pub fn vertical_divider<'a, Message: 'a>() -> Element<'a, Message> {
+    container("")
+        .width(1.0)
+        .height(Length::Fill)
+        .style(|_theme| {
+            let fade_gradient = gradient::Linear::new(0.0)
+                .add_stop(0.0, Color::TRANSPARENT)
+                .add_stop(0.5, colors::ON_SURFACE.scale_alpha(0.5))
+                .add_stop(1.0, Color::TRANSPARENT)
+                .into();
+
+            container::Style {
+                background: Some(Background::Gradient(fade_gradient)),
+                ..container::Style::default()
+            }
+        })
+        .into()
+}
+
+/// The detail pane for the selected entry: header, markdown and metadata rows
+pub fn detail_pane<'a, Message>(
+    entry: &'a ListEntry,
+    icon: IconHandle,
+    markdown_items: Option<&'a [markdown::Item]>,
+    metadata: &'a [(String, String)],
+    on_link: impl Fn(markdown::Uri) -> Message + 'a,
+) -> Container<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let header = row![
+        render_icon(icon, icons::XL),
+        text(entry.name())
+            .typography(typo::TITLE_L)
+            .color(colors::ON_SURFACE),
+    ]
+    .spacing(spacing::SPACE_M)
+    .align_y(Alignment::Center);
+
+    let mut content = Column::new().push(header).spacing(spacing::SPACE_M);
+
+    if let Some(items) = markdown_items {
+        let style = markdown::Style::from_palette(Palette {
+            text: colors::ON_SURFACE,
+            primary: colors::TERTIARY,
+            ..Palette::DARK
+        });
+        content = content.push(
+            markdown::view(
+                items,
+                markdown::Settings::with_text_size(typo::BODY_M.0, style),
+            )
+            .map(on_link),
+        );
+    }
+
+    if !metadata.is_empty() {
+        content = content.push(divider());
+    }
+
+    for (label, value) in metadata {
+        content = content.push(
+            column![
+                text(label.as_str())
+                    .typography(typo::LABEL_M)
+                    .color(colors::ON_SURFACE_VARIANT),
+                text(value.as_str())
+                    .typography(typo::BODY_S)
+                    .color(colors::ON_SURFACE),
+            ]
+            .spacing(spacing::SPACE_XXS),
+        );
+    }
+
+    container(scrollable(content).height(Length::Fill))
+        .padding(spacing::SPACE_M)
+        .height(Length::Fill)
+}
+
+/// A dismissible banner for the outcome of the last action, listing the
+/// error chain for failures
+pub fn toast<'a, Message>(toast: &'a Toast, on_dismiss: Message) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let (accent, container_color) = match toast.kind {
+        ToastKind::Success => (colors::TERTIARY, colors::TERTIARY_CONTAINER),
+        ToastKind::Error => (colors::ERROR, colors::ERROR_CONTAINER),
+    };
+
+    let mut content = Column::new()
+        .push(
+            text(toast.title.as_str())
+                .typography(typo::TITLE_S)
+                .color(colors::ON_SURFACE),
+        )
+        .spacing(spacing::SPACE_XXS);
+    for line in &toast.details {
+        content = content.push(
+            text(line.as_str())
+                .typography(typo::BODY_S)
+                .color(colors::ON_SURFACE_VARIANT),
+        );
+    }
+
+    let dismiss = button(
+        text("✕")
+            .typography(typo::LABEL_M)
+            .color(colors::ON_SURFACE_VARIANT),
+    )
+    .on_press(on_dismiss)
+    .style(button::text);
+
+    container(
+        row![content, horizontal(), dismiss]
+            .spacing(spacing::SPACE_S)
+            .align_y(Alignment::Start),
+    )
+    .width(Length::Fill)
+    .padding(spacing::SPACE_S)
+    .style(move |_theme| container::Style {
+        background: Some(container_color.scale_alpha(0.6).into()),
+        border: iced::Border {
+            color: accent,
+            width: 1.0,
+            radius: 8.0.into(),
+        },
+        ..container::Style::default()
+    })
+    .into()
+}
+
+/// The captured output of a shell run, in a scrollable monospace block
+pub fn output_view<'a, Message: 'a>(title: &'a str, output: &'a str) -> Element<'a, Message> {
+    let output = if output.is_empty() {
+        "(no output)"
+    } else {
+        output
+    };
+
+    container(
+        column![
+            text(title)
+                .typography(typo::TITLE_S)
+                .color(colors::ON_SURFACE),
+            scrollable(
+                text(output)
+                    .typography(typo::CODE_S)
+                    .color(colors::ON_SURFACE_VARIANT),
+            )
+            .height(Length::Fill),
+        ]
+        .spacing(spacing::SPACE_S),
+    )
+    .padding(spacing::SPACE_M)
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .into()
+}
+
+/// The actions available for the selected entry, as a small floating menu
+/// with an optional heading
+pub fn actions_panel<'a, Message>(
+    title: Option<&'a str>,
+    items: impl Iterator<Item = &'a str>,
+    selected: usize,
+    on_choose: impl Fn(usize) -> Message,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let heading = title.map(|title| {
+        container(
+            text(title)
+                .typography(typo::LABEL_M)
+                .color(colors::ON_SURFACE_VARIANT),
+        )
+        .padding([spacing::SPACE_XXS, spacing::SPACE_S])
+    });
+
+    let items = items.enumerate().map(|(i, title)| {
+        let is_selected = i == selected;
+        button(
+            text(title)
+                .typography(typo::BODY_M)
+                .color(colors::ON_SURFACE),
+        )
+        .on_press(on_choose(i))
+        .width(Length::Fill)
+        .padding([spacing::SPACE_XS, spacing::SPACE_S])
+        .style(move |_theme, status| {
+            let highlighted = is_selected || status == button::Status::Hovered;
+            button::Style {
+                background: highlighted.then(|| colors::ON_SURFACE.scale_alpha(0.1).into()),
+                text_color: colors::ON_SURFACE,
+                border: iced::Border {
+                    radius: 6.0.into(),
+                    ..iced::Border::default()
+                },
+                ..Default::default()
+            }
+        })
+        .into()
+    });
+
+    container(
+        Column::new()
+            .push(heading)
+            .extend(items)
+            .spacing(spacing::SPACE_XXS),
+    )
+    .width(260)
+    .padding(spacing::SPACE_XS)
+    .style(|_theme| container::Style {
+        background: Some(colors::SURFACE_CONTAINER_HIGH.into()),
+        border: iced::Border {
+            color: colors::OUTLINE_VARIANT,
+            width: 1.0,
+            radius: 10.0.into(),
+        },
+        ..container::Style::default()
+    })
+    .into()
+}
+
+/// A section title above a group of list entries, drawn at a fixed height
+/// so the list layout doesn't need to measure it
+pub fn section_header<'a, Message: 'a>(title: String, height: f32) -> Element<'a, Message> {
+    container(
+        text(title)
+            .typography(typo::LABEL_M)
+            .color(colors::ON_SURFACE_VARIANT),
+    )
+    .height(height)
+    .padding([spacing::SPACE_NONE, spacing::SPACE_S])
+    .align_y(Alignment::End)
+    .into()
+}
+
+/// A clickable list entry with selection state styling and an optional
+/// quick-select shortcut hint
 pub fn list_item<'a, Message>(
     entry: &'a ListEntry,
+    icon: IconHandle,
+    shortcut: Option<&'a str>,
     is_selected: bool,
     on_press: Message,
 ) -> Element<'a, Message>
@@ -110,8 +357,8 @@ where
 {
     let kind: &str = entry.kind();
 
-    let content = row![
-        render_icon(entry.icon(), icons::LG),
+    let mut content = row![
+        render_icon(icon, icons::LG),
         column![
             text(entry.name())
                 .typography(typo::TITLE_M)
@@ -129,30 +376,94 @@ where
     .spacing(spacing::SPACE_M)
     .align_y(Alignment::Center);
 
+    if let Some(shortcut) = shortcut {
+        content = content.push(shortcut_hint(shortcut));
+    }
+
     button(content)
         .on_press(on_press)
         .width(Length::Fill)
         .padding(spacing::SPACE_S)
-        .style(move |_theme, status| {
-            let is_hovered = status == button::Status::Hovered;
+        .style(move |_theme, status| item_style(is_selected, status))
+        .into()
+}
 
-            let bg_color = if is_selected || is_hovered {
-                colors::ON_SURFACE.scale_alpha(0.1)
-            } else {
-                Color::TRANSPARENT
-            };
+/// A clickable grid cell showing an entry's symbol, or its icon, above its
+/// name, styled like a list entry
+pub fn grid_cell<'a, Message>(
+    entry: &'a ListEntry,
+    icon: IconHandle,
+    height: f32,
+    is_selected: bool,
+    on_press: Message,
+) -> Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let symbol: Element<'a, Message> = match entry.entity.symbol() {
+        Some(symbol) => text(symbol).size(icons::LG).into(),
+        None => render_icon(icon, icons::LG),
+    };
+    let name = container(
+        text(entry.name())
+            .typography(typo::LABEL_S)
+            .color(colors::ON_SURFACE_VARIANT)
+            .wrapping(text::Wrapping::None),
+    )
+    .clip(true);
 
-            button::Style {
-                background: Some(bg_color.into()),
-                text_color: colors::ON_SURFACE,
-                border: iced::Border {
-                    radius: 8.0.into(),
-                    ..iced::Border::default()
-                },
-                ..Default::default()
-            }
-        })
-        .into()
+    button(
+        column![symbol, name]
+            .spacing(spacing::SPACE_XS)
+            .align_x(Alignment::Center),
+    )
+    .on_press(on_press)
+    .width(Length::Fill)
+    .height(height)
+    .padding(spacing::SPACE_S)
+    .style(move |_theme, status| item_style(is_selected, status))
+    .into()
+}
+
+/// The background of list entries and grid cells, shown when selected or
+/// hovered
+fn item_style(is_selected: bool, status: button::Status) -> button::Style {
+    let is_hovered = status == button::Status::Hovered;
+
+    let bg_color = if is_selected || is_hovered {
+        colors::ON_SURFACE.scale_alpha(0.1)
+    } else {
+        Color::TRANSPARENT
+    };
+
+    button::Style {
+        background: Some(bg_color.into()),
+        text_color: colors::ON_SURFACE,
+        border: iced::Border {
+            radius: 8.0.into(),
+            ..iced::Border::default()
+        },
+        ..Default::default()
+    }
+}
+
+/// A small keycap-style label showing a keyboard shortcut
+pub fn shortcut_hint<'a, Message: 'a>(label: &'a str) -> Element<'a, Message> {
+    container(
+        text(label)
+            .typography(typo::LABEL_M)
+            .color(colors::ON_SURFACE_VARIANT),
+    )
+    .padding([spacing::SPACE_XXS, spacing::SPACE_XS])
+    .style(|_theme| container::Style {
+        background: Some(colors::ON_SURFACE.scale_alpha(0.08).into()),
+        border: iced::Border {
+            radius: 4.0.into(),
+            ..iced::Border::default()
+        },
+        ..container::Style::default()
+    })
+    .into()
 }
 
 pub fn render_icon<'a, Message>(icon_handler: IconHandle, size: f32) -> Element<'a, Message> {